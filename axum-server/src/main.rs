@@ -1,18 +1,41 @@
 use axum::{
-    async_trait, body::Bytes, body::Full, extract::FromRequest,
-    extract::OriginalUri, extract::RawBody, extract::RequestParts,
-    handler::get, http::header, http::header::HeaderName,
-    http::header::HeaderValue, http::Response, http::StatusCode,
-    response::IntoResponse, Router,
+    async_trait, body::Bytes, body::Full, extract::Extension,
+    extract::FromRequest, extract::OriginalUri, extract::RawBody,
+    extract::RequestParts, handler::get, http::header,
+    http::header::HeaderName, http::header::HeaderValue, http::HeaderMap,
+    http::Request, http::Response, http::StatusCode, response::IntoResponse,
+    AddExtensionLayer, Router,
 };
 use std::convert::Infallible;
 use std::env;
+use std::fmt::Display;
+use std::fs;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tower_http::trace::TraceLayer;
 
+use libs::archive;
+use libs::build_payload;
+use libs::multipart;
+use libs::parse_logo_size;
+use libs::parse_margin;
+use libs::parse_module_size;
+use libs::parse_rows;
+use libs::Batch;
+use libs::BatchError;
+use libs::BatchOutput;
 use libs::EcLevel;
+use libs::Fields;
 use libs::Format;
 use libs::Generator;
+use libs::Halftone;
+use libs::Logo;
+use libs::Presets;
+use libs::Row;
+use libs::Sheet;
+use libs::Spacing;
+use libs::Template;
+use libs::Warning;
 use libs::HELP;
 use libs::HTML_HELP;
 use libs::TEMPLATE;
@@ -26,11 +49,22 @@ async fn main() {
 
     tracing_subscriber::fmt::init();
 
+    // Styles for the X-QR-Style header, the built-in ones unless a file is
+    // given.
+    let presets: Presets = match env::var("PRESETS_FILE") {
+        Ok(path) => fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("{}: {}", path, e))
+            .parse()
+            .unwrap_or_else(|e| panic!("{}: {}", path, e)),
+        Err(_) => Presets::default(),
+    };
+
     let app = Router::new()
         .nest(
             "/",
             get(get_handler).post(post_handler).options(options_handler),
         )
+        .layer(AddExtensionLayer::new(Arc::new(presets)))
         .layer(TraceLayer::new_for_http())
         .check_infallible();
 
@@ -60,6 +94,56 @@ fn get_first_header_value<B>(
         .map(String::from)
 }
 
+fn get_header_value<B>(
+    req: &RequestParts<B>,
+    key: header::HeaderName,
+) -> Option<String> {
+    req.headers()
+        .and_then(|h| h.get(key))
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.trim().to_string())
+}
+
+fn bad_request(key: &str, err: impl Display) -> (StatusCode, String) {
+    (StatusCode::BAD_REQUEST, format!("{}: {}", key, err))
+}
+
+/// Fills in the headers the request doesn't set from the style it names.
+fn apply_style<B>(
+    req: &mut RequestParts<B>,
+) -> Result<(), (StatusCode, String)> {
+    let name = match get_first_header_value(
+        req,
+        HeaderName::from_static("x-qr-style"),
+    ) {
+        Some(name) => name,
+        None => return Ok(()),
+    };
+
+    let presets = req
+        .extensions()
+        .and_then(|e| e.get::<Arc<Presets>>())
+        .cloned()
+        .unwrap_or_default();
+
+    let preset = presets
+        .find(&name)
+        .map_err(|e| bad_request("x-qr-style", e))?;
+
+    if let Some(headers) = req.headers_mut() {
+        for (key, value) in preset.unset(|key| headers.contains_key(key)) {
+            let key = HeaderName::from_bytes(key.as_bytes())
+                .map_err(|e| bad_request("x-qr-style", e))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| bad_request("x-qr-style", e))?;
+
+            headers.insert(key, value);
+        }
+    }
+
+    Ok(())
+}
+
 struct QRGenerator(Generator);
 
 #[async_trait]
@@ -67,11 +151,13 @@ impl<B> FromRequest<B> for QRGenerator
 where
     B: Send, // required by `async_trait`
 {
-    type Rejection = StatusCode;
+    type Rejection = (StatusCode, String);
 
     async fn from_request(
         req: &mut RequestParts<B>,
     ) -> Result<Self, Self::Rejection> {
+        apply_style(req)?;
+
         let mut gen = Generator::default();
 
         if let Some(val) = get_first_header_value(req, header::ACCEPT) {
@@ -81,61 +167,86 @@ where
         if let Some(val) =
             get_first_header_value(req, HeaderName::from_static("x-qr-width"))
         {
-            gen.width =
-                val.parse().map(Some).map_err(|_| StatusCode::BAD_REQUEST)?;
+            gen.width = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-width", e))?;
         }
 
         if let Some(val) =
             get_first_header_value(req, HeaderName::from_static("x-qr-height"))
         {
-            gen.height =
-                val.parse().map(Some).map_err(|_| StatusCode::BAD_REQUEST)?;
+            gen.height = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-height", e))?;
         }
 
         if let Some(val) = get_first_header_value(
             req,
             HeaderName::from_static("x-qr-min-width"),
         ) {
-            gen.min_width =
-                val.parse().map(Some).map_err(|_| StatusCode::BAD_REQUEST)?;
+            gen.min_width = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-min-width", e))?;
         }
 
         if let Some(val) = get_first_header_value(
             req,
             HeaderName::from_static("x-qr-min-height"),
         ) {
-            gen.min_height =
-                val.parse().map(Some).map_err(|_| StatusCode::BAD_REQUEST)?;
+            gen.min_height = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-min-height", e))?;
         }
 
         if let Some(val) = get_first_header_value(
             req,
             HeaderName::from_static("x-qr-max-width"),
         ) {
-            gen.max_width =
-                val.parse().map(Some).map_err(|_| StatusCode::BAD_REQUEST)?;
+            gen.max_width = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-max-width", e))?;
         }
 
         if let Some(val) = get_first_header_value(
             req,
             HeaderName::from_static("x-qr-max-height"),
         ) {
-            gen.max_height =
-                val.parse().map(Some).map_err(|_| StatusCode::BAD_REQUEST)?;
+            gen.max_height = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-max-height", e))?;
         }
 
-        if let Some(val) = get_first_header_value(
-            req,
-            HeaderName::from_static("x-qr-dark-color"),
-        ) {
-            gen.dark_color = Some(format!("#{}", val));
+        if let Some(val) =
+            get_header_value(req, HeaderName::from_static("x-qr-dark-color"))
+        {
+            gen.dark_color = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-dark-color", e))?;
         }
 
-        if let Some(val) = get_first_header_value(
-            req,
-            HeaderName::from_static("x-qr-light-color"),
-        ) {
-            gen.light_color = Some(format!("#{}", val));
+        if let Some(val) =
+            get_header_value(req, HeaderName::from_static("x-qr-light-color"))
+        {
+            gen.light_color = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-light-color", e))?;
+        }
+
+        if let Some(val) =
+            get_header_value(req, HeaderName::from_static("x-qr-dark-gradient"))
+        {
+            gen.dark_gradient = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-dark-gradient", e))?;
         }
 
         if let Some(val) = get_first_header_value(
@@ -149,8 +260,10 @@ where
             req,
             HeaderName::from_static("x-qr-version-number"),
         ) {
-            gen.version_number =
-                val.parse().map(Some).map_err(|_| StatusCode::BAD_REQUEST)?;
+            gen.version_number = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-version-number", e))?;
         }
 
         if let Some(val) = get_first_header_value(
@@ -162,7 +275,7 @@ where
                 "M" => Ok(Some(EcLevel::M)),
                 "Q" => Ok(Some(EcLevel::Q)),
                 "H" => Ok(Some(EcLevel::H)),
-                _ => Err(StatusCode::BAD_REQUEST),
+                _ => Err(bad_request("x-qr-ec-level", "expected L, M, Q or H")),
             }?;
         }
 
@@ -170,14 +283,315 @@ where
             req,
             HeaderName::from_static("x-qr-quiet-zone"),
         ) {
-            gen.quiet_zone =
-                val.parse().map(Some).map_err(|_| StatusCode::BAD_REQUEST)?;
+            gen.quiet_zone = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-quiet-zone", e))?;
+        }
+
+        if let Some(val) =
+            get_first_header_value(req, HeaderName::from_static("x-qr-srgb"))
+        {
+            gen.srgb = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-srgb", e))?;
+        }
+
+        if let Some(val) = get_first_header_value(
+            req,
+            HeaderName::from_static("x-qr-module-size"),
+        ) {
+            gen.module_size = parse_module_size(&val)
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-module-size", e))?;
+        }
+
+        if let Some(val) =
+            get_first_header_value(req, HeaderName::from_static("x-qr-margin"))
+        {
+            gen.margin = parse_margin(&val)
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-margin", e))?;
+        }
+
+        if let Some(val) = get_first_header_value(
+            req,
+            HeaderName::from_static("x-qr-physical-size"),
+        ) {
+            gen.physical_size = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-physical-size", e))?;
+        }
+
+        if let Some(val) =
+            get_first_header_value(req, HeaderName::from_static("x-qr-dpi"))
+        {
+            gen.dpi = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-dpi", e))?;
+        }
+
+        if let Some(val) =
+            get_first_header_value(req, HeaderName::from_static("x-qr-shape"))
+        {
+            gen.shape = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-shape", e))?;
+        }
+
+        if let Some(val) = get_first_header_value(
+            req,
+            HeaderName::from_static("x-qr-eye-shape"),
+        ) {
+            gen.eye_shape = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-eye-shape", e))?;
+        }
+
+        if let Some(val) = get_first_header_value(
+            req,
+            HeaderName::from_static("x-qr-eye-dot-shape"),
+        ) {
+            gen.eye_dot_shape = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-eye-dot-shape", e))?;
+        }
+
+        if let Some(val) =
+            get_header_value(req, HeaderName::from_static("x-qr-eye-color"))
+        {
+            gen.eye_color = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-eye-color", e))?;
+        }
+
+        if let Some(val) =
+            get_header_value(req, HeaderName::from_static("x-qr-logo"))
+        {
+            gen.logo = Logo::from_base64(&val)
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-logo", e))?;
+        }
+
+        if let Some(val) = get_first_header_value(
+            req,
+            HeaderName::from_static("x-qr-logo-size"),
+        ) {
+            gen.logo_size = parse_logo_size(&val)
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-logo-size", e))?;
+        }
+
+        if let Some(val) =
+            get_first_header_value(req, HeaderName::from_static("x-qr-frame"))
+        {
+            gen.frame = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-frame", e))?;
+        }
+
+        if let Some(val) =
+            get_header_value(req, HeaderName::from_static("x-qr-frame-text"))
+        {
+            gen.frame_text = Some(val);
+        }
+
+        if let Some(val) = get_first_header_value(
+            req,
+            HeaderName::from_static("x-qr-frame-position"),
+        ) {
+            gen.frame_position = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-frame-position", e))?;
+        }
+
+        if let Some(val) =
+            get_header_value(req, HeaderName::from_static("x-qr-frame-color"))
+        {
+            gen.frame_color = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-frame-color", e))?;
+        }
+
+        if let Some(val) =
+            get_header_value(req, HeaderName::from_static("x-qr-halftone"))
+        {
+            gen.halftone = Halftone::from_base64(&val)
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-halftone", e))?;
+        }
+
+        if let Some(val) =
+            get_first_header_value(req, HeaderName::from_static("x-qr-invert"))
+        {
+            gen.invert = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-invert", e))?;
+        }
+
+        if let Some(val) =
+            get_first_header_value(req, HeaderName::from_static("x-qr-rotate"))
+        {
+            gen.rotation = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-rotate", e))?;
+        }
+
+        if let Some(val) =
+            get_first_header_value(req, HeaderName::from_static("x-qr-mirror"))
+        {
+            gen.mirror = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-mirror", e))?;
+        }
+
+        if let Some(val) =
+            get_header_value(req, HeaderName::from_static("x-qr-alt-text"))
+        {
+            gen.alt_text = Some(val);
+        }
+
+        if let Some(val) = get_first_header_value(
+            req,
+            HeaderName::from_static("x-qr-accessible"),
+        ) {
+            gen.accessible = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-accessible", e))?;
+        }
+
+        if let Some(val) = get_first_header_value(
+            req,
+            HeaderName::from_static("x-qr-scalable"),
+        ) {
+            gen.scalable = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-scalable", e))?;
+        }
+
+        if let Some(val) = get_first_header_value(
+            req,
+            HeaderName::from_static("x-qr-input-encoding"),
+        ) {
+            gen.input_encoding = val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-input-encoding", e))?;
+        }
+
+        if gen.module_size.is_some() && gen.physical_size.is_some() {
+            return Err(bad_request(
+                "x-qr-module-size",
+                "can't be set along with x-qr-physical-size",
+            ));
         }
 
         Ok(QRGenerator(gen))
     }
 }
 
+struct ContentType(Option<String>);
+
+#[async_trait]
+impl<B> FromRequest<B> for ContentType
+where
+    B: Send, // required by `async_trait`
+{
+    type Rejection = Infallible;
+
+    async fn from_request(
+        req: &mut RequestParts<B>,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(Self(get_header_value(req, header::CONTENT_TYPE)))
+    }
+}
+
+/// The request headers as sent, before a style fills them in.
+struct RequestHeaders(HeaderMap);
+
+#[async_trait]
+impl<B> FromRequest<B> for RequestHeaders
+where
+    B: Send, // required by `async_trait`
+{
+    type Rejection = Infallible;
+
+    async fn from_request(
+        req: &mut RequestParts<B>,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(Self(req.headers().cloned().unwrap_or_default()))
+    }
+}
+
+/// What a batch is sent back in, the names of its files, and the sheet its
+/// codes are laid out on.
+struct QRBatch(BatchOutput, Option<Template>, Option<Sheet>);
+
+#[async_trait]
+impl<B> FromRequest<B> for QRBatch
+where
+    B: Send, // required by `async_trait`
+{
+    type Rejection = (StatusCode, String);
+
+    async fn from_request(
+        req: &mut RequestParts<B>,
+    ) -> Result<Self, Self::Rejection> {
+        let output = get_first_header_value(req, header::ACCEPT)
+            .map(|val| BatchOutput::from(val.as_str()))
+            .unwrap_or_default();
+
+        let names = match get_header_value(
+            req,
+            HeaderName::from_static("x-qr-batch-name"),
+        ) {
+            Some(val) => val
+                .parse()
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-batch-name", e))?,
+            None => None,
+        };
+
+        let spacing = |name: &'static str| {
+            get_header_value(req, HeaderName::from_static(name))
+                .map(|val| val.parse::<Spacing>())
+                .transpose()
+                .map_err(|e| bad_request(name, e))
+        };
+        let margin = spacing("x-qr-sheet-margin")?;
+        let gutter = spacing("x-qr-sheet-gutter")?;
+
+        let sheet = match get_header_value(
+            req,
+            HeaderName::from_static("x-qr-sheet"),
+        ) {
+            Some(val) => val
+                .parse::<Sheet>()
+                .and_then(|sheet| sheet.space(margin, gutter))
+                .map(Some)
+                .map_err(|e| bad_request("x-qr-sheet", e))?,
+            None => None,
+        };
+
+        Ok(Self(output, names, sheet))
+    }
+}
+
 enum QRResponse {
     Cors,
     Plain(String),
@@ -186,6 +600,8 @@ enum QRResponse {
     Png(Vec<u8>),
     Jpeg(Vec<u8>),
     Unicode(Vec<u8>),
+    Zip(Vec<u8>),
+    Pdf(Vec<u8>),
 }
 
 fn cors<T>(mut res: Response<T>) -> Response<T> {
@@ -203,6 +619,29 @@ fn cors<T>(mut res: Response<T>) -> Response<T> {
         header::ACCESS_CONTROL_ALLOW_HEADERS,
         HeaderValue::from_static("*"),
     );
+
+    res.headers_mut().insert(
+        header::ACCESS_CONTROL_EXPOSE_HEADERS,
+        HeaderValue::from_static("X-QR-Warnings"),
+    );
+    res
+}
+
+fn warn<T>(mut res: Response<T>, warnings: &[Warning]) -> Response<T> {
+    if warnings.is_empty() {
+        return res;
+    }
+
+    let warnings = warnings
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if let Ok(val) = HeaderValue::from_str(&warnings) {
+        res.headers_mut()
+            .insert(HeaderName::from_static("x-qr-warnings"), val);
+    }
     res
 }
 
@@ -275,49 +714,236 @@ impl IntoResponse for QRResponse {
 
                 res
             }
+
+            Self::Zip(zip) => {
+                let mut res = Response::new(zip.into());
+                res.headers_mut().insert(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static("application/zip"),
+                );
+                res.headers_mut().insert(
+                    header::CONTENT_DISPOSITION,
+                    HeaderValue::from_static(
+                        "attachment; filename=\"qrcodes.zip\"",
+                    ),
+                );
+
+                res
+            }
+
+            Self::Pdf(pdf) => {
+                let mut res = Response::new(pdf.into());
+                res.headers_mut().insert(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static("application/pdf"),
+                );
+                res.headers_mut().insert(
+                    header::CONTENT_DISPOSITION,
+                    HeaderValue::from_static(
+                        "attachment; filename=\"qrcodes.pdf\"",
+                    ),
+                );
+
+                res
+            }
         };
         cors(resp)
     }
 }
 
-fn generate(bytes: &[u8], gen: &Generator) -> Result<QRResponse, StatusCode> {
-    let image = gen.generate(bytes).map_err(|_| StatusCode::BAD_REQUEST)?;
+fn generate(
+    bytes: &[u8],
+    gen: &Generator,
+) -> Result<Response<Full<Bytes>>, StatusCode> {
+    let code = gen.code(bytes).map_err(|_| StatusCode::BAD_REQUEST)?;
+    if let Err(e) = gen.check_logo(&code) {
+        return Ok(cors(bad_request("logo", e).into_response()));
+    }
+    if let Err(e) = gen.check_halftone() {
+        return Ok(cors(bad_request("x-qr-halftone", e).into_response()));
+    }
+    if let Err(e) = gen.check_size(&code) {
+        return Ok(cors(bad_request("size", e).into_response()));
+    }
 
-    match gen.format {
+    let warnings = gen.lint(&code);
+    let image = gen
+        .render(bytes, &code)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let resp = match gen.format {
         Format::Svg => {
-            Ok(QRResponse::Svg(String::from_utf8_lossy(&image).to_string()))
+            QRResponse::Svg(String::from_utf8_lossy(&image).to_string())
         }
 
-        Format::Png => Ok(QRResponse::Png(image)),
+        Format::Png => QRResponse::Png(image),
 
-        Format::Jpeg => Ok(QRResponse::Jpeg(image)),
+        Format::Jpeg => QRResponse::Jpeg(image),
 
         Format::Html => {
             let html = TEMPLATE
                 .replace("{{ content }}", &String::from_utf8_lossy(&image))
                 .replace("{{ help }}", &HTML_HELP);
-            Ok(QRResponse::Html(html))
+            QRResponse::Html(html)
         }
 
-        Format::Unicode => Ok(QRResponse::Unicode(image)),
+        Format::Unicode => QRResponse::Unicode(image),
+
+        Format::PlainText => {
+            QRResponse::Plain(String::from_utf8_lossy(&image).to_string())
+        }
+    };
+
+    Ok(warn(resp.into_response(), &warnings))
+}
 
-        Format::PlainText => Ok(QRResponse::Plain(
-            String::from_utf8_lossy(&image).to_string(),
-        )),
+/// The kind of payload a `/__payload__/<kind>` or `/__<kind>__` route
+/// builds.
+fn payload_kind(path: &str) -> Option<&str> {
+    match path.strip_prefix("__payload__/") {
+        Some(kind) => Some(kind),
+        None => path.strip_prefix("__")?.strip_suffix("__"),
     }
 }
 
+/// Generates the code of a payload built from fields, `None` if there is no
+/// such kind of payload.
+fn generate_payload(
+    kind: &str,
+    fields: &Fields,
+    gen: &Generator,
+) -> Option<Result<Response<Full<Bytes>>, StatusCode>> {
+    if kind == "template" {
+        return Some(generate_template(fields, gen));
+    }
+
+    let resp = match build_payload(kind, fields)? {
+        Ok(payload) => {
+            let mut gen = gen.clone();
+            payload.configure(&mut gen);
+
+            let estimate = gen.estimate(payload.data.as_bytes());
+            if estimate.fits() {
+                generate(payload.data.as_bytes(), &gen)
+            } else {
+                Ok(cors(bad_request(kind, estimate).into_response()))
+            }
+        }
+        Err(e) => Ok(cors(bad_request(kind, e).into_response())),
+    };
+    Some(resp)
+}
+
+/// Generates the code a template makes, or a ZIP archive of the codes if it
+/// has a sequence of many.
+fn generate_template(
+    fields: &Fields,
+    gen: &Generator,
+) -> Result<Response<Full<Bytes>>, StatusCode> {
+    let mut codes = match Template::expand(fields) {
+        Ok(codes) => codes,
+        Err(e) => return Ok(cors(bad_request("template", e).into_response())),
+    };
+
+    if codes.len() == 1 {
+        let (_, input) = codes.remove(0);
+        return generate(input.as_bytes(), gen);
+    }
+
+    match archive(gen, codes) {
+        Ok(zip) => Ok(QRResponse::Zip(zip).into_response()),
+        Err(e) => Ok(cors(bad_request("template", e).into_response())),
+    }
+}
+
+/// The generator of a row: the request one, or one of the request headers
+/// with the row's own applied over them.
+async fn row_generator(
+    headers: &HeaderMap,
+    presets: &Arc<Presets>,
+    gen: &Generator,
+    row: &Row,
+) -> Result<Generator, BatchError> {
+    let overrides = row.headers();
+    if overrides.is_empty() {
+        return Ok(gen.clone());
+    }
+
+    let mut req = Request::new(());
+    *req.headers_mut() = headers.clone();
+    req.extensions_mut().insert(presets.clone());
+    for (key, value) in overrides {
+        let err = |e: &dyn Display| row.error(format!("{}: {}", key, e));
+        let name =
+            HeaderName::from_bytes(key.as_bytes()).map_err(|e| err(&e))?;
+        let value = HeaderValue::from_str(value).map_err(|e| err(&e))?;
+        req.headers_mut().insert(name, value);
+    }
+
+    match QRGenerator::from_request(&mut RequestParts::new(req)).await {
+        Ok(QRGenerator(gen)) => Ok(gen),
+        Err((_, e)) => Err(row.error(e)),
+    }
+}
+
+/// Generates a code per row of a CSV or JSON Lines body, into a ZIP
+/// archive, a PDF document, or the SVG documents of sheets.
+async fn generate_batch(
+    headers: &HeaderMap,
+    presets: &Arc<Presets>,
+    gen: &Generator,
+    content_type: Option<&str>,
+    body: &[u8],
+) -> Result<Response<Full<Bytes>>, StatusCode> {
+    let mut req = Request::new(());
+    *req.headers_mut() = headers.clone();
+    let QRBatch(output, names, sheet) =
+        match QRBatch::from_request(&mut RequestParts::new(req)).await {
+            Ok(batch) => batch,
+            Err(e) => return Ok(cors(e.into_response())),
+        };
+
+    let rows = match parse_rows(content_type, body) {
+        Ok(rows) => rows,
+        Err(e) => return Ok(cors(bad_request("batch", e).into_response())),
+    };
+
+    let mut batch = Batch::new(output, names, sheet, &rows);
+    for row in &rows {
+        let added = match row_generator(headers, presets, gen, row).await {
+            Ok(gen) => batch.add(row, &gen),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = added {
+            return Ok(cors(bad_request("batch", e).into_response()));
+        }
+    }
+
+    let resp = match batch.finish() {
+        (BatchOutput::Zip, bytes) => QRResponse::Zip(bytes),
+        (BatchOutput::Pdf, bytes) => QRResponse::Pdf(bytes),
+        (BatchOutput::Svg, bytes) => {
+            QRResponse::Svg(String::from_utf8_lossy(&bytes).to_string())
+        }
+    };
+    Ok(resp.into_response())
+}
+
 async fn options_handler() -> Result<QRResponse, StatusCode> {
     Ok(QRResponse::Cors)
 }
 
 async fn post_handler(
     OriginalUri(uri): OriginalUri,
-    QRGenerator(gen): QRGenerator,
+    RequestHeaders(headers): RequestHeaders,
+    Extension(presets): Extension<Arc<Presets>>,
+    QRGenerator(mut gen): QRGenerator,
+    ContentType(content_type): ContentType,
     RawBody(body): RawBody,
-) -> Result<QRResponse, StatusCode> {
+) -> Result<Response<Full<Bytes>>, StatusCode> {
     let (_, path) = uri.path().split_once('/').unwrap_or_default();
-    if !path.is_empty() {
+    let kind = payload_kind(path);
+    if !path.is_empty() && kind.is_none() {
         return Err(StatusCode::BAD_REQUEST);
     };
 
@@ -325,17 +951,78 @@ async fn post_handler(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    if path == "__batch__" {
+        return generate_batch(
+            &headers,
+            &presets,
+            &gen,
+            content_type.as_deref(),
+            &bytes,
+        )
+        .await;
+    }
+
+    if let Some(kind) = kind {
+        let fields = Fields::from_body(content_type.as_deref(), &bytes);
+        return match fields {
+            Ok(fields) => generate_payload(kind, &fields, &gen)
+                .unwrap_or(Err(StatusCode::BAD_REQUEST)),
+            Err(e) => Ok(cors(bad_request(kind, e).into_response())),
+        };
+    }
+
+    // A form upload carries the data and the images as separate fields.
+    let bytes = match content_type.as_deref().and_then(multipart::boundary) {
+        Some(boundary) => {
+            let parts = multipart::parse(&bytes, &boundary)
+                .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+            let mut data = vec![];
+            for part in parts {
+                match part.name.as_deref() {
+                    Some("data") => data = part.data,
+                    Some("logo") => match Logo::from_bytes(&part.data) {
+                        Ok(logo) => gen.logo = Some(logo),
+                        Err(e) => {
+                            return Ok(cors(
+                                bad_request("logo", e).into_response(),
+                            ))
+                        }
+                    },
+                    Some("halftone") => {
+                        match Halftone::from_bytes(&part.data) {
+                            Ok(halftone) => gen.halftone = Some(halftone),
+                            Err(e) => {
+                                return Ok(cors(
+                                    bad_request("halftone", e).into_response(),
+                                ))
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Bytes::from(data)
+        }
+        None => bytes,
+    };
+
     if bytes.is_empty() {
         Err(StatusCode::BAD_REQUEST)
     } else {
-        generate(&bytes, &gen)
+        match gen.decode_input(&bytes) {
+            Ok(bytes) => generate(&bytes, &gen),
+            Err(e) => {
+                Ok(cors(bad_request("x-qr-input-encoding", e).into_response()))
+            }
+        }
     }
 }
 
 async fn get_handler(
     OriginalUri(uri): OriginalUri,
     QRGenerator(gen): QRGenerator,
-) -> Result<QRResponse, StatusCode> {
+) -> Result<Response<Full<Bytes>>, StatusCode> {
     let (_, path) = uri.path().split_once('/').unwrap_or_default();
 
     if path.is_empty() {
@@ -344,22 +1031,33 @@ async fn get_handler(
                 let html = TEMPLATE
                     .replace("{{ content }}", "")
                     .replace("{{ help }}", &HTML_HELP);
-                Ok(QRResponse::Html(html))
+                Ok(QRResponse::Html(html).into_response())
             }
             Format::PlainText | Format::Unicode => {
-                Ok(QRResponse::Plain(HELP.to_string()))
+                Ok(QRResponse::Plain(HELP.to_string()).into_response())
             }
             Format::Jpeg | Format::Png | Format::Svg => {
                 Err(StatusCode::BAD_REQUEST)
             }
         }
+    } else if let Some(resp) = payload_kind(path).and_then(|kind| {
+        let fields = Fields::from_query(uri.query().unwrap_or_default());
+        generate_payload(kind, &fields, &gen)
+    }) {
+        resp
+    } else if path.starts_with("__payload__/") {
+        Err(StatusCode::BAD_REQUEST)
     } else {
         let input = uri
             .query()
             .map(|q| format!("{}?{}", path, q))
             .unwrap_or_else(|| path.to_string());
 
-        let bytes = input.as_bytes();
-        generate(bytes, &gen)
+        match gen.decode_input(input.as_bytes()) {
+            Ok(bytes) => generate(&bytes, &gen),
+            Err(e) => {
+                Ok(cors(bad_request("x-qr-input-encoding", e).into_response()))
+            }
+        }
     }
 }
@@ -2,9 +2,28 @@ use worker::*;
 
 mod utils;
 
+use libs::archive;
+use libs::build_payload;
+use libs::multipart;
+use libs::parse_logo_size;
+use libs::parse_margin;
+use libs::parse_module_size;
+use libs::parse_rows;
+use libs::Batch;
+use libs::BatchError;
+use libs::BatchOutput;
 use libs::EcLevel;
+use libs::Fields;
 use libs::Format;
 use libs::Generator;
+use libs::Halftone;
+use libs::Logo;
+use libs::Presets;
+use libs::Row;
+use libs::Sheet;
+use libs::Spacing;
+use libs::Template;
+use libs::Warning;
 use libs::HELP;
 use libs::HTML_HELP;
 use libs::TEMPLATE;
@@ -21,7 +40,38 @@ fn get_first_header_value(headers: &Headers, key: &str) -> Option<String> {
         .and_then(|s| s.split(';').next().map(String::from))
 }
 
+fn get_header_value(headers: &Headers, key: &str) -> Option<String> {
+    headers
+        .get(key)
+        .unwrap_or_default()
+        .map(|s| s.trim().to_string())
+}
+
+/// Fills in the headers the request doesn't set from the style it names.
+fn with_style(headers: &Headers) -> Result<Headers> {
+    let mut merged = Headers::new();
+    for (key, value) in headers.entries() {
+        merged.set(&key, &value)?;
+    }
+
+    if let Some(name) = get_first_header_value(headers, "x-qr-style") {
+        let presets = Presets::default();
+        let preset = presets
+            .find(&name)
+            .map_err(|e| format!("x-qr-style: {}", e))?;
+
+        for (key, value) in
+            preset.unset(|key| headers.has(key).unwrap_or(false))
+        {
+            merged.set(key, value)?;
+        }
+    }
+
+    Ok(merged)
+}
+
 fn generator_from_headers(headers: &Headers) -> Result<Generator> {
+    let headers = &with_style(headers)?;
     let mut gen = Generator::default();
 
     if let Some(val) = get_first_header_value(headers, "accept") {
@@ -52,12 +102,25 @@ fn generator_from_headers(headers: &Headers) -> Result<Generator> {
         gen.max_height = val.parse().map(Some).map_err(|_| "Bad Request")?;
     }
 
-    if let Some(val) = get_first_header_value(headers, "x-qr-dark-color") {
-        gen.dark_color = Some(format!("#{}", val));
+    if let Some(val) = get_header_value(headers, "x-qr-dark-color") {
+        gen.dark_color = val
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("x-qr-dark-color: {}", e))?;
     }
 
-    if let Some(val) = get_first_header_value(headers, "x-qr-light-color") {
-        gen.light_color = Some(format!("#{}", val));
+    if let Some(val) = get_header_value(headers, "x-qr-light-color") {
+        gen.light_color = val
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("x-qr-light-color: {}", e))?;
+    }
+
+    if let Some(val) = get_header_value(headers, "x-qr-dark-gradient") {
+        gen.dark_gradient = val
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("x-qr-dark-gradient: {}", e))?;
     }
 
     if let Some(val) = get_first_header_value(headers, "x-qr-version-type") {
@@ -83,12 +146,181 @@ fn generator_from_headers(headers: &Headers) -> Result<Generator> {
         gen.quiet_zone = val.parse().map(Some).map_err(|_| "Bad Request")?;
     }
 
+    if let Some(val) = get_first_header_value(headers, "x-qr-srgb") {
+        gen.srgb = val.parse().map(Some).map_err(|_| "Bad Request")?;
+    }
+
+    if let Some(val) = get_first_header_value(headers, "x-qr-module-size") {
+        gen.module_size = parse_module_size(&val)
+            .map(Some)
+            .map_err(|e| format!("x-qr-module-size: {}", e))?;
+    }
+
+    if let Some(val) = get_first_header_value(headers, "x-qr-margin") {
+        gen.margin = parse_margin(&val)
+            .map(Some)
+            .map_err(|e| format!("x-qr-margin: {}", e))?;
+    }
+
+    if let Some(val) = get_first_header_value(headers, "x-qr-physical-size") {
+        gen.physical_size = val
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("x-qr-physical-size: {}", e))?;
+    }
+
+    if let Some(val) = get_first_header_value(headers, "x-qr-dpi") {
+        gen.dpi = val
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("x-qr-dpi: {}", e))?;
+    }
+
+    if let Some(val) = get_first_header_value(headers, "x-qr-shape") {
+        gen.shape = val
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("x-qr-shape: {}", e))?;
+    }
+
+    if let Some(val) = get_first_header_value(headers, "x-qr-eye-shape") {
+        gen.eye_shape = val
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("x-qr-eye-shape: {}", e))?;
+    }
+
+    if let Some(val) = get_first_header_value(headers, "x-qr-eye-dot-shape") {
+        gen.eye_dot_shape = val
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("x-qr-eye-dot-shape: {}", e))?;
+    }
+
+    if let Some(val) = get_header_value(headers, "x-qr-eye-color") {
+        gen.eye_color = val
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("x-qr-eye-color: {}", e))?;
+    }
+
+    if let Some(val) = get_header_value(headers, "x-qr-logo") {
+        gen.logo = Logo::from_base64(&val)
+            .map(Some)
+            .map_err(|e| format!("x-qr-logo: {}", e))?;
+    }
+
+    if let Some(val) = get_first_header_value(headers, "x-qr-logo-size") {
+        gen.logo_size = parse_logo_size(&val)
+            .map(Some)
+            .map_err(|e| format!("x-qr-logo-size: {}", e))?;
+    }
+
+    if let Some(val) = get_first_header_value(headers, "x-qr-frame") {
+        gen.frame = val
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("x-qr-frame: {}", e))?;
+    }
+
+    if let Some(val) = get_header_value(headers, "x-qr-frame-text") {
+        gen.frame_text = Some(val);
+    }
+
+    if let Some(val) = get_first_header_value(headers, "x-qr-frame-position") {
+        gen.frame_position = val
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("x-qr-frame-position: {}", e))?;
+    }
+
+    if let Some(val) = get_header_value(headers, "x-qr-frame-color") {
+        gen.frame_color = val
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("x-qr-frame-color: {}", e))?;
+    }
+
+    if let Some(val) = get_header_value(headers, "x-qr-halftone") {
+        gen.halftone = Halftone::from_base64(&val)
+            .map(Some)
+            .map_err(|e| format!("x-qr-halftone: {}", e))?;
+    }
+
+    if let Some(val) = get_first_header_value(headers, "x-qr-invert") {
+        gen.invert = val
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("x-qr-invert: {}", e))?;
+    }
+
+    if let Some(val) = get_first_header_value(headers, "x-qr-rotate") {
+        gen.rotation = val
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("x-qr-rotate: {}", e))?;
+    }
+
+    if let Some(val) = get_first_header_value(headers, "x-qr-mirror") {
+        gen.mirror = val
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("x-qr-mirror: {}", e))?;
+    }
+
+    if let Some(val) = get_header_value(headers, "x-qr-alt-text") {
+        gen.alt_text = Some(val);
+    }
+
+    if let Some(val) = get_first_header_value(headers, "x-qr-accessible") {
+        gen.accessible = val
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("x-qr-accessible: {}", e))?;
+    }
+
+    if let Some(val) = get_first_header_value(headers, "x-qr-scalable") {
+        gen.scalable = val
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("x-qr-scalable: {}", e))?;
+    }
+
+    if let Some(val) = get_first_header_value(headers, "x-qr-input-encoding") {
+        gen.input_encoding = val
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("x-qr-input-encoding: {}", e))?;
+    }
+
+    if gen.module_size.is_some() && gen.physical_size.is_some() {
+        return Err(
+            "x-qr-module-size: can't be set along with x-qr-physical-size"
+                .into(),
+        );
+    }
+
     Ok(gen)
 }
 
 fn generate(bytes: &[u8], gen: &Generator) -> Result<Response> {
-    match gen.generate(bytes) {
-        Err(_) => Response::error("Bad Request", 400),
+    let code = match gen.code(bytes) {
+        Ok(code) => code,
+        Err(_) => return Response::error("Bad Request", 400),
+    };
+    if let Err(e) = gen.check_logo(&code) {
+        return Response::error(format!("logo: {}", e), 400);
+    }
+    if let Err(e) = gen.check_halftone() {
+        return Response::error(format!("x-qr-halftone: {}", e), 400);
+    }
+    if let Err(e) = gen.check_size(&code) {
+        return Response::error(format!("size: {}", e), 400);
+    }
+
+    let warnings = gen.lint(&code);
+    let resp = match gen.render(bytes, &code) {
+        Err(_) => return Response::error("Bad Request", 400),
 
         Ok(image) => match gen.format {
             Format::Html => {
@@ -129,7 +361,191 @@ fn generate(bytes: &[u8], gen: &Generator) -> Result<Response> {
                 Response::ok(String::from_utf8_lossy(&image)).and_then(cors)
             }
         },
+    };
+
+    resp.and_then(|r| warn(r, &warnings))
+}
+
+/// The kind of payload a `/__payload__/<kind>` or `/__<kind>__` route
+/// builds.
+fn payload_kind(path: &str) -> Option<&str> {
+    match path.strip_prefix("__payload__/") {
+        Some(kind) => Some(kind),
+        None => path.strip_prefix("__")?.strip_suffix("__"),
+    }
+}
+
+/// Generates the code of a payload built from fields, `None` if there is no
+/// such kind of payload.
+fn generate_payload(
+    kind: &str,
+    fields: &Fields,
+    gen: &Generator,
+) -> Option<Result<Response>> {
+    if kind == "template" {
+        return Some(generate_template(fields, gen));
+    }
+
+    let resp = match build_payload(kind, fields)? {
+        Ok(payload) => {
+            let mut gen = gen.clone();
+            payload.configure(&mut gen);
+
+            let estimate = gen.estimate(payload.data.as_bytes());
+            if estimate.fits() {
+                generate(payload.data.as_bytes(), &gen)
+            } else {
+                Response::error(format!("{}: {}", kind, estimate), 400)
+            }
+        }
+        Err(e) => Response::error(format!("{}: {}", kind, e), 400),
+    };
+    Some(resp)
+}
+
+/// Generates the code a template makes, or a ZIP archive of the codes if it
+/// has a sequence of many.
+fn generate_template(fields: &Fields, gen: &Generator) -> Result<Response> {
+    let mut codes = match Template::expand(fields) {
+        Ok(codes) => codes,
+        Err(e) => return Response::error(format!("template: {}", e), 400),
+    };
+
+    if codes.len() == 1 {
+        let (_, input) = codes.remove(0);
+        return generate(input.as_bytes(), gen);
+    }
+
+    match archive(gen, codes) {
+        Ok(zip) => {
+            let mut headers = Headers::new();
+            headers.set("Content-Type", "application/zip")?;
+            headers.set(
+                "Content-Disposition",
+                "attachment; filename=\"qrcodes.zip\"",
+            )?;
+            Response::from_bytes(zip).map(|r| r.with_headers(headers))
+        }
+        Err(e) => Response::error(format!("template: {}", e), 400),
+    }
+}
+
+/// The generator of a row: the request one, or one of the request headers
+/// with the row's own applied over them.
+fn row_generator(
+    headers: &Headers,
+    gen: &Generator,
+    row: &Row,
+) -> std::result::Result<Generator, BatchError> {
+    let overrides = row.headers();
+    if overrides.is_empty() {
+        return Ok(gen.clone());
+    }
+
+    let mut merged = Headers::new();
+    for (key, value) in headers.entries() {
+        merged
+            .set(&key, &value)
+            .map_err(|e| row.error(format!("{}: {}", key, e)))?;
+    }
+    for (key, value) in overrides {
+        merged
+            .set(key, value)
+            .map_err(|e| row.error(format!("{}: {}", key, e)))?;
+    }
+
+    generator_from_headers(&merged).map_err(|e| row.error(e.to_string()))
+}
+
+/// Generates a code per row of a CSV or JSON Lines body, into a ZIP
+/// archive, a PDF document, or the SVG documents of sheets.
+fn generate_batch(
+    headers: &Headers,
+    gen: &Generator,
+    content_type: Option<&str>,
+    body: &[u8],
+) -> Result<Response> {
+    let output = get_first_header_value(headers, "accept")
+        .map(|val| BatchOutput::from(val.as_str()))
+        .unwrap_or_default();
+
+    let names = match get_header_value(headers, "x-qr-batch-name") {
+        Some(val) => match val.parse::<Template>() {
+            Ok(names) => Some(names),
+            Err(e) => {
+                return Response::error(format!("x-qr-batch-name: {}", e), 400)
+            }
+        },
+        None => None,
+    };
+
+    let spacing = |name: &str| {
+        get_header_value(headers, name)
+            .map(|val| val.parse::<Spacing>())
+            .transpose()
+            .map_err(|e| format!("{}: {}", name, e))
+    };
+    let (margin, gutter) =
+        match (spacing("x-qr-sheet-margin"), spacing("x-qr-sheet-gutter")) {
+            (Ok(margin), Ok(gutter)) => (margin, gutter),
+            (Err(e), _) | (_, Err(e)) => return Response::error(e, 400),
+        };
+
+    let sheet = match get_header_value(headers, "x-qr-sheet") {
+        Some(val) => match val
+            .parse::<Sheet>()
+            .and_then(|sheet| sheet.space(margin, gutter))
+        {
+            Ok(sheet) => Some(sheet),
+            Err(e) => {
+                return Response::error(format!("x-qr-sheet: {}", e), 400)
+            }
+        },
+        None => None,
+    };
+
+    let rows = match parse_rows(content_type, body) {
+        Ok(rows) => rows,
+        Err(e) => return Response::error(format!("batch: {}", e), 400),
+    };
+
+    let mut batch = Batch::new(output, names, sheet, &rows);
+    for row in &rows {
+        let added = row_generator(headers, gen, row)
+            .and_then(|gen| batch.add(row, &gen));
+        if let Err(e) = added {
+            return Response::error(format!("batch: {}", e), 400);
+        }
+    }
+
+    let (output, bytes) = batch.finish();
+    let (content_type, filename) = match output {
+        BatchOutput::Zip => ("application/zip", Some("qrcodes.zip")),
+        BatchOutput::Pdf => ("application/pdf", Some("qrcodes.pdf")),
+        BatchOutput::Svg => ("image/svg+xml", None),
+    };
+    let mut headers = Headers::new();
+    headers.set("Content-Type", content_type)?;
+    if let Some(filename) = filename {
+        headers.set(
+            "Content-Disposition",
+            &format!("attachment; filename=\"{}\"", filename),
+        )?;
+    }
+    Response::from_bytes(bytes).map(|r| r.with_headers(headers))
+}
+
+fn warn(mut res: Response, warnings: &[Warning]) -> Result<Response> {
+    if !warnings.is_empty() {
+        let warnings = warnings
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        res.headers_mut().set("X-QR-Warnings", &warnings)?;
     }
+    Ok(res)
 }
 
 fn cors(mut res: Response) -> Result<Response> {
@@ -137,6 +553,7 @@ fn cors(mut res: Response) -> Result<Response> {
     headers.set("Access-Control-Allow-Methods", "HEAD, POST, GET, OPTIONS")?;
     headers.set("Access-Control-Allow-Origin", "*")?;
     headers.set("Access-Control-Allow-Headers", "*")?;
+    headers.set("Access-Control-Expose-Headers", "X-QR-Warnings")?;
     Ok(res)
 }
 
@@ -163,18 +580,105 @@ pub async fn main(mut req: Request, _env: Env) -> Result<Response> {
         }
 
         Method::Post => {
-            let bytes = req.bytes().await.unwrap();
+            let content_type = get_header_value(req.headers(), "content-type");
+            let mut gen = match generator_from_headers(req.headers()) {
+                Ok(gen) => gen,
+                Err(e) => {
+                    return Response::error(e.to_string(), 400).and_then(cors)
+                }
+            };
+
+            let path = req
+                .path()
+                .split_once('/')
+                .map(|(_, p)| p.to_string())
+                .unwrap_or_default();
+
+            let mut bytes = req.bytes().await.unwrap();
+
+            if path == "__batch__" {
+                return generate_batch(
+                    req.headers(),
+                    &gen,
+                    content_type.as_deref(),
+                    &bytes,
+                )
+                .and_then(cors);
+            }
+
+            if let Some(kind) = payload_kind(&path) {
+                return match Fields::from_body(content_type.as_deref(), &bytes)
+                {
+                    Ok(fields) => generate_payload(kind, &fields, &gen)
+                        .unwrap_or_else(|| Response::error("Bad Request", 400)),
+                    Err(e) => Response::error(format!("{}: {}", kind, e), 400),
+                }
+                .and_then(cors);
+            }
+
+            // A form upload carries the data and the images as separate fields.
+            if let Some(boundary) =
+                content_type.as_deref().and_then(multipart::boundary)
+            {
+                let parts = match multipart::parse(&bytes, &boundary) {
+                    Ok(parts) => parts,
+                    Err(e) => {
+                        return Response::error(e.to_string(), 400)
+                            .and_then(cors)
+                    }
+                };
+
+                bytes = vec![];
+                for part in parts {
+                    match part.name.as_deref() {
+                        Some("data") => bytes = part.data,
+                        Some("logo") => match Logo::from_bytes(&part.data) {
+                            Ok(logo) => gen.logo = Some(logo),
+                            Err(e) => {
+                                return Response::error(
+                                    format!("logo: {}", e),
+                                    400,
+                                )
+                                .and_then(cors)
+                            }
+                        },
+                        Some("halftone") => {
+                            match Halftone::from_bytes(&part.data) {
+                                Ok(halftone) => gen.halftone = Some(halftone),
+                                Err(e) => {
+                                    return Response::error(
+                                        format!("halftone: {}", e),
+                                        400,
+                                    )
+                                    .and_then(cors)
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
 
             if bytes.is_empty() {
                 Response::error("Bad Request", 400)
             } else {
-                let gen = generator_from_headers(req.headers())?;
-                generate(&bytes, &gen)
+                match gen.decode_input(&bytes) {
+                    Ok(bytes) => generate(&bytes, &gen),
+                    Err(e) => Response::error(
+                        format!("x-qr-input-encoding: {}", e),
+                        400,
+                    ),
+                }
             }
         }
 
         Method::Head | Method::Get => {
-            let gen = generator_from_headers(req.headers())?;
+            let gen = match generator_from_headers(req.headers()) {
+                Ok(gen) => gen,
+                Err(e) => {
+                    return Response::error(e.to_string(), 400).and_then(cors)
+                }
+            };
 
             let path = req
                 .path()
@@ -196,14 +700,31 @@ pub async fn main(mut req: Request, _env: Env) -> Result<Response> {
                     }
                 }
             } else {
-                let path = req
-                    .url()?
-                    .query()
-                    .map(|q| format!("{}?{}", path, q))
-                    .unwrap_or_else(|| path.to_string());
-
-                let bytes = path.as_bytes();
-                generate(bytes, &gen)
+                let query = req.url()?.query().map(String::from);
+                let fields =
+                    Fields::from_query(query.as_deref().unwrap_or_default());
+
+                match payload_kind(&path)
+                    .and_then(|kind| generate_payload(kind, &fields, &gen))
+                {
+                    Some(resp) => resp,
+                    None if path.starts_with("__payload__/") => {
+                        Response::error("Bad Request", 400)
+                    }
+                    None => {
+                        let path = query
+                            .map(|q| format!("{}?{}", path, q))
+                            .unwrap_or_else(|| path.to_string());
+
+                        match gen.decode_input(path.as_bytes()) {
+                            Ok(bytes) => generate(&bytes, &gen),
+                            Err(e) => Response::error(
+                                format!("x-qr-input-encoding: {}", e),
+                                400,
+                            ),
+                        }
+                    }
+                }
             }
         }
         _ => Response::error("Method Not Allowed", 405),
@@ -0,0 +1,280 @@
+#!/usr/bin/env python3
+"""Cuts DejaVu Sans Bold down to the characters captions are drawn in.
+
+    python3 subset.py DejaVuSans-Bold.ttf DejaVuSans-Bold-Subset.ttf
+
+Keeps Latin, Greek, Cyrillic, common punctuation, currency signs and
+arrows, and the tables `ab_glyph` reads: the outlines, the metrics, the
+character map and the kerning pairs. The hinting and the OpenType layout
+tables are dropped, so are the glyph names. Other characters are drawn as
+the missing glyph box.
+"""
+
+import struct
+import sys
+
+RANGES = [
+    (0x0020, 0x007E),  # Basic Latin
+    (0x00A0, 0x024F),  # Latin-1 Supplement, Latin Extended-A and B
+    (0x0370, 0x03FF),  # Greek
+    (0x0400, 0x04FF),  # Cyrillic
+    (0x1E00, 0x1EFF),  # Latin Extended Additional
+    (0x2010, 0x2027),  # Dashes, quotes, bullets, ellipsis
+    (0x2030, 0x203A),  # Per mille, primes, angle quotes
+    (0x20A0, 0x20BF),  # Currency signs
+    (0x2122, 0x2122),  # Trade mark
+    (0x2190, 0x2199),  # Arrows
+]
+
+TABLES = [b"OS/2", b"cmap", b"glyf", b"head", b"hhea", b"hmtx", b"kern",
+          b"loca", b"maxp", b"name", b"post"]
+
+
+def u16(data, at):
+    return struct.unpack(">H", data[at:at + 2])[0]
+
+
+def i16(data, at):
+    return struct.unpack(">h", data[at:at + 2])[0]
+
+
+def u32(data, at):
+    return struct.unpack(">I", data[at:at + 4])[0]
+
+
+def read_tables(font):
+    tables = {}
+    for i in range(u16(font, 4)):
+        tag, _, offset, length = struct.unpack(
+            ">4sIII", font[12 + 16 * i:28 + 16 * i])
+        tables[tag] = font[offset:offset + length]
+    return tables
+
+
+def read_cmap(cmap):
+    """The characters of the format 4 Unicode subtable."""
+    for i in range(u16(cmap, 2)):
+        platform, encoding, offset = struct.unpack(
+            ">HHI", cmap[4 + 8 * i:12 + 8 * i])
+        if (platform, encoding) in [(3, 1), (0, 3)] and \
+                u16(cmap, offset) == 4:
+            break
+    else:
+        sys.exit("no format 4 Unicode cmap")
+
+    t = offset
+    count = u16(cmap, t + 6) // 2
+    ends = t + 14
+    starts = ends + 2 * count + 2
+    deltas = starts + 2 * count
+    range_offsets = deltas + 2 * count
+
+    glyphs = {}
+    for s in range(count):
+        start, end = u16(cmap, starts + 2 * s), u16(cmap, ends + 2 * s)
+        delta = u16(cmap, deltas + 2 * s)
+        range_offset = u16(cmap, range_offsets + 2 * s)
+        for c in range(start, end + 1):
+            if c == 0xFFFF:
+                continue
+            if range_offset == 0:
+                glyph = (c + delta) & 0xFFFF
+            else:
+                at = range_offsets + 2 * s + range_offset + 2 * (c - start)
+                glyph = u16(cmap, at)
+                glyph = (glyph + delta) & 0xFFFF if glyph else 0
+            if glyph:
+                glyphs[c] = glyph
+    return glyphs
+
+
+def write_cmap(glyphs):
+    """A format 4 subtable, one segment per run of consecutive characters
+    with consecutive glyphs."""
+    segments = []
+    for c in sorted(glyphs):
+        last = segments[-1] if segments else None
+        if last and c == last[1] + 1 and glyphs[c] == glyphs[last[1]] + 1:
+            last[1] = c
+        else:
+            segments.append([c, c])
+    segments.append([0xFFFF, 0xFFFF])
+
+    count = len(segments)
+    power = 1 << (count.bit_length() - 1)
+    ends = b"".join(struct.pack(">H", e) for _, e in segments)
+    starts = b"".join(struct.pack(">H", s) for s, _ in segments)
+    deltas = b"".join(
+        struct.pack(">H", (glyphs[s] - s) & 0xFFFF if s != 0xFFFF else 1)
+        for s, _ in segments)
+    offsets = b"\0\0" * count
+    body = ends + b"\0\0" + starts + deltas + offsets
+    subtable = struct.pack(">7H", 4, 14 + len(body), 0, 2 * count,
+                           2 * power, power.bit_length() - 1,
+                           2 * count - 2 * power) + body
+    return struct.pack(">HHHHI", 0, 1, 3, 1, 12) + subtable
+
+
+def glyph_data(glyf, loca, long_loca, glyph):
+    if long_loca:
+        start, end = u32(loca, 4 * glyph), u32(loca, 4 * glyph + 4)
+    else:
+        start, end = 2 * u16(loca, 2 * glyph), 2 * u16(loca, 2 * glyph + 2)
+    return glyf[start:end]
+
+
+def components(data):
+    """The glyphs a composite glyph is made of, and where their ids are."""
+    found = []
+    if not data or i16(data, 0) >= 0:
+        return found
+    at = 10
+    while True:
+        flags = u16(data, at)
+        found.append((at + 2, u16(data, at + 2)))
+        at += 4 + (4 if flags & 0x1 else 2)
+        if flags & 0x8:
+            at += 2
+        elif flags & 0x40:
+            at += 4
+        elif flags & 0x80:
+            at += 8
+        if not flags & 0x20:
+            return found
+
+
+def strip_instructions(data):
+    """The glyph without its hinting instructions."""
+    if not data:
+        return data
+    contours = i16(data, 0)
+    if contours >= 0:
+        at = 10 + 2 * contours
+        length = u16(data, at)
+        return data[:at] + b"\0\0" + data[at + 2 + length:]
+
+    data = bytearray(data)
+    at = 10
+    while True:
+        flags = u16(data, at)
+        struct.pack_into(">H", data, at, flags & ~0x100)
+        at += 4 + (4 if flags & 0x1 else 2)
+        if flags & 0x8:
+            at += 2
+        elif flags & 0x40:
+            at += 4
+        elif flags & 0x80:
+            at += 8
+        if not flags & 0x20:
+            return bytes(data[:at])
+
+
+def write_kern(kern, ids):
+    """The format 0 pairs between the glyphs kept, renumbered."""
+    pairs = []
+    at = 4
+    for _ in range(u16(kern, 2)):
+        length, coverage = u16(kern, at + 2), u16(kern, at + 4)
+        if coverage >> 8 == 0:
+            for i in range(u16(kern, at + 6)):
+                pair = at + 14 + 6 * i
+                left, right = u16(kern, pair), u16(kern, pair + 2)
+                if left in ids and right in ids:
+                    pairs.append((ids[left], ids[right], i16(kern, pair + 4)))
+        at += length
+    pairs.sort()
+
+    count = len(pairs)
+    power = 1 << (count.bit_length() - 1)
+    body = b"".join(struct.pack(">HHh", *p) for p in pairs)
+    subtable = struct.pack(">7H", 0, 14 + len(body), 1, count, 6 * power,
+                           power.bit_length() - 1, 6 * (count - power))
+    return struct.pack(">HH", 0, 1) + subtable + body
+
+
+def checksum(data):
+    data += b"\0" * (-len(data) % 4)
+    return sum(struct.unpack(">%dI" % (len(data) // 4), data)) & 0xFFFFFFFF
+
+
+def main(source, target):
+    font = open(source, "rb").read()
+    tables = read_tables(font)
+    head, hhea, maxp = tables[b"head"], tables[b"hhea"], tables[b"maxp"]
+    long_loca = i16(head, 50) == 1
+    glyph_count = u16(maxp, 4)
+    metric_count = u16(hhea, 34)
+
+    cmap = {c: g for c, g in read_cmap(tables[b"cmap"]).items()
+            if any(start <= c <= end for start, end in RANGES)}
+
+    # The missing glyph box first, then the glyphs in character order, then
+    # the parts of composite glyphs.
+    order = [0]
+    for c in sorted(cmap):
+        if cmap[c] not in order:
+            order.append(cmap[c])
+    for glyph in order:
+        data = glyph_data(tables[b"glyf"], tables[b"loca"], long_loca, glyph)
+        for _, part in components(data):
+            if part not in order:
+                order.append(part)
+    ids = {glyph: i for i, glyph in enumerate(order)}
+
+    glyf, loca, hmtx = b"", b"", b""
+    for glyph in order:
+        data = glyph_data(tables[b"glyf"], tables[b"loca"], long_loca, glyph)
+        data = bytearray(strip_instructions(data))
+        for at, part in components(data):
+            struct.pack_into(">H", data, at, ids[part])
+        loca += struct.pack(">I", len(glyf))
+        glyf += bytes(data) + b"\0" * (len(data) % 2)
+
+        metric = min(glyph, metric_count - 1)
+        advance = u16(tables[b"hmtx"], 4 * metric)
+        if glyph < metric_count:
+            bearing = i16(tables[b"hmtx"], 4 * glyph + 2)
+        else:
+            at = 4 * metric_count + 2 * (glyph - metric_count)
+            bearing = i16(tables[b"hmtx"], at)
+        hmtx += struct.pack(">Hh", advance, bearing)
+    loca += struct.pack(">I", len(glyf))
+    assert glyph_count >= len(order)
+
+    # Long offsets, and the checksum adjustment cleared to be set below.
+    head = head[:8] + b"\0" * 4 + head[12:50] + struct.pack(">h", 1) + \
+        head[52:]
+    tables.update({
+        b"head": head,
+        b"hhea": hhea[:34] + struct.pack(">H", len(order)) + hhea[36:],
+        b"maxp": maxp[:4] + struct.pack(">H", len(order)) + maxp[6:],
+        b"cmap": write_cmap({c: ids[g] for c, g in cmap.items()}),
+        b"glyf": glyf,
+        b"loca": loca,
+        b"hmtx": hmtx,
+        b"kern": write_kern(tables[b"kern"], ids),
+        # Version 3, without glyph names.
+        b"post": struct.pack(">I", 0x30000) + tables[b"post"][4:32],
+    })
+
+    count = len(TABLES)
+    power = 1 << (count.bit_length() - 1)
+    out = struct.pack(">IHHHH", 0x10000, count, 16 * power,
+                      power.bit_length() - 1, 16 * (count - power))
+    offset = len(out) + 16 * count
+    directory, data = b"", b""
+    for tag in TABLES:
+        table = tables[tag]
+        directory += struct.pack(">4sIII", tag, checksum(table),
+                                 offset + len(data), len(table))
+        data += table + b"\0" * (-len(table) % 4)
+    out = bytearray(out + directory + data)
+
+    head_at = u32(out, 12 + 16 * TABLES.index(b"head") + 8)
+    adjustment = (0xB1B0AFBA - checksum(bytes(out))) & 0xFFFFFFFF
+    struct.pack_into(">I", out, head_at + 8, adjustment)
+    open(target, "wb").write(out)
+
+
+if __name__ == "__main__":
+    main(*sys.argv[1:])
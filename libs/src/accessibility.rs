@@ -0,0 +1,60 @@
+//! Metadata for screen readers in SVG output.
+
+/// Labels longer than this are cut short, the full payload stays in the
+/// description.
+const MAX_LABEL_CHARS: usize = 100;
+
+/// Marks the root element of an SVG document as an image labelled `label`,
+/// with a `<title>` and a `<desc>`.
+pub fn annotate(svg: &str, label: &str, description: &str) -> String {
+    let label = escape(&truncate(label));
+    let description = escape(description);
+
+    let start = match svg.find("<svg") {
+        Some(i) => i,
+        None => return svg.to_string(),
+    };
+    let end = match svg[start..].find('>') {
+        Some(i) => start + i,
+        None => return svg.to_string(),
+    };
+
+    format!(
+        r#"{head} role="img" aria-label="{label}"><title>{label}</title><desc>{description}</desc>{tail}"#,
+        head = &svg[..end],
+        label = label,
+        description = description,
+        tail = &svg[end + 1..],
+    )
+}
+
+fn truncate(s: &str) -> String {
+    let s = s.trim();
+    if s.chars().count() <= MAX_LABEL_CHARS {
+        return s.to_string();
+    }
+
+    let mut short: String = s.chars().take(MAX_LABEL_CHARS - 1).collect();
+    short.push('…');
+    short
+}
+
+/// Escapes text for XML content and attribute values.
+pub fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Not allowed in XML 1.0 at all.
+            c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {
+                escaped.push(' ')
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,470 @@
+//! Many codes from one request, one per row of a CSV or JSON Lines body.
+//! A row holds the input, as `data` or as the fields of a payload `kind`,
+//! and may override the request headers for its code with `x-qr-` fields.
+
+use std::collections::HashSet;
+use std::error;
+use std::fmt;
+use std::iter;
+
+use crate::gen::Format;
+use crate::gen::Generator;
+use crate::payload::build_payload;
+use crate::payload::Fields;
+use crate::pdf::Page;
+use crate::pdf::Pdf;
+use crate::pdf::POINTS_PER_INCH;
+use crate::sheet::Sheet;
+use crate::sheet::Sheets;
+use crate::template::Template;
+use crate::template::MAX_CODES;
+use crate::zip::Zip;
+
+/// The largest batch, in bytes, as it is built in memory before it is sent.
+pub const MAX_BATCH_SIZE: usize = 64 << 20;
+
+/// What the codes of a batch are sent back in.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BatchOutput {
+    /// A file per code, in the format asked for, else PNG.
+    #[default]
+    Zip,
+    /// A page per code, of its printed size, or a page per sheet.
+    Pdf,
+    /// A sheet, as an SVG document.
+    Svg,
+}
+
+impl From<&str> for BatchOutput {
+    fn from(headerval: &str) -> Self {
+        match headerval.to_lowercase().as_str() {
+            "application/pdf" => Self::Pdf,
+            "image/svg+xml" => Self::Svg,
+            _ => Self::default(),
+        }
+    }
+}
+
+/// A row of the body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    /// The position of the row, from 1.
+    pub number: usize,
+    /// The line the row starts at, for errors.
+    pub line: usize,
+    pub fields: Fields,
+}
+
+impl Row {
+    /// The `x-qr-` fields, to apply over the request headers.
+    pub fn headers(&self) -> Vec<(&str, &str)> {
+        self.fields
+            .iter()
+            .filter(|(key, value)| {
+                key.get(.."x-qr-".len())
+                    .is_some_and(|prefix| prefix.eq_ignore_ascii_case("x-qr-"))
+                    && !value.is_empty()
+            })
+            .collect()
+    }
+
+    /// The input of the code: the `data` field, decoded as `gen` says, or
+    /// else the payload of the `kind` field, built from the other fields.
+    /// A payload makes `gen` follow its spec.
+    pub fn input(&self, gen: &mut Generator) -> Result<Vec<u8>, BatchError> {
+        if let Some(data) = self.fields.get("data") {
+            return gen
+                .decode_input(data.as_bytes())
+                .map(|input| input.into_owned())
+                .map_err(|e| {
+                    self.error(format!("x-qr-input-encoding: {}", e))
+                });
+        }
+
+        let kind =
+            self.fields.get("kind").map(str::trim).ok_or_else(|| {
+                self.error("missing field \"data\" or \"kind\"")
+            })?;
+        match build_payload(kind, &self.fields) {
+            Some(Ok(payload)) => {
+                payload.configure(gen);
+                Ok(payload.data.into_bytes())
+            }
+            Some(Err(e)) => Err(self.error(format!("{}: {}", kind, e))),
+            None => Err(self.error(format!("unknown kind {:?}", kind))),
+        }
+    }
+
+    /// The `caption` field, printed under the code on sheets.
+    pub fn caption(&self) -> Option<&str> {
+        self.fields
+            .get("caption")
+            .map(str::trim)
+            .filter(|caption| !caption.is_empty())
+    }
+
+    pub fn error(&self, message: impl Into<String>) -> BatchError {
+        BatchError {
+            line: Some(self.line),
+            message: message.into(),
+        }
+    }
+}
+
+/// Reads the rows of a CSV or a JSON Lines body, telling them apart by the
+/// content type, or by the body itself when it is neither.
+pub fn parse_rows(
+    content_type: Option<&str>,
+    body: &[u8],
+) -> Result<Vec<Row>, BatchError> {
+    let body = String::from_utf8_lossy(body);
+    // Spreadsheets start their CSV with a byte order mark.
+    let body = body.strip_prefix('\u{feff}').unwrap_or(&body);
+
+    let content_type = content_type
+        .and_then(|c| c.split(';').next())
+        .map(|c| c.trim().to_lowercase());
+    let rows = match content_type.as_deref() {
+        Some("text/csv") => csv(body)?,
+        Some(
+            "application/json"
+            | "application/jsonl"
+            | "application/x-jsonlines"
+            | "application/x-ndjson",
+        ) => json_lines(body)?,
+        _ if body.trim_start().starts_with('{') => json_lines(body)?,
+        _ => csv(body)?,
+    };
+
+    if rows.is_empty() {
+        return Err(BatchError {
+            line: None,
+            message: "no rows".into(),
+        });
+    }
+    if let Some(row) = rows.get(MAX_CODES) {
+        return Err(row.error(format!(
+            "at most {} codes allowed per request",
+            MAX_CODES
+        )));
+    }
+    Ok(rows)
+}
+
+/// Reads a JSON object per line. Blank lines are skipped.
+fn json_lines(body: &str) -> Result<Vec<Row>, BatchError> {
+    let mut rows = vec![];
+    for (i, line) in body.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = Fields::from_json(line).map_err(|e| BatchError {
+            line: Some(i + 1),
+            message: e.to_string(),
+        })?;
+        rows.push(Row {
+            number: rows.len() + 1,
+            line: i + 1,
+            fields,
+        });
+    }
+    Ok(rows)
+}
+
+/// Reads CSV as of RFC 4180, the first record naming the columns. Blank
+/// lines are skipped, and short records leave the last fields out.
+fn csv(body: &str) -> Result<Vec<Row>, BatchError> {
+    let mut records = records(body)?.into_iter();
+    let columns: Vec<String> = match records.next() {
+        Some((_, names)) => {
+            names.iter().map(|n| n.trim().to_string()).collect()
+        }
+        None => return Ok(vec![]),
+    };
+
+    records
+        .enumerate()
+        .map(|(i, (line, cells))| {
+            if cells.len() > columns.len() {
+                return Err(BatchError {
+                    line: Some(line),
+                    message: format!(
+                        "{} cells for {} columns",
+                        cells.len(),
+                        columns.len()
+                    ),
+                });
+            }
+            Ok(Row {
+                number: i + 1,
+                line,
+                fields: columns.iter().cloned().zip(cells).collect(),
+            })
+        })
+        .collect()
+}
+
+/// The cells of each record, with the line it starts at. Quoted cells may
+/// hold commas, line breaks and quotes, doubled.
+fn records(body: &str) -> Result<Vec<(usize, Vec<String>)>, BatchError> {
+    let mut records = vec![];
+    let mut chars = body.chars().peekable();
+    let mut line = 1;
+
+    while chars.peek().is_some() {
+        let start = line;
+        let mut cells = vec![];
+        let mut cell = String::new();
+        let mut quoted = false;
+
+        loop {
+            match chars.next() {
+                None if quoted => {
+                    return Err(BatchError {
+                        line: Some(start),
+                        message: "quoted cell is not closed".into(),
+                    })
+                }
+                None => break,
+                Some('"') if quoted => {
+                    if chars.peek() == Some(&'"') {
+                        chars.next();
+                        cell.push('"');
+                    } else {
+                        quoted = false;
+                    }
+                }
+                Some('"') if cell.is_empty() => quoted = true,
+                Some(',') if !quoted => cells.push(std::mem::take(&mut cell)),
+                Some('\r') if !quoted && chars.peek() == Some(&'\n') => {}
+                Some('\n') if !quoted => {
+                    line += 1;
+                    break;
+                }
+                Some(c) => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    cell.push(c);
+                }
+            }
+        }
+        cells.push(cell);
+
+        if cells.len() > 1 || !cells[0].trim().is_empty() {
+            records.push((start, cells));
+        }
+    }
+    Ok(records)
+}
+
+enum Output {
+    Zip(Zip, HashSet<String>),
+    Pdf(Pdf),
+    Sheets(Sheets),
+}
+
+/// The codes of the rows, as the files of a ZIP archive, the pages of a
+/// PDF document, or the labels of sheets, captioned by the `caption`
+/// fields.
+pub struct Batch {
+    output: Output,
+    /// File names with `{{field}}` placeholders, `{{row}}` being the
+    /// number of the row.
+    names: Option<Template>,
+    /// Digits of the last row number, to pad the others to.
+    width: usize,
+}
+
+impl Batch {
+    /// Lays the codes out on sheets, if any, as PDF pages if asked for and
+    /// else as SVG documents.
+    pub fn new(
+        output: BatchOutput,
+        names: Option<Template>,
+        sheet: Option<Sheet>,
+        rows: &[Row],
+    ) -> Self {
+        let output = match (output, sheet) {
+            (output, Some(sheet)) => {
+                let captions = rows.iter().any(|row| row.caption().is_some());
+                Output::Sheets(Sheets::new(
+                    sheet,
+                    output == BatchOutput::Pdf,
+                    captions,
+                ))
+            }
+            (BatchOutput::Pdf, None) => Output::Pdf(Pdf::new()),
+            (_, None) => Output::Zip(Zip::new(), HashSet::new()),
+        };
+        Self {
+            output,
+            names,
+            width: rows.len().to_string().len(),
+        }
+    }
+
+    /// Adds the code of the row, generated by `gen` with the row headers
+    /// applied.
+    pub fn add(
+        &mut self,
+        row: &Row,
+        gen: &Generator,
+    ) -> Result<(), BatchError> {
+        let mut gen = gen.clone();
+        let input = row.input(&mut gen)?;
+
+        // PDF pages and sheets draw the code in their own format, which
+        // decides whether there is a logo to make room for.
+        match &self.output {
+            // Terminal text is what an Accept naming no type of code, e.g.
+            // application/zip, comes to.
+            Output::Zip(..) if matches!(gen.format, Format::Unicode) => {
+                gen.format = Format::Png
+            }
+            Output::Zip(..) => {}
+            Output::Pdf(_) => gen.format = Format::Png,
+            Output::Sheets(sheets) => gen.format = sheets.format(),
+        }
+        gen.check_halftone()
+            .map_err(|e| row.error(format!("halftone: {}", e)))?;
+
+        let estimate = gen.estimate(&input);
+        if !estimate.fits() {
+            return Err(row.error(estimate.to_string()));
+        }
+        let code = gen.code(&input).map_err(|e| row.error(e.to_string()))?;
+        gen.check_logo(&code)
+            .map_err(|e| row.error(format!("logo: {}", e)))?;
+        gen.check_size(&code)
+            .map_err(|e| row.error(format!("size: {}", e)))?;
+
+        match &mut self.output {
+            Output::Zip(zip, names) => {
+                let name = name(self.names.as_ref(), self.width, row)?;
+                let name = format!("{}.{}", name, gen.format.extension());
+                if !names.insert(name.clone()) {
+                    return Err(
+                        row.error(format!("duplicate file name {:?}", name))
+                    );
+                }
+                let file = gen
+                    .render(&input, &code)
+                    .map_err(|e| row.error(e.to_string()))?;
+                zip.add(&name, &file);
+            }
+            Output::Pdf(pdf) => {
+                let image =
+                    gen.image(&code).map_err(|e| row.error(e.to_string()))?;
+                let scale = POINTS_PER_INCH / gen.dpi() as f64;
+                let (width, height) = (
+                    image.width() as f64 * scale,
+                    image.height() as f64 * scale,
+                );
+
+                let image = pdf.image(&image);
+                let mut page = Page::new(width, height);
+                page.draw(image, 0.0, 0.0, width, height);
+                pdf.add_page(page);
+            }
+            Output::Sheets(sheets) => sheets
+                .add(&gen, &input, &code, row.caption())
+                .map_err(|e| row.error(e.to_string()))?,
+        }
+
+        if self.size() > MAX_BATCH_SIZE {
+            return Err(row.error(format!(
+                "the batch is larger than {} MB",
+                MAX_BATCH_SIZE >> 20
+            )));
+        }
+        Ok(())
+    }
+
+    /// The bytes of the codes added so far.
+    pub fn size(&self) -> usize {
+        match &self.output {
+            Output::Zip(zip, _) => zip.size(),
+            Output::Pdf(pdf) => pdf.size(),
+            Output::Sheets(sheets) => sheets.size(),
+        }
+    }
+
+    /// The document, and what it is.
+    pub fn finish(self) -> (BatchOutput, Vec<u8>) {
+        match self.output {
+            Output::Zip(zip, _) => (BatchOutput::Zip, zip.finish()),
+            Output::Pdf(pdf) => (BatchOutput::Pdf, pdf.finish()),
+            Output::Sheets(sheets) if sheets.is_zip() => {
+                (BatchOutput::Zip, sheets.finish())
+            }
+            Output::Sheets(sheets) if sheets.is_pdf() => {
+                (BatchOutput::Pdf, sheets.finish())
+            }
+            Output::Sheets(sheets) => (BatchOutput::Svg, sheets.finish()),
+        }
+    }
+}
+
+/// The file name of the row, from the pattern, else its `name` field or
+/// its number. Characters file systems don't take become `_`.
+fn name(
+    names: Option<&Template>,
+    width: usize,
+    row: &Row,
+) -> Result<String, BatchError> {
+    let number = format!("{:0width$}", row.number, width = width);
+    let name = match names {
+        Some(names) => {
+            let fields: Fields = row
+                .fields
+                .iter()
+                .chain(iter::once(("row", number.as_str())))
+                .collect();
+            let mut names = names
+                .render(&fields)
+                .map_err(|e| row.error(format!("file name: {}", e)))?;
+            if names.len() != 1 {
+                return Err(row.error("file name: sequences are not allowed"));
+            }
+            names.remove(0).1
+        }
+        None => row.fields.get("name").unwrap_or(&number).to_string(),
+    };
+
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_control() || "/\\:*?\"<>|".contains(c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    if name.is_empty() || name.chars().all(|c| c == '.') {
+        return Err(row.error(format!("invalid file name {:?}", name)));
+    }
+    Ok(name)
+}
+
+/// A batch that couldn't be read or a row whose code couldn't be
+/// generated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchError {
+    /// The line of the row, unless the error is about the whole body.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl error::Error for BatchError {}
@@ -0,0 +1,80 @@
+//! How much of a code some input fills, to tell before generating whether it
+//! fits the selected version.
+
+use qrcode::bits::Bits;
+use qrcode::EcLevel;
+use qrcode::Version;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub bytes: usize,
+    /// The data bits needed in the most compact encoding, `None` if the
+    /// version can't encode the input at all, e.g. bytes in a small micro
+    /// version.
+    pub bits: Option<usize>,
+    /// The data bits the version holds at the error correction level.
+    pub capacity: usize,
+    pub version: Version,
+    pub ec_level: EcLevel,
+}
+
+impl Estimate {
+    /// Estimates for the given version, or else for the smallest normal
+    /// version that holds the input.
+    pub fn new(
+        input: &[u8],
+        version: Option<Version>,
+        ec_level: EcLevel,
+    ) -> Self {
+        if let Some(version) = version {
+            return Self::measure(input, version, ec_level);
+        }
+
+        (1..=40)
+            .map(|n| Self::measure(input, Version::Normal(n), ec_level))
+            .find(Self::fits)
+            .unwrap_or_else(|| {
+                Self::measure(input, Version::Normal(40), ec_level)
+            })
+    }
+
+    fn measure(input: &[u8], version: Version, ec_level: EcLevel) -> Self {
+        let mut bits = Bits::new(version);
+        let needed = bits.push_optimal_data(input).ok().map(|_| bits.len());
+
+        Self {
+            bytes: input.len(),
+            bits: needed,
+            capacity: bits.max_len(ec_level).unwrap_or(0),
+            version,
+            ec_level,
+        }
+    }
+
+    pub fn fits(&self) -> bool {
+        matches!(self.bits, Some(bits) if bits <= self.capacity)
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let version = match self.version {
+            Version::Normal(n) => n.to_string(),
+            Version::Micro(n) => format!("M{}", n),
+        };
+
+        match self.bits {
+            Some(bits) => write!(
+                f,
+                "{} bytes take {} bits, version {} at level {:?} holds {}",
+                self.bytes, bits, version, self.ec_level, self.capacity
+            ),
+            None => write!(
+                f,
+                "{} bytes can't be encoded in version {}",
+                self.bytes, version
+            ),
+        }
+    }
+}
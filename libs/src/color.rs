@@ -0,0 +1,100 @@
+use image::Rgba;
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A validated color, shared by all the renderers.
+///
+/// Accepts hex with or without the leading `#` (`rgb`, `rgba`, `rrggbb`,
+/// `rrggbbaa`), CSS functions like `rgb()` and `hsl()`, and CSS color names.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Color(csscolorparser::Color);
+
+impl Color {
+    pub fn from_rgba8(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self(csscolorparser::Color::from_rgba8(r, g, b, a))
+    }
+
+    pub fn black() -> Self {
+        Self::from_rgba8(0, 0, 0, 255)
+    }
+
+    pub fn white() -> Self {
+        Self::from_rgba8(255, 255, 255, 255)
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.0.a
+    }
+
+    pub fn is_opaque(&self) -> bool {
+        self.0.a >= 1.0
+    }
+
+    /// The color as it can be used in an SVG `fill` attribute.
+    pub fn to_svg(&self) -> String {
+        self.0.to_hex_string()
+    }
+
+    /// The color as an sRGB encoded raster pixel, i.e. the same values that
+    /// end up in the SVG `fill`.
+    pub fn to_rgba(&self) -> Rgba<u8> {
+        Rgba(self.0.to_rgba8())
+    }
+
+    /// Relative luminance as defined by WCAG 2.x.
+    pub fn luminance(&self) -> f64 {
+        let (r, g, b, _) = self.0.to_linear_rgba();
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    }
+
+    /// Alpha-composites this color over an opaque `bg`.
+    pub fn over(&self, bg: &Self) -> Self {
+        let (fg, bg) = (&self.0, &bg.0);
+        let a = fg.a;
+        Self(csscolorparser::Color {
+            r: fg.r * a + bg.r * (1.0 - a),
+            g: fg.g * a + bg.g * (1.0 - a),
+            b: fg.b * a + bg.b * (1.0 - a),
+            a: 1.0,
+        })
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_svg())
+    }
+}
+
+impl FromStr for Color {
+    type Err = ColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<csscolorparser::Color>()
+            .map(Self)
+            .map_err(|reason| ColorError {
+                input: s.to_string(),
+                reason,
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorError {
+    input: String,
+    reason: csscolorparser::ParseColorError,
+}
+
+impl fmt::Display for ColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid color {:?}: {} (expected hex like 336699 or 336699cc, \
+             rgb(), hsl() or a CSS color name)",
+            self.input, self.reason
+        )
+    }
+}
+
+impl error::Error for ColorError {}
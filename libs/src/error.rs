@@ -0,0 +1,30 @@
+use std::error;
+use std::fmt;
+
+/// Returned when parsing one of the named options (shapes, styles, ...).
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidValue {
+    pub value: String,
+    pub expected: &'static str,
+}
+
+impl InvalidValue {
+    pub fn new(value: &str, expected: &'static str) -> Self {
+        Self {
+            value: value.to_string(),
+            expected,
+        }
+    }
+}
+
+impl fmt::Display for InvalidValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid value {:?}: expected {}",
+            self.value, self.expected
+        )
+    }
+}
+
+impl error::Error for InvalidValue {}
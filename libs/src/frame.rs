@@ -0,0 +1,739 @@
+//! Frames around the code with a call-to-action caption. Everything is laid
+//! out in module units, with the caption converted to outlines so that SVG
+//! output doesn't depend on the fonts installed where it is viewed.
+
+use ab_glyph::Font;
+use ab_glyph::FontRef;
+use ab_glyph::GlyphId;
+use ab_glyph::OutlineCurve;
+use ab_glyph_rasterizer::point;
+use ab_glyph_rasterizer::Rasterizer;
+use image::imageops;
+use image::Rgba;
+use image::RgbaImage;
+use lazy_static::lazy_static;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::color::Color;
+use crate::error::InvalidValue;
+use crate::render::num;
+use crate::svg::nest;
+
+pub const DEFAULT_CAPTION: &str = "SCAN ME";
+
+/// The width of the frame lines, in modules.
+const THICKNESS: f64 = 1.0;
+
+/// The corner radius of `Frame::Rounded` and `Frame::Bubble`, in modules.
+const RADIUS: f64 = 2.0;
+
+/// How far the `Frame::Banner` ribbon sticks out on each side, in modules.
+const RIBBON_OVERHANG: f64 = 2.0;
+
+/// The space between the code and the `Frame::Bubble` speech bubble, which
+/// its pointer spans, in modules.
+const BUBBLE_GAP: f64 = 1.0;
+
+/// The caption glyphs take this share of the caption band height.
+const CAPTION_HEIGHT: f64 = 0.5;
+
+/// Captions that would be drawn smaller than this share of their full size
+/// on one line are wrapped onto two lines of this size.
+const WRAPPED_SIZE: f64 = 0.7;
+
+/// Captions aren't drawn smaller than this share of their full size, but
+/// cut short.
+const MIN_SIZE: f64 = 0.35;
+
+/// Bezier handle length for a quarter circle of radius 1.
+const KAPPA: f64 = 0.552_284_75;
+
+lazy_static! {
+    /// DejaVu Sans Bold, cut down by `fonts/subset.py` to Latin, Greek,
+    /// Cyrillic and common symbols, see `fonts/LICENSE`.
+    static ref FONT: FontRef<'static> = FontRef::try_from_slice(
+        include_bytes!("../fonts/DejaVuSans-Bold-Subset.ttf")
+    )
+    .expect("embedded font is valid");
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Frame {
+    /// A line around the code with the caption outside of it.
+    Border,
+    /// A filled box with rounded corners, the caption inside.
+    Rounded,
+    /// A speech bubble pointing at the code.
+    Bubble,
+    /// A ribbon with notched ends.
+    Banner,
+}
+
+impl FromStr for Frame {
+    type Err = InvalidValue;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "border" => Ok(Self::Border),
+            "rounded" => Ok(Self::Rounded),
+            "bubble" => Ok(Self::Bubble),
+            "banner" => Ok(Self::Banner),
+            _ => Err(InvalidValue::new(s, "border, rounded, bubble or banner")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CaptionPosition {
+    Top,
+    #[default]
+    Bottom,
+}
+
+impl FromStr for CaptionPosition {
+    type Err = InvalidValue;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "top" | "above" => Ok(Self::Top),
+            "bottom" | "below" => Ok(Self::Bottom),
+            _ => Err(InvalidValue::new(s, "top or bottom")),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FrameStyle {
+    pub frame: Frame,
+    pub caption: String,
+    pub position: CaptionPosition,
+    /// The frame, and the caption of `Frame::Border`.
+    pub color: Color,
+    /// The caption drawn on a filled frame, and the space around the frame.
+    pub background: Color,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Segment {
+    Move(f64, f64),
+    Line(f64, f64),
+    Quad(f64, f64, f64, f64),
+    Cubic(f64, f64, f64, f64, f64, f64),
+    Close,
+}
+
+/// Filled outlines, in module units. Overlapping subpaths wound in opposite
+/// directions cut holes, in SVG (nonzero fill rule) and when rasterized
+/// alike.
+#[derive(Debug, Clone, Default)]
+struct Path(Vec<Segment>);
+
+impl Path {
+    /// A rectangle with rounded corners, clockwise on screen unless
+    /// `reverse`.
+    fn rect(&mut self, x: f64, y: f64, w: f64, h: f64, r: f64, reverse: bool) {
+        let r = r.min(w / 2.0).min(h / 2.0);
+        let k = r * (1.0 - KAPPA);
+        let (x1, y1) = (x + w, y + h);
+
+        let corners = [
+            // Each corner: the point before, the handles and the point after.
+            [(x1 - r, y), (x1 - k, y), (x1, y + k), (x1, y + r)],
+            [(x1, y1 - r), (x1, y1 - k), (x1 - k, y1), (x1 - r, y1)],
+            [(x + r, y1), (x + k, y1), (x, y1 - k), (x, y1 - r)],
+            [(x, y + r), (x, y + k), (x + k, y), (x + r, y)],
+        ];
+
+        let mut points = vec![];
+        for c in corners.iter() {
+            points.push(Segment::Line(c[0].0, c[0].1));
+            if r > 0.0 {
+                let [_, a, b, p] = *c;
+                points.push(Segment::Cubic(a.0, a.1, b.0, b.1, p.0, p.1));
+            }
+        }
+
+        if reverse {
+            // Walk the same outline backwards.
+            let mut ends: Vec<(f64, f64)> = points
+                .iter()
+                .map(|s| match *s {
+                    Segment::Line(x, y) | Segment::Cubic(_, _, _, _, x, y) => {
+                        (x, y)
+                    }
+                    _ => unreachable!(),
+                })
+                .collect();
+            ends.rotate_right(1);
+
+            let mut reversed = vec![];
+            for (i, s) in points.iter().enumerate().rev() {
+                let (ex, ey) = ends[i];
+                reversed.push(match *s {
+                    Segment::Cubic(ax, ay, bx, by, _, _) => {
+                        Segment::Cubic(bx, by, ax, ay, ex, ey)
+                    }
+                    _ => Segment::Line(ex, ey),
+                });
+            }
+            points = reversed;
+        }
+
+        let start = match points.last() {
+            Some(Segment::Line(x, y))
+            | Some(Segment::Cubic(_, _, _, _, x, y)) => (*x, *y),
+            _ => return,
+        };
+        self.0.push(Segment::Move(start.0, start.1));
+        self.0.extend(points);
+        self.0.push(Segment::Close);
+    }
+
+    fn polygon(&mut self, points: &[(f64, f64)]) {
+        for (i, &(x, y)) in points.iter().enumerate() {
+            self.0.push(if i == 0 {
+                Segment::Move(x, y)
+            } else {
+                Segment::Line(x, y)
+            });
+        }
+        self.0.push(Segment::Close);
+    }
+
+    /// Mirrors the path vertically within a height of `h`.
+    fn flip(&mut self, h: f64) {
+        for s in self.0.iter_mut() {
+            *s = match *s {
+                Segment::Move(x, y) => Segment::Move(x, h - y),
+                Segment::Line(x, y) => Segment::Line(x, h - y),
+                Segment::Quad(ax, ay, x, y) => {
+                    Segment::Quad(ax, h - ay, x, h - y)
+                }
+                Segment::Cubic(ax, ay, bx, by, x, y) => {
+                    Segment::Cubic(ax, h - ay, bx, h - by, x, h - y)
+                }
+                Segment::Close => Segment::Close,
+            };
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn to_svg(&self) -> String {
+        let mut d = String::new();
+        for s in &self.0 {
+            match *s {
+                Segment::Move(x, y) => write!(d, "M{} {}", num(x), num(y)),
+                Segment::Line(x, y) => write!(d, "L{} {}", num(x), num(y)),
+                Segment::Quad(ax, ay, x, y) => {
+                    write!(d, "Q{} {} {} {}", num(ax), num(ay), num(x), num(y))
+                }
+                Segment::Cubic(ax, ay, bx, by, x, y) => write!(
+                    d,
+                    "C{} {} {} {} {} {}",
+                    num(ax),
+                    num(ay),
+                    num(bx),
+                    num(by),
+                    num(x),
+                    num(y)
+                ),
+                Segment::Close => write!(d, "Z"),
+            }
+            .unwrap();
+        }
+        d
+    }
+
+    /// Blends `color` into `image` by the anti-aliased coverage of the path
+    /// at `scale` pixels per module.
+    fn fill(&self, image: &mut RgbaImage, scale: f64, color: &Color) {
+        let mut raster =
+            Rasterizer::new(image.width() as usize, image.height() as usize);
+        let p = |x: f64, y: f64| point((x * scale) as f32, (y * scale) as f32);
+
+        let (mut start, mut current) = ((0.0, 0.0), (0.0, 0.0));
+        for s in &self.0 {
+            let (cx, cy) = current;
+            current = match *s {
+                Segment::Move(x, y) => {
+                    start = (x, y);
+                    (x, y)
+                }
+                Segment::Line(x, y) => {
+                    raster.draw_line(p(cx, cy), p(x, y));
+                    (x, y)
+                }
+                Segment::Quad(ax, ay, x, y) => {
+                    raster.draw_quad(p(cx, cy), p(ax, ay), p(x, y));
+                    (x, y)
+                }
+                Segment::Cubic(ax, ay, bx, by, x, y) => {
+                    raster.draw_cubic(p(cx, cy), p(ax, ay), p(bx, by), p(x, y));
+                    (x, y)
+                }
+                Segment::Close => {
+                    raster.draw_line(p(cx, cy), p(start.0, start.1));
+                    start
+                }
+            };
+        }
+
+        let color = color.to_rgba();
+        raster.for_each_pixel_2d(|x, y, coverage| {
+            if coverage > 0.0 {
+                let px = image.get_pixel_mut(x, y);
+                *px = blend(&color, px, coverage.min(1.0) as f64);
+            }
+        });
+    }
+}
+
+/// Mixes `fg` into `bg` by `coverage`.
+fn blend(fg: &Rgba<u8>, bg: &Rgba<u8>, coverage: f64) -> Rgba<u8> {
+    let mut out = *bg;
+    for (o, (f, b)) in out.0.iter_mut().zip(fg.0.iter().zip(bg.0.iter())) {
+        *o =
+            (*f as f64 * coverage + *b as f64 * (1.0 - coverage)).round() as u8;
+    }
+    out
+}
+
+/// Lays out a line of text in font units: the glyphs at their pen
+/// positions, and the advance of the whole line.
+fn layout(text: &str) -> (Vec<(GlyphId, f64)>, f64) {
+    let font = &*FONT;
+    let mut glyphs = vec![];
+    let mut pen = 0.0;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(prev) = previous {
+            pen += font.kern_unscaled(prev, id) as f64;
+        }
+        glyphs.push((id, pen));
+        pen += font.h_advance_unscaled(id) as f64;
+        previous = Some(id);
+    }
+    (glyphs, pen)
+}
+
+/// Breaks `text` into the lines it is drawn in, for a box `width` font
+/// units wide at full size. Returns the lines and their size as a share of
+/// the full size.
+///
+/// Text that has to shrink below `WRAPPED_SIZE` to fit on one line is
+/// wrapped onto two, split at the space that makes the longer line the
+/// shortest. Lines that would still be drawn smaller than `MIN_SIZE` are
+/// cut short with an ellipsis.
+fn wrap(text: &str, width: f64) -> (Vec<String>, f64) {
+    let advance = |line: &str| layout(line).1;
+    let size = |lines: &[String], full: f64| {
+        let longest = lines.iter().map(|l| advance(l)).fold(0.0, f64::max);
+        full.min(width / longest)
+    };
+
+    let text = text.trim();
+    let mut lines = vec![text.to_string()];
+    let mut full = 1.0;
+    if size(&lines, full) < WRAPPED_SIZE {
+        let split = text
+            .match_indices(' ')
+            .map(|(i, _)| {
+                vec![
+                    text[..i].trim_end().to_string(),
+                    text[i..].trim_start().to_string(),
+                ]
+            })
+            .filter(|split| split.iter().all(|l| !l.is_empty()))
+            .min_by(|a, b| size(b, 1.0).total_cmp(&size(a, 1.0)));
+        if let Some(split) = split {
+            if size(&split, WRAPPED_SIZE) > size(&lines, full) {
+                lines = split;
+                full = WRAPPED_SIZE;
+            }
+        }
+    }
+
+    for line in &mut lines {
+        let chars: Vec<char> = line.chars().collect();
+        let cut = |n: usize| {
+            let kept: String = chars[..n].iter().collect();
+            format!("{}…", kept.trim_end())
+        };
+        let fits = |line: &str| full.min(width / advance(line)) >= MIN_SIZE;
+        if fits(line) {
+            continue;
+        }
+        // The most characters that fit with the ellipsis.
+        let (mut fit, mut over) = (0, chars.len());
+        while over - fit > 1 {
+            let n = (fit + over) / 2;
+            if fits(&cut(n)) {
+                fit = n;
+            } else {
+                over = n;
+            }
+        }
+        *line = cut(fit);
+    }
+
+    let size = size(&lines, full);
+    (lines, size)
+}
+
+/// Appends the outlines of `text`, scaled to fit `width` by `height` and
+/// centered in the box at `(x, y)`, wrapped or cut short if it is long.
+fn caption(
+    path: &mut Path,
+    text: &str,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+) {
+    let font = &*FONT;
+    let units = font.units_per_em().unwrap_or(1000.0) as f64;
+
+    // Cap letters fill CAPTION_HEIGHT of the band at full size.
+    let cap = font
+        .outline(font.glyph_id('H'))
+        .map(|o| o.bounds.min.y as f64)
+        .unwrap_or(units * 0.73);
+    let full = height * CAPTION_HEIGHT / cap;
+    let (lines, size) = wrap(text, width / full);
+    let scale = full * size;
+
+    // Lay out in font units first, y up, each line a share of the height
+    // apart.
+    let gap = height / lines.len() as f64;
+    let mut outlines = vec![];
+    let (mut top, mut bottom) = (f64::MIN, f64::MAX);
+    for (i, line) in lines.iter().enumerate() {
+        let (glyphs, advance) = layout(line);
+        let left = x + (width - advance * scale) / 2.0;
+        let drop = i as f64 * gap / scale;
+        for (id, pen) in glyphs {
+            if let Some(o) = font.outline(id) {
+                top = top.max(o.bounds.min.y as f64 - drop);
+                bottom = bottom.min(o.bounds.max.y as f64 - drop);
+                outlines.push((o, left + pen * scale, i as f64 * gap));
+            }
+        }
+    }
+    if outlines.is_empty() {
+        return;
+    }
+
+    // Centers the ink vertically and each line's advance horizontally.
+    let baseline = y + height / 2.0 + (top + bottom) / 2.0 * scale;
+    let p = |pt: ab_glyph::Point, left: f64, drop: f64| {
+        (
+            left + pt.x as f64 * scale,
+            baseline + drop - pt.y as f64 * scale,
+        )
+    };
+
+    for (outline, left, drop) in outlines {
+        let p = |pt| p(pt, left, drop);
+        let mut current = None;
+        for curve in &outline.curves {
+            let (start, end) = match *curve {
+                OutlineCurve::Line(a, b) => (a, b),
+                OutlineCurve::Quad(a, _, b) => (a, b),
+                OutlineCurve::Cubic(a, _, _, b) => (a, b),
+            };
+
+            if current != Some(start) {
+                if current.is_some() {
+                    path.0.push(Segment::Close);
+                }
+                let (sx, sy) = p(start);
+                path.0.push(Segment::Move(sx, sy));
+            }
+
+            path.0.push(match *curve {
+                OutlineCurve::Line(_, b) => {
+                    let (x, y) = p(b);
+                    Segment::Line(x, y)
+                }
+                OutlineCurve::Quad(_, c, b) => {
+                    let ((cx, cy), (x, y)) = (p(c), p(b));
+                    Segment::Quad(cx, cy, x, y)
+                }
+                OutlineCurve::Cubic(_, c1, c2, b) => {
+                    let (c1, c2, (x, y)) = (p(c1), p(c2), p(b));
+                    Segment::Cubic(c1.0, c1.1, c2.0, c2.1, x, y)
+                }
+            });
+            current = Some(end);
+        }
+        if current.is_some() {
+            path.0.push(Segment::Close);
+        }
+    }
+}
+
+/// A line of text as filled outlines, like the frame captions, for the
+/// captions under the codes of a label sheet.
+#[derive(Debug, Clone, Default)]
+pub struct Text(Path);
+
+impl Text {
+    /// Fits `text` into the box at `(x, y)` of `width` by `height`, y down.
+    pub fn new(text: &str, x: f64, y: f64, width: f64, height: f64) -> Self {
+        let mut path = Path::default();
+        caption(&mut path, text, x, y, width, height);
+        Self(path)
+    }
+
+    pub fn to_svg(&self) -> String {
+        self.0.to_svg()
+    }
+
+    /// The outlines as PDF path operators, filled, on a page of `height`,
+    /// PDF counting y up.
+    pub fn to_pdf(&self, height: f64) -> String {
+        let mut path = self.0.clone();
+        path.flip(height);
+
+        let mut ops = String::new();
+        let (mut start, mut current) = ((0.0, 0.0), (0.0, 0.0));
+        for s in &path.0 {
+            match *s {
+                Segment::Move(x, y) => {
+                    write!(ops, "{} {} m ", num(x), num(y)).unwrap();
+                    start = (x, y);
+                    current = (x, y);
+                }
+                Segment::Line(x, y) => {
+                    write!(ops, "{} {} l ", num(x), num(y)).unwrap();
+                    current = (x, y);
+                }
+                Segment::Quad(ax, ay, x, y) => {
+                    // PDF has cubic curves only, the same with handles at
+                    // two thirds towards the control point.
+                    let (cx, cy) = current;
+                    let c1 = (
+                        cx + (ax - cx) * 2.0 / 3.0,
+                        cy + (ay - cy) * 2.0 / 3.0,
+                    );
+                    let c2 =
+                        (x + (ax - x) * 2.0 / 3.0, y + (ay - y) * 2.0 / 3.0);
+                    write!(
+                        ops,
+                        "{} {} {} {} {} {} c ",
+                        num(c1.0),
+                        num(c1.1),
+                        num(c2.0),
+                        num(c2.1),
+                        num(x),
+                        num(y)
+                    )
+                    .unwrap();
+                    current = (x, y);
+                }
+                Segment::Cubic(ax, ay, bx, by, x, y) => {
+                    write!(
+                        ops,
+                        "{} {} {} {} {} {} c ",
+                        num(ax),
+                        num(ay),
+                        num(bx),
+                        num(by),
+                        num(x),
+                        num(y)
+                    )
+                    .unwrap();
+                    current = (x, y);
+                }
+                Segment::Close => {
+                    ops.push_str("h ");
+                    current = start;
+                }
+            }
+        }
+        if !ops.is_empty() {
+            ops.push_str("f\n");
+        }
+        ops
+    }
+}
+
+/// Where the code, the frame and the caption go, in module units.
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub width: usize,
+    pub height: usize,
+    pub code_x: usize,
+    pub code_y: usize,
+    code_width: usize,
+    frame: Path,
+    caption: Path,
+    caption_on_frame: bool,
+}
+
+impl Layout {
+    /// Lays out a frame around a code `code_width` modules wide, quiet zone
+    /// included.
+    pub fn new(style: &FrameStyle, code_width: usize) -> Self {
+        let n = code_width as f64;
+        let t = THICKNESS;
+        let band = (n * 0.2).round().max(4.0);
+
+        let mut frame = Path::default();
+        // The caption box: (x, y, width, height).
+        let (w, h, code_x, code_y, text_box, caption_on_frame) = match style
+            .frame
+        {
+            Frame::Border => {
+                let (w, h) = (n + 2.0 * t, n + 2.0 * t + band);
+                frame.rect(0.0, 0.0, w, n + 2.0 * t, 0.0, false);
+                frame.rect(t, t, n, n, 0.0, true);
+                (w, h, t, t, (t, n + 2.0 * t, n, band), false)
+            }
+
+            Frame::Rounded => {
+                let (w, h) = (n + 2.0 * t, n + t + band);
+                frame.rect(0.0, 0.0, w, h, RADIUS, false);
+                (w, h, t, t, (t, n + t, n, band), true)
+            }
+
+            Frame::Bubble => {
+                let (w, h) = (n, n + BUBBLE_GAP + band);
+                let top = n + BUBBLE_GAP;
+                frame.rect(t, top, n - 2.0 * t, band, RADIUS, false);
+                let c = n / 2.0;
+                frame.polygon(&[
+                    (c - 1.0, top + 0.01),
+                    (c, n),
+                    (c + 1.0, top + 0.01),
+                ]);
+                let text = (t + RADIUS / 2.0, top, n - 2.0 * t - RADIUS, band);
+                (w, h, 0.0, 0.0, text, true)
+            }
+
+            Frame::Banner => {
+                let o = RIBBON_OVERHANG;
+                let (w, h) = (n + 2.0 * o, n + band);
+                let (top, bottom, mid) = (n, n + band, n + band / 2.0);
+                frame.polygon(&[
+                    (0.0, top),
+                    (w, top),
+                    (w - o / 2.0, mid),
+                    (w, bottom),
+                    (0.0, bottom),
+                    (o / 2.0, mid),
+                ]);
+                (w, h, o, 0.0, (o, top, n, band), true)
+            }
+        };
+
+        let (tx, ty, tw, th) = text_box;
+        let mut code_y = code_y;
+        let mut text_y = ty;
+        if style.position == CaptionPosition::Top {
+            frame.flip(h);
+            code_y = h - code_y - n;
+            text_y = h - ty - th;
+        }
+
+        let mut text = Path::default();
+        caption(&mut text, &style.caption, tx, text_y, tw, th);
+
+        Self {
+            width: w as usize,
+            height: h as usize,
+            code_x: code_x as usize,
+            code_y: code_y as usize,
+            code_width,
+            frame,
+            caption: text,
+            caption_on_frame,
+        }
+    }
+
+    fn caption_color<'a>(&self, style: &'a FrameStyle) -> &'a Color {
+        if self.caption_on_frame {
+            &style.background
+        } else {
+            &style.color
+        }
+    }
+
+    /// Wraps the SVG of the code, a complete document, into the frame.
+    pub fn svg(
+        &self,
+        style: &FrameStyle,
+        code: &str,
+        module_size: u32,
+    ) -> String {
+        let mut svg = format!(
+            concat!(
+                r#"<?xml version="1.0" standalone="yes"?>"#,
+                r#"<svg xmlns="http://www.w3.org/2000/svg""#,
+                r#" version="1.1" width="{pw}" height="{ph}""#,
+                r#" viewBox="0 0 {w} {h}">"#,
+                r#"<rect x="0" y="0" width="{w}" height="{h}" fill="{bg}"/>"#,
+                r#"<path fill="{fg}" d="{d}"/>"#,
+            ),
+            pw = self.width as u32 * module_size,
+            ph = self.height as u32 * module_size,
+            w = self.width,
+            h = self.height,
+            bg = style.background.to_svg(),
+            fg = style.color.to_svg(),
+            d = self.frame.to_svg(),
+        );
+
+        let (x, y, n) = (
+            self.code_x as f64,
+            self.code_y as f64,
+            self.code_width as f64,
+        );
+        svg.push_str(&nest(code, x, y, n, n));
+
+        if !self.caption.is_empty() {
+            write!(
+                svg,
+                r#"<path fill="{}" d="{}"/>"#,
+                self.caption_color(style).to_svg(),
+                self.caption.to_svg(),
+            )
+            .unwrap();
+        }
+
+        svg.push_str("</svg>");
+        svg
+    }
+
+    /// Draws the frame around the image of the code.
+    pub fn raster(
+        &self,
+        style: &FrameStyle,
+        code: &RgbaImage,
+        module_size: u32,
+    ) -> RgbaImage {
+        let scale = module_size as f64;
+        let mut image = RgbaImage::from_pixel(
+            self.width as u32 * module_size,
+            self.height as u32 * module_size,
+            style.background.to_rgba(),
+        );
+
+        self.frame.fill(&mut image, scale, &style.color);
+        imageops::replace(
+            &mut image,
+            code,
+            self.code_x as u32 * module_size,
+            self.code_y as u32 * module_size,
+        );
+        self.caption
+            .fill(&mut image, scale, self.caption_color(style));
+
+        image
+    }
+}
@@ -1,30 +1,130 @@
-use csscolorparser::Color;
 use image::codecs::jpeg::JpegEncoder;
+use image::codecs::jpeg::PixelDensity;
 use image::codecs::png::PngEncoder;
+use image::imageops;
+use image::imageops::FilterType;
 use image::ColorType;
 use image::EncodableLayout;
 use image::Rgba;
-use qrcode::render::svg;
+use image::RgbaImage;
 use qrcode::render::unicode;
+use qrcode::render::Pixel;
+use qrcode::render::Renderer;
 use qrcode::types::QrError;
 use qrcode::EcLevel;
 use qrcode::QrCode;
 use qrcode::QrResult;
 use qrcode::Version;
+use std::borrow::Cow;
+use std::error;
+use std::fmt;
+
+use crate::accessibility;
+use crate::capacity::Estimate;
+use crate::color::Color;
+use crate::error::InvalidValue;
+use crate::frame::CaptionPosition;
+use crate::frame::Frame;
+use crate::frame::FrameStyle;
+use crate::frame::Layout;
+use crate::frame::DEFAULT_CAPTION;
+use crate::gradient::Gradient;
+use crate::halftone::Halftone;
+use crate::halftone::HalftoneError;
+use crate::input::InputEncoding;
+use crate::input::InputError;
+use crate::lint;
+use crate::lint::Warning;
+use crate::logo;
+use crate::logo::Logo;
+use crate::logo::LogoError;
+use crate::logo::DEFAULT_LOGO_SIZE;
+use crate::matrix::Area;
+use crate::matrix::Matrix;
+use crate::meta;
+use crate::physical::PhysicalSize;
+use crate::physical::DEFAULT_DPI;
+use crate::render::Canvas;
+use crate::render::EyeShape;
+use crate::render::Shape;
+use crate::render::Style;
+use crate::render::SUPERSAMPLING;
+use crate::svg;
+use crate::transform::Rotation;
+use crate::transform::Transform;
+
+/// The largest module, in pixels, and quiet zone, in modules, taken. They
+/// don't bound the image on their own, [`MAX_SAMPLES`] does.
+pub const MAX_MODULE_SIZE: u32 = 100;
+pub const MAX_MARGIN: u32 = 100;
+
+/// The most samples taken to draw an image, [`SUPERSAMPLING`]² for each of
+/// its pixels, or characters for text: 4096 × 4096 pixels, 64 MB of RGBA.
+pub const MAX_SAMPLES: u64 = 1 << 28;
+
+/// Centers `image` on a square of `size` pixels filled with `background`,
+/// scaling it down first if it is larger.
+fn fit(image: RgbaImage, size: u32, background: Rgba<u8>) -> RgbaImage {
+    if image.dimensions() == (size, size) {
+        return image;
+    }
+
+    let image = if image.width() > size || image.height() > size {
+        let scale = size as f64 / image.width().max(image.height()) as f64;
+        let width = ((image.width() as f64 * scale).round() as u32).max(1);
+        let height = ((image.height() as f64 * scale).round() as u32).max(1);
+        imageops::resize(&image, width, height, FilterType::Nearest)
+    } else {
+        image
+    };
+
+    let mut canvas = RgbaImage::from_pixel(size, size, background);
+    let x = (size - image.width()) / 2;
+    let y = (size - image.height()) / 2;
+    imageops::overlay(&mut canvas, &image, x, y);
+    canvas
+}
 
-#[derive(Debug, Clone, Copy)]
+/// Parses a module size in pixels, from 1 to [`MAX_MODULE_SIZE`].
+pub fn parse_module_size(s: &str) -> Result<u32, InvalidValue> {
+    s.trim()
+        .parse()
+        .ok()
+        .filter(|size| (1..=MAX_MODULE_SIZE).contains(size))
+        .ok_or_else(|| InvalidValue::new(s, "a number of pixels from 1 to 100"))
+}
+
+/// Parses a quiet zone width in modules, from 0 to [`MAX_MARGIN`].
+pub fn parse_margin(s: &str) -> Result<u32, InvalidValue> {
+    s.trim()
+        .parse()
+        .ok()
+        .filter(|margin| *margin <= MAX_MARGIN)
+        .ok_or_else(|| {
+            InvalidValue::new(s, "a number of modules from 0 to 100")
+        })
+}
+
+#[derive(Debug, Clone, Copy, Default)]
 pub enum Format {
     Svg,
     Html,
+    #[default]
     Unicode,
     PlainText,
     Png,
     Jpeg,
 }
 
-impl Default for Format {
-    fn default() -> Self {
-        Self::Unicode
+impl Format {
+    /// The file extension of the output, SVG for HTML.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Svg | Self::Html => "svg",
+            Self::Unicode | Self::PlainText => "txt",
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+        }
     }
 }
 
@@ -47,18 +147,13 @@ impl From<&String> for Format {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub enum VersionType {
     NormalVersion,
+    #[default]
     MicroVersion,
 }
 
-impl Default for VersionType {
-    fn default() -> Self {
-        Self::MicroVersion
-    }
-}
-
 impl From<&str> for VersionType {
     fn from(string: &str) -> Self {
         match string {
@@ -69,7 +164,7 @@ impl From<&str> for VersionType {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct Generator {
     pub format: Format,
 
@@ -82,8 +177,9 @@ pub struct Generator {
     pub max_width: Option<u32>,
     pub max_height: Option<u32>,
 
-    pub dark_color: Option<String>,
-    pub light_color: Option<String>,
+    pub dark_color: Option<Color>,
+    pub light_color: Option<Color>,
+    pub dark_gradient: Option<Gradient>,
 
     pub version_type: VersionType,
     pub version_number: Option<i16>,
@@ -91,19 +187,110 @@ pub struct Generator {
     pub error_correction_level: Option<EcLevel>,
 
     pub quiet_zone: Option<bool>,
+
+    pub srgb: Option<bool>,
+
+    pub module_size: Option<u32>,
+    pub margin: Option<u32>,
+
+    pub physical_size: Option<PhysicalSize>,
+    pub dpi: Option<u32>,
+
+    pub shape: Option<Shape>,
+
+    pub eye_shape: Option<EyeShape>,
+    pub eye_dot_shape: Option<EyeShape>,
+    pub eye_color: Option<Color>,
+
+    pub logo: Option<Logo>,
+    /// The logo width as a share of the code width.
+    pub logo_size: Option<f64>,
+
+    pub frame: Option<Frame>,
+    pub frame_text: Option<String>,
+    pub frame_position: Option<CaptionPosition>,
+    pub frame_color: Option<Color>,
+
+    pub halftone: Option<Halftone>,
+
+    pub invert: Option<bool>,
+    pub rotation: Option<Rotation>,
+    pub mirror: Option<bool>,
+
+    /// Describes the SVG output for screen readers, e.g. "Link to our
+    /// menu". Implies `accessible`.
+    pub alt_text: Option<String>,
+    /// Whether the SVG output is labelled with the encoded data, unless
+    /// `alt_text` is given.
+    pub accessible: Option<bool>,
+
+    /// Whether the SVG output has no fixed size and scales to fit instead.
+    pub scalable: Option<bool>,
+
+    /// How the input is encoded, for the servers to decode it first.
+    pub input_encoding: Option<InputEncoding>,
 }
 
 impl Generator {
-    pub fn generate(&self, input: &[u8]) -> QrResult<Vec<u8>> {
+    pub fn code(&self, input: &[u8]) -> QrResult<QrCode> {
+        if self.error_correction_level.is_some()
+            || (self.logo.is_none() && self.halftone.is_none())
+        {
+            return self.code_with(input, self.error_correction_level);
+        }
+
+        // The picture around the dots throws some scanners off, so leave as
+        // much room as possible to recover from that.
+        if self.halftone.is_some() {
+            return self
+                .code_with(input, Some(EcLevel::H))
+                .or_else(|_| self.code_with(input, None));
+        }
+
+        // The logo hides part of the code, so leave room to recover it.
+        let code = self.code_with(input, Some(EcLevel::Q))?;
+        if self.check_logo(&code).is_ok() {
+            return Ok(code);
+        }
+        Ok(self.code_with(input, Some(EcLevel::H)).unwrap_or(code))
+    }
+
+    /// Decodes `input` from the `input_encoding`, if there is one.
+    pub fn decode_input<'a>(
+        &self,
+        input: &'a [u8],
+    ) -> Result<Cow<'a, [u8]>, InputError> {
+        match self.input_encoding {
+            Some(encoding) => encoding.decode(input).map(Cow::Owned),
+            None => Ok(Cow::Borrowed(input)),
+        }
+    }
+
+    /// How much of the selected version `input` fills, or of the smallest
+    /// one that holds it. Doesn't account for the error correction raised
+    /// for a logo or a halftone.
+    pub fn estimate(&self, input: &[u8]) -> Estimate {
+        let version = self.version_number.map(|n| match self.version_type {
+            VersionType::MicroVersion => Version::Micro(n),
+            VersionType::NormalVersion => Version::Normal(n),
+        });
+
+        // The levels `code_with` falls back to.
+        let ec_level = match (self.error_correction_level, version) {
+            (Some(ec_level), _) => ec_level,
+            (None, Some(_)) => EcLevel::L,
+            (None, None) => EcLevel::M,
+        };
+
+        Estimate::new(input, version, ec_level)
+    }
+
+    fn code_with(&self, input: &[u8], ec: Option<EcLevel>) -> QrResult<QrCode> {
         use EcLevel::*;
         use Version::*;
         use VersionType::*;
 
-        let code = match (
-            self.version_type,
-            self.version_number,
-            self.error_correction_level,
-        ) {
+        match (self.version_type, self.version_number, ec) {
             (MicroVersion, Some(n), Some(e)) => {
                 QrCode::with_version(input, Micro(n), e)
             }
@@ -118,81 +305,421 @@ impl Generator {
             }
             (_, _, Some(e)) => QrCode::with_error_correction_level(input, e),
             (_, _, _) => QrCode::new(input),
+        }
+    }
+
+    /// Returns the (min_width, min_height, max_width, max_height) passed to
+    /// the renderers.
+    pub fn dimensions(&self) -> (u32, u32, u32, u32) {
+        let default = if matches!(self.format, Format::Unicode) {
+            20
+        } else {
+            360
         };
 
-        let code = code?;
+        let physical = match self.format {
+            Format::Unicode | Format::PlainText => None,
+            _ => self.physical_size.map(|s| s.to_pixels(self.dpi())),
+        };
 
-        let min_height = self.height.or(self.min_height).unwrap_or_else(|| {
-            if matches!(self.format, Format::Unicode) {
-                20
-            } else {
-                360
-            }
-        });
-        let min_width = self.width.or(self.min_width).unwrap_or_else(|| {
-            if matches!(self.format, Format::Unicode) {
-                20
-            } else {
-                360
-            }
-        });
+        let width = physical.or(self.width);
+        let height = physical.or(self.height);
+
+        let min_height = height.or(self.min_height).unwrap_or(default);
+        let min_width = width.or(self.min_width).unwrap_or(default);
 
-        let max_height = self
-            .height
+        let max_height = height
             .or(self.max_height)
             .unwrap_or_default()
             .max(min_height);
 
-        let max_width = self
-            .width
-            .or(self.max_width)
-            .unwrap_or_default()
-            .max(min_width);
+        let max_width =
+            width.or(self.max_width).unwrap_or_default().max(min_width);
+
+        (min_width, min_height, max_width, max_height)
+    }
+
+    /// The resolution written into raster outputs.
+    pub fn dpi(&self) -> u32 {
+        self.dpi.unwrap_or(DEFAULT_DPI)
+    }
+
+    /// The quiet zone width in modules.
+    pub fn margin(&self, code: &QrCode) -> u32 {
+        match (self.margin, self.quiet_zone) {
+            (Some(m), _) => m,
+            (None, Some(false)) => 0,
+            (None, _) if code.version().is_micro() => 2,
+            (None, _) => 4,
+        }
+    }
+
+    /// The module matrix with the quiet zone drawn in and the logo area
+    /// cleared.
+    pub fn matrix(&self, code: &QrCode) -> Matrix {
+        let mut matrix = Matrix::new(code, self.margin(code) as usize);
+        if let Some(area) = self.logo_area(code) {
+            matrix.excavate(area);
+        }
+        matrix
+    }
+
+    /// The modules covered by the logo, if any. Text can't show the logo,
+    /// so it covers nothing there.
+    pub fn logo_area(&self, code: &QrCode) -> Option<Area> {
+        if matches!(self.format, Format::Unicode | Format::PlainText) {
+            return None;
+        }
+        let size = self.logo_size.unwrap_or(DEFAULT_LOGO_SIZE);
+        self.logo.as_ref().map(|logo| logo.area(code, size))
+    }
+
+    /// Checks that the code can still be read with the logo on top.
+    pub fn check_logo(&self, code: &QrCode) -> Result<(), LogoError> {
+        match self.logo_area(code) {
+            Some(area) => logo::check(code, area),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks that the halftone, if any, can be drawn: only in raster output,
+    /// with square modules and eyes and a plain dark color.
+    pub fn check_halftone(&self) -> Result<(), HalftoneError> {
+        if self.halftone.is_none() {
+            return Ok(());
+        }
+        if !matches!(self.format, Format::Png | Format::Jpeg) {
+            return Err(HalftoneError::Format);
+        }
+        if self.shape.is_some_and(|shape| shape != Shape::Square)
+            || self.eye_shape.is_some()
+            || self.eye_dot_shape.is_some()
+            || self.dark_gradient.is_some()
+        {
+            return Err(HalftoneError::Style);
+        }
+        Ok(())
+    }
+
+    /// The width and height of the largest image drawn on the way to the
+    /// output, in pixels or characters: the code, the frame around it, or
+    /// the physical size it is fitted to. SVG isn't drawn, so it has none.
+    pub fn output_size(&self, code: &QrCode) -> (u64, u64) {
+        let modules = code.width() + 2 * self.margin(code) as usize;
+        let module_size = self.module_size(code) as u64;
+        let (module_width, module_height) =
+            self.module_dimensions(modules, self.module_size);
+        let (width, height) = match self.format {
+            Format::Svg | Format::Html => return (0, 0),
+            Format::PlainText => {
+                let size = self.module_size.unwrap_or(1).max(1) as u64;
+                (2 * size, size)
+            }
+            Format::Unicode => (module_width as u64, module_height as u64),
+            Format::Png | Format::Jpeg => (
+                module_size.max(module_width as u64),
+                module_size.max(module_height as u64),
+            ),
+        };
+        let (mut width, mut height) =
+            (modules as u64 * width, modules as u64 * height);
+
+        if let Some(style) = self.frame_style() {
+            let layout = Layout::new(&style, modules);
+            width = width.max(layout.width as u64 * module_size);
+            height = height.max(layout.height as u64 * module_size);
+        }
+        if matches!(self.format, Format::Png | Format::Jpeg) {
+            if let Some(size) = self.physical_size {
+                let size = size.to_pixels(self.dpi()) as u64;
+                width = width.max(size);
+                height = height.max(size);
+            }
+        }
+        (width, height)
+    }
+
+    /// Checks that the output takes at most [`MAX_SAMPLES`] to draw.
+    pub fn check_size(&self, code: &QrCode) -> Result<(), GenerateError> {
+        let (width, height) = self.output_size(code);
+        let samples = (SUPERSAMPLING * SUPERSAMPLING) as u64;
+        if width.saturating_mul(height).saturating_mul(samples) > MAX_SAMPLES {
+            return Err(GenerateError::TooLarge { width, height });
+        }
+        Ok(())
+    }
+
+    /// The size of a single module in pixels, either as requested or as the
+    /// renderer will compute it from the max dimensions.
+    pub fn module_size(&self, code: &QrCode) -> u32 {
+        if let Some(size) = self.module_size {
+            return size.max(1);
+        }
+
+        let (_, _, max_width, max_height) = self.dimensions();
+        let modules = code.width() as u32 + 2 * self.margin(code);
+        let (width, height) = match self.frame_style() {
+            Some(style) => {
+                let layout = Layout::new(&style, modules as usize);
+                (layout.width as u32, layout.height as u32)
+            }
+            None => (modules, modules),
+        };
+        (max_width / width).min(max_height / height).max(1)
+    }
+
+    /// The frame drawn around image outputs, if any.
+    pub fn frame_style(&self) -> Option<FrameStyle> {
+        if matches!(self.format, Format::Unicode | Format::PlainText) {
+            return None;
+        }
+
+        let (dark, light) = self.colors();
+        self.frame.map(|frame| FrameStyle {
+            frame,
+            caption: self
+                .frame_text
+                .clone()
+                .unwrap_or_else(|| DEFAULT_CAPTION.into()),
+            position: self.frame_position.unwrap_or_default(),
+            color: self.frame_color.clone().unwrap_or(dark),
+            background: light,
+        })
+    }
+
+    fn renderer<'a, P: Pixel>(
+        &self,
+        modules: &'a [qrcode::Color],
+        width: usize,
+        module_size: Option<u32>,
+    ) -> Renderer<'a, P> {
+        let (min_width, min_height, max_width, max_height) = self.dimensions();
+
+        // The quiet zone is already part of the modules.
+        let mut renderer = Renderer::new(modules, width, 0);
+        renderer.quiet_zone(false);
+
+        match module_size {
+            Some(size) => renderer.module_dimensions(size, size),
+            None => renderer
+                .min_dimensions(min_width, min_height)
+                .max_dimensions(max_width, max_height),
+        };
+
+        renderer
+    }
+
+    /// The pixel width and height of a module as the plain renderers pick
+    /// it: stretched to the requested dimensions unless the size is fixed.
+    fn module_dimensions(
+        &self,
+        width: usize,
+        module_size: Option<u32>,
+    ) -> (u32, u32) {
+        match module_size {
+            Some(size) => (size.max(1), size.max(1)),
+            None => {
+                let (_, _, max_width, max_height) = self.dimensions();
+                let width = width as u32;
+                ((max_width / width).max(1), (max_height / width).max(1))
+            }
+        }
+    }
+
+    pub fn dark_color(&self) -> Color {
+        self.dark_color.clone().unwrap_or_else(Color::black)
+    }
+
+    pub fn light_color(&self) -> Color {
+        self.light_color.clone().unwrap_or_else(Color::white)
+    }
+
+    /// The dark and the light color as drawn, swapped when inverted.
+    pub(crate) fn colors(&self) -> (Color, Color) {
+        let (dark, light) = (self.dark_color(), self.light_color());
+        if self.invert.unwrap_or(false) {
+            (light, dark)
+        } else {
+            (dark, light)
+        }
+    }
+
+    pub fn transform(&self) -> Transform {
+        Transform {
+            rotation: self.rotation.unwrap_or_default(),
+            mirror: self.mirror.unwrap_or(false),
+        }
+    }
+
+    pub fn style(&self) -> Style {
+        let (dark, light) = self.colors();
+        Style {
+            shape: self.shape.unwrap_or_default(),
+            eye_shape: self.eye_shape,
+            eye_dot_shape: self.eye_dot_shape,
+            dark,
+            light,
+            eye_color: self.eye_color.clone(),
+            gradient: self.dark_gradient.clone(),
+            logo: self.logo.clone(),
+        }
+    }
+
+    /// The label and the description of the SVG output, if any. Codes on
+    /// the HTML page are always labelled.
+    fn labels(&self, input: &[u8]) -> Option<(String, String)> {
+        if let Some(alt) = &self.alt_text {
+            return Some((alt.clone(), format!("QR code: {}", alt)));
+        }
+
+        let accessible = self.accessible.unwrap_or(false)
+            || matches!(self.format, Format::Html);
+        if !accessible {
+            return None;
+        }
+
+        let data = String::from_utf8_lossy(input);
+        Some((format!("QR code: {}", data.trim()), data.into_owned()))
+    }
+
+    /// The scannability warnings of `code`, as it would be drawn.
+    pub fn lint(&self, code: &QrCode) -> Vec<Warning> {
+        lint::lint(self, code)
+    }
+
+    fn svg(&self, code: &QrCode, matrix: &Matrix) -> String {
+        let style = self.style();
+        let frame = self.frame_style();
+
+        // The frame is laid out in whole modules, so the code inside it
+        // can't be stretched to the requested dimensions.
+        let module_size = match frame {
+            Some(_) => Some(self.module_size(code)),
+            None => self.module_size,
+        };
+
+        let svg = if style.is_plain() {
+            let width = matrix.width();
+            svg::render(
+                matrix.modules(),
+                width,
+                self.module_dimensions(width, module_size),
+                &style.dark,
+                &style.light,
+            )
+        } else {
+            Canvas::new(matrix, &style).svg(&style, self.module_size(code))
+        };
+
+        let svg = match frame {
+            Some(frame) => Layout::new(&frame, matrix.width()).svg(
+                &frame,
+                &svg,
+                self.module_size(code),
+            ),
+            None => svg,
+        };
+
+        let transform = self.transform();
+        if transform.is_identity() {
+            svg
+        } else {
+            transform.svg(&svg)
+        }
+    }
+
+    fn raster(&self, code: &QrCode, matrix: &Matrix) -> RgbaImage {
+        let style = self.style();
+        let frame = self.frame_style();
+
+        // A physical size is padded to exactly, so the modules must not be
+        // larger than the renderer would round them to.
+        let physical = self.physical_size.map(|s| s.to_pixels(self.dpi()));
+        let module_size = match (&frame, physical) {
+            (None, None) => self.module_size,
+            _ => Some(self.module_size(code)),
+        };
+
+        let image = if let Some(halftone) = &self.halftone {
+            halftone.render(matrix, &style, self.module_size(code))
+        } else if style.is_plain() {
+            self.renderer::<Rgba<u8>>(
+                matrix.modules(),
+                matrix.width(),
+                module_size,
+            )
+            .dark_color(style.dark.to_rgba())
+            .light_color(style.light.to_rgba())
+            .build()
+        } else {
+            Canvas::new(matrix, &style).raster(&style, self.module_size(code))
+        };
+
+        let image = match frame {
+            Some(frame) => Layout::new(&frame, matrix.width()).raster(
+                &frame,
+                &image,
+                self.module_size(code),
+            ),
+            None => image,
+        };
+
+        let image = self.transform().raster(image);
+        match physical {
+            Some(size) => fit(image, size.max(1), style.light.to_rgba()),
+            None => image,
+        }
+    }
+
+    /// Draws `code` as pixels, as it would be written to PNG, whatever the
+    /// format. The logo, if any, must have passed `check_logo` with a raster
+    /// format.
+    pub fn image(&self, code: &QrCode) -> Result<RgbaImage, GenerateError> {
+        let gen = Self {
+            format: Format::Png,
+            ..self.clone()
+        };
+        gen.check_size(code)?;
+        Ok(gen.raster(code, &gen.matrix(code)))
+    }
+
+    pub fn generate(&self, input: &[u8]) -> Result<Vec<u8>, GenerateError> {
+        let code = self.code(input)?;
+        self.check_logo(&code).map_err(GenerateError::Logo)?;
+        self.check_halftone().map_err(GenerateError::Halftone)?;
+        self.render(input, &code)
+    }
+
+    /// Draws `code`, the code of `input`, in the output format. The logo, if
+    /// any, must have passed `check_logo`.
+    pub fn render(
+        &self,
+        input: &[u8],
+        code: &QrCode,
+    ) -> Result<Vec<u8>, GenerateError> {
+        self.check_size(code)?;
+        let matrix = self.matrix(code);
+        let width = matrix.width();
+        let modules = &self.transform().modules(matrix.modules(), width);
+        let invert = self.invert.unwrap_or(false);
 
         let image = match self.format {
             Format::Svg | Format::Html => {
-                let mut bytes = code
-                    .render()
-                    .min_dimensions(min_width, min_height)
-                    .max_dimensions(max_width, max_height)
-                    .dark_color(svg::Color(
-                        self.dark_color.as_deref().unwrap_or("#000"),
-                    ))
-                    .light_color(svg::Color(
-                        self.light_color.as_deref().unwrap_or("#fff"),
-                    ))
-                    .quiet_zone(self.quiet_zone.unwrap_or(true))
-                    .build()
-                    .into_bytes();
+                let mut svg = self.svg(code, &matrix);
+                if self.scalable.unwrap_or(false) {
+                    svg = svg::scalable(&svg);
+                }
+                if let Some((label, description)) = self.labels(input) {
+                    svg = accessibility::annotate(&svg, &label, &description);
+                }
+
+                let mut bytes = svg.into_bytes();
                 bytes.push(b'\n');
                 bytes
             }
 
             Format::Png => {
-                let (dr, dg, db, da) = self
-                    .dark_color
-                    .as_deref()
-                    .unwrap_or("#000")
-                    .parse::<Color>()
-                    .unwrap_or(Color::from_rgba8(0, 0, 0, 0))
-                    .to_linear_rgba_u8();
-
-                let (lr, lg, lb, la) = self
-                    .light_color
-                    .as_deref()
-                    .unwrap_or("#fff")
-                    .parse::<Color>()
-                    .unwrap_or(Color::from_rgba8(255, 255, 255, 0))
-                    .to_linear_rgba_u8();
-
-                let image = code
-                    .render::<Rgba<u8>>()
-                    .dark_color(Rgba([dr, dg, db, da]))
-                    .light_color(Rgba([lr, lg, lb, la]))
-                    .min_dimensions(min_width, min_height)
-                    .max_dimensions(max_width, max_height)
-                    .quiet_zone(self.quiet_zone.unwrap_or(true))
-                    .build();
+                let image = self.raster(code, &matrix);
 
                 let bytes = image.as_bytes();
                 let mut result: Vec<u8> = Default::default();
@@ -205,39 +732,27 @@ impl Generator {
                         ColorType::Rgba8,
                     )
                     .map_err(|_| QrError::UnsupportedCharacterSet)?;
-                println!("aaa");
+
+                if self.srgb.unwrap_or(false) {
+                    meta::png_srgb(&mut result);
+                }
+
+                if self.dpi.is_some() || self.physical_size.is_some() {
+                    meta::png_dpi(&mut result, self.dpi());
+                }
                 result
             }
 
             Format::Jpeg => {
-                let (dr, dg, db, da) = self
-                    .dark_color
-                    .as_deref()
-                    .unwrap_or("#000")
-                    .parse::<Color>()
-                    .unwrap_or(Color::from_rgba8(0, 0, 0, 0))
-                    .to_linear_rgba_u8();
-
-                let (lr, lg, lb, la) = self
-                    .light_color
-                    .as_deref()
-                    .unwrap_or("#fff")
-                    .parse::<Color>()
-                    .unwrap_or(Color::from_rgba8(255, 255, 255, 0))
-                    .to_linear_rgba_u8();
-
-                let image = code
-                    .render::<Rgba<u8>>()
-                    .dark_color(Rgba([dr, dg, db, da]))
-                    .light_color(Rgba([lr, lg, lb, la]))
-                    .min_dimensions(min_width, min_height)
-                    .max_dimensions(max_width, max_height)
-                    .quiet_zone(self.quiet_zone.unwrap_or(true))
-                    .build();
+                let image = self.raster(code, &matrix);
 
                 let bytes = image.as_bytes();
                 let mut result: Vec<u8> = Default::default();
                 let mut encoder = JpegEncoder::new(&mut result);
+                if self.dpi.is_some() || self.physical_size.is_some() {
+                    let dpi = self.dpi().min(u16::MAX as u32) as u16;
+                    encoder.set_pixel_density(PixelDensity::dpi(dpi));
+                }
                 encoder
                     .encode(
                         bytes,
@@ -250,10 +765,14 @@ impl Generator {
             }
 
             Format::PlainText => {
-                let mut bytes = code
-                    .render::<char>()
-                    .module_dimensions(2, 1)
-                    .quiet_zone(self.quiet_zone.unwrap_or(true))
+                let size = self.module_size.unwrap_or(1).max(1);
+                let (dark, light) =
+                    if invert { (' ', '█') } else { ('█', ' ') };
+                let mut bytes = Renderer::<char>::new(modules, width, 0)
+                    .quiet_zone(false)
+                    .module_dimensions(2 * size, size)
+                    .dark_color(dark)
+                    .light_color(light)
                     .build()
                     .into_bytes();
                 bytes.push(b'\n');
@@ -261,13 +780,21 @@ impl Generator {
             }
 
             Format::Unicode => {
-                let mut bytes = code
-                    .render::<unicode::Dense1x2>()
-                    .min_dimensions(min_width, min_height)
-                    .max_dimensions(max_width, max_height)
-                    .dark_color(unicode::Dense1x2::Light)
-                    .light_color(unicode::Dense1x2::Dark)
-                    .quiet_zone(self.quiet_zone.unwrap_or(true))
+                // Terminals draw light text on a dark background, so the
+                // colors are swapped unless inverted.
+                let (dark, light) = if invert {
+                    (unicode::Dense1x2::Dark, unicode::Dense1x2::Light)
+                } else {
+                    (unicode::Dense1x2::Light, unicode::Dense1x2::Dark)
+                };
+                let mut bytes = self
+                    .renderer::<unicode::Dense1x2>(
+                        modules,
+                        width,
+                        self.module_size,
+                    )
+                    .dark_color(dark)
+                    .light_color(light)
                     .build()
                     .into_bytes();
                 bytes.push(b'\n');
@@ -278,3 +805,41 @@ impl Generator {
         Ok(image)
     }
 }
+
+/// Why the output of a code couldn't be generated.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GenerateError {
+    /// The input doesn't fit the code, or the image couldn't be encoded.
+    Code(QrError),
+    /// The logo would make the code unreadable.
+    Logo(LogoError),
+    /// The halftone can't be drawn in the output.
+    Halftone(HalftoneError),
+    /// The output would take more than [`MAX_SAMPLES`] to draw.
+    TooLarge { width: u64, height: u64 },
+}
+
+impl From<QrError> for GenerateError {
+    fn from(err: QrError) -> Self {
+        Self::Code(err)
+    }
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Code(err) => err.fmt(f),
+            Self::Logo(err) => err.fmt(f),
+            Self::Halftone(err) => err.fmt(f),
+            Self::TooLarge { width, height } => write!(
+                f,
+                "the image would be {}x{}, more than {} pixels",
+                width,
+                height,
+                MAX_SAMPLES / (SUPERSAMPLING * SUPERSAMPLING) as u64
+            ),
+        }
+    }
+}
+
+impl error::Error for GenerateError {}
@@ -0,0 +1,303 @@
+use image::Rgba;
+use std::error;
+use std::fmt;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::color::Color;
+use crate::color::ColorError;
+use crate::render::num;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientKind {
+    /// Along a line at the given angle in degrees, clockwise from "to top"
+    /// like in CSS.
+    Linear(f64),
+    /// Outwards from the center to the corners.
+    Radial,
+}
+
+/// A color at a position between `0.0` (start) and `1.0` (end).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorStop {
+    pub color: Color,
+    pub offset: f64,
+}
+
+/// A gradient fill for the dark modules, written like the CSS functions:
+/// `linear(45deg, #336699, #993366 80%)` or `radial(red, blue)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    pub stops: Vec<ColorStop>,
+}
+
+impl Gradient {
+    pub fn colors(&self) -> impl Iterator<Item = &Color> {
+        self.stops.iter().map(|s| &s.color)
+    }
+
+    /// The `<linearGradient>` or `<radialGradient>` element with the given
+    /// id, spanning a view box of `size` units.
+    pub fn to_svg(&self, id: &str, size: f64) -> String {
+        let mut svg = match self.kind {
+            GradientKind::Linear(angle) => {
+                let ((x1, y1), (x2, y2)) = line(angle, size);
+                format!(
+                    r#"<linearGradient id="{}" gradientUnits="userSpaceOnUse" x1="{}" y1="{}" x2="{}" y2="{}">"#,
+                    id,
+                    num(x1),
+                    num(y1),
+                    num(x2),
+                    num(y2),
+                )
+            }
+            GradientKind::Radial => format!(
+                r#"<radialGradient id="{}" gradientUnits="userSpaceOnUse" cx="{c}" cy="{c}" r="{}">"#,
+                id,
+                num(radius(size)),
+                c = num(size / 2.0),
+            ),
+        };
+
+        for stop in &self.stops {
+            let [r, g, b, a] = stop.color.to_rgba().0;
+            write!(
+                svg,
+                r#"<stop offset="{}" stop-color="{}""#,
+                num(stop.offset),
+                Color::from_rgba8(r, g, b, 255).to_svg(),
+            )
+            .unwrap();
+            if a < 255 {
+                write!(svg, r#" stop-opacity="{}""#, num(a as f64 / 255.0))
+                    .unwrap();
+            }
+            svg.push_str("/>");
+        }
+
+        svg.push_str(match self.kind {
+            GradientKind::Linear(_) => "</linearGradient>",
+            GradientKind::Radial => "</radialGradient>",
+        });
+        svg
+    }
+
+    /// The color at `(x, y)` in a square of `size` units, interpolated in
+    /// sRGB like SVG does.
+    pub fn color_at(&self, x: f64, y: f64, size: f64) -> Rgba<u8> {
+        let t = match self.kind {
+            GradientKind::Linear(angle) => {
+                let ((x1, y1), (x2, y2)) = line(angle, size);
+                let (dx, dy) = (x2 - x1, y2 - y1);
+                ((x - x1) * dx + (y - y1) * dy) / (dx * dx + dy * dy)
+            }
+            GradientKind::Radial => {
+                let c = size / 2.0;
+                ((x - c).powi(2) + (y - c).powi(2)).sqrt() / radius(size)
+            }
+        };
+
+        let t = t.clamp(0.0, 1.0);
+        let last = self.stops.len() - 1;
+        let next = self
+            .stops
+            .iter()
+            .position(|s| s.offset >= t)
+            .unwrap_or(last);
+
+        if next == 0 {
+            return self.stops[0].color.to_rgba();
+        }
+
+        let (a, b) = (&self.stops[next - 1], &self.stops[next]);
+        let span = b.offset - a.offset;
+        let f = if span > 0.0 {
+            (t - a.offset) / span
+        } else {
+            1.0
+        };
+        let (a, b) = (a.color.to_rgba(), b.color.to_rgba());
+
+        let mut out = a;
+        for (o, (a, b)) in out.0.iter_mut().zip(a.0.iter().zip(b.0.iter())) {
+            *o = (*a as f64 + (*b as f64 - *a as f64) * f).round() as u8;
+        }
+        out
+    }
+}
+
+/// The start and end of a CSS style gradient line at `angle` degrees across
+/// a square of `size` units, so that the corners get the end colors.
+fn line(angle: f64, size: f64) -> ((f64, f64), (f64, f64)) {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let half = size * (sin.abs() + cos.abs()) / 2.0;
+    let c = size / 2.0;
+    let (dx, dy) = (sin * half, -cos * half);
+    ((c - dx, c - dy), (c + dx, c + dy))
+}
+
+/// From the center to the corners.
+fn radius(size: f64) -> f64 {
+    size / 2.0 * std::f64::consts::SQRT_2
+}
+
+/// Splits on the commas that are not inside parentheses, so that colors like
+/// `rgb(1, 2, 3)` stay in one piece.
+fn split_args(s: &str) -> Vec<&str> {
+    let mut args = vec![];
+    let (mut depth, mut start) = (0, 0);
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    args.push(s[start..].trim());
+    args
+}
+
+fn parse_angle(s: &str) -> Option<f64> {
+    let s = s.trim().to_lowercase();
+    if let Some(side) = s.strip_prefix("to ") {
+        return match side.trim() {
+            "top" => Some(0.0),
+            "right" => Some(90.0),
+            "bottom" => Some(180.0),
+            "left" => Some(270.0),
+            _ => None,
+        };
+    }
+
+    // A bare number could also be a hex color, so the unit is required.
+    let (value, scale) = if let Some(v) = s.strip_suffix("deg") {
+        (v, 1.0)
+    } else if let Some(v) = s.strip_suffix("turn") {
+        (v, 360.0)
+    } else {
+        return None;
+    };
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .map(|v| v * scale)
+        .filter(|v| v.is_finite())
+}
+
+fn parse_stop(s: &str) -> Result<(Color, Option<f64>), GradientError> {
+    // An offset is the last word, unless it belongs to the color itself.
+    if let Some((color, offset)) = s.rsplit_once(' ') {
+        if let Some(pct) = offset.trim().strip_suffix('%') {
+            let pct: f64 = pct
+                .parse()
+                .map_err(|_| GradientError::Syntax(s.to_string()))?;
+            let color = color.trim().parse().map_err(GradientError::Color)?;
+            return Ok((color, Some(pct / 100.0)));
+        }
+    }
+    let color = s.parse().map_err(GradientError::Color)?;
+    Ok((color, None))
+}
+
+impl FromStr for Gradient {
+    type Err = GradientError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || GradientError::Syntax(s.to_string());
+        let trimmed = s.trim();
+
+        let (name, args) = trimmed
+            .strip_suffix(')')
+            .and_then(|t| t.split_once('('))
+            .ok_or_else(err)?;
+
+        let mut args = split_args(args);
+        let kind = match name.trim().to_lowercase().as_str() {
+            "linear" | "linear-gradient" => {
+                match args.first().and_then(|a| parse_angle(a)) {
+                    Some(angle) => {
+                        args.remove(0);
+                        GradientKind::Linear(angle)
+                    }
+                    // Top to bottom, like CSS.
+                    None => GradientKind::Linear(180.0),
+                }
+            }
+            "radial" | "radial-gradient" => GradientKind::Radial,
+            _ => return Err(err()),
+        };
+
+        if args.len() < 2 {
+            return Err(err());
+        }
+
+        let stops = args
+            .into_iter()
+            .map(parse_stop)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Like CSS: the ends default to 0% and 100%, missing offsets in
+        // between are spread evenly, and offsets never go backwards.
+        let mut offsets: Vec<Option<f64>> =
+            stops.iter().map(|(_, o)| *o).collect();
+        let last = offsets.len() - 1;
+        offsets[0] = offsets[0].or(Some(0.0));
+        offsets[last] = offsets[last].or(Some(1.0));
+
+        let mut i = 0;
+        while i < last {
+            let start = i;
+            i += 1;
+            while offsets[i].is_none() {
+                i += 1;
+            }
+            let (a, b) = (offsets[start].unwrap(), offsets[i].unwrap());
+            let gap = (i - start) as f64;
+            for (j, offset) in offsets[start + 1..i].iter_mut().enumerate() {
+                *offset = Some(a + (b - a) * (j + 1) as f64 / gap);
+            }
+        }
+
+        let mut max = 0.0_f64;
+        let stops = stops
+            .into_iter()
+            .zip(offsets)
+            .map(|((color, _), offset)| {
+                max = max.max(offset.unwrap().clamp(0.0, 1.0));
+                ColorStop { color, offset: max }
+            })
+            .collect();
+
+        Ok(Self { kind, stops })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GradientError {
+    Syntax(String),
+    Color(ColorError),
+}
+
+impl fmt::Display for GradientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(input) => write!(
+                f,
+                "invalid gradient {:?}: expected linear([angle,] color \
+                 [offset%], color [offset%], ...) with an angle like 45deg \
+                 or to right, or radial(color [offset%], color [offset%], \
+                 ...)",
+                input
+            ),
+            Self::Color(e) => write!(f, "invalid gradient: {}", e),
+        }
+    }
+}
+
+impl error::Error for GradientError {}
@@ -0,0 +1,229 @@
+//! Halftone codes: a picture under the code, with the data modules shrunk to
+//! dots in its colors. Scanners sample modules at their centers, so only the
+//! dots and the function patterns need to keep the contrast.
+
+use image::imageops;
+use image::imageops::FilterType;
+use image::Rgba;
+use image::RgbaImage;
+use std::error;
+use std::fmt;
+
+use crate::color::Color;
+use crate::matrix::Kind;
+use crate::matrix::Matrix;
+use crate::render::Style;
+
+/// The width of the dots, as a share of the module width.
+const DOT_SIZE: f64 = 1.0 / 3.0;
+
+/// How far from the midpoint between the dark and the light color the dots
+/// have to stay, as a share of the distance between the two.
+const LUMA_MARGIN: f64 = 0.25;
+
+#[derive(Debug, Clone)]
+pub struct Halftone {
+    image: RgbaImage,
+}
+
+impl Halftone {
+    pub fn from_bytes(data: &[u8]) -> Result<Self, HalftoneError> {
+        let image = image::load_from_memory(data)
+            .map_err(|e| HalftoneError::Image(e.to_string()))?
+            .to_rgba8();
+
+        if image.width() == 0 || image.height() == 0 {
+            return Err(HalftoneError::Image("empty image".into()));
+        }
+
+        Ok(Self { image })
+    }
+
+    /// Decodes a base64 encoded image, optionally as a `data:` URI.
+    pub fn from_base64(s: &str) -> Result<Self, HalftoneError> {
+        let s = s.trim();
+        let s = match s.strip_prefix("data:") {
+            Some(uri) => uri.split_once(',').map(|(_, d)| d).unwrap_or(""),
+            None => s,
+        };
+
+        let data = base64::decode(s).map_err(HalftoneError::Base64)?;
+        Self::from_bytes(&data)
+    }
+
+    /// Renders `matrix` over the image, cropped to fill the code inside the
+    /// quiet zone, with `module_size` pixels per module.
+    pub fn render(
+        &self,
+        matrix: &Matrix,
+        style: &Style,
+        module_size: u32,
+    ) -> RgbaImage {
+        let dark = style.dark.to_rgba();
+        let light = style.light.to_rgba();
+        let eye = style.eye_color.as_ref().map_or(dark, Color::to_rgba);
+
+        let size = matrix.width() as u32 * module_size;
+        let inner = (matrix.width() - 2 * matrix.margin()) as u32 * module_size;
+        let offset = matrix.margin() as u32 * module_size;
+
+        let mut image = RgbaImage::from_pixel(size, size, light);
+        let picture = self.cover(inner);
+        for (x, y, px) in picture.enumerate_pixels() {
+            let bg = image.get_pixel_mut(x + offset, y + offset);
+            *bg = over(px, bg);
+        }
+
+        let (min, max) = bounds(luma(&dark), luma(&light));
+        let dot = ((module_size as f64 * DOT_SIZE).round() as u32).max(1);
+        let inset = (module_size - dot.min(module_size)) / 2;
+
+        for my in 0..matrix.width() {
+            for mx in 0..matrix.width() {
+                let (x, y) = (mx as u32 * module_size, my as u32 * module_size);
+                let is_dark = matrix.is_dark(mx as isize, my as isize);
+
+                match matrix.kind(mx, my) {
+                    Kind::QuietZone => {}
+
+                    Kind::Data => {
+                        let (x, y) = (x + inset, y + inset);
+                        let color = average(&image, x, y, dot);
+                        let color = if is_dark {
+                            toward(&color, &dark, |l| l <= min)
+                        } else {
+                            toward(&color, &light, |l| l >= max)
+                        };
+                        fill(&mut image, x, y, dot, color);
+                    }
+
+                    kind => {
+                        let color = match (is_dark, kind) {
+                            (true, Kind::Finder) => eye,
+                            (true, _) => dark,
+                            (false, _) => light,
+                        };
+                        fill(&mut image, x, y, module_size, color);
+                    }
+                }
+            }
+        }
+
+        if let (Some(logo), Some(area)) = (&style.logo, matrix.excavated()) {
+            logo.composite(&mut image, area, module_size);
+        }
+
+        image
+    }
+
+    /// The image scaled and center cropped to a `size` pixels square.
+    fn cover(&self, size: u32) -> RgbaImage {
+        let (w, h) = self.image.dimensions();
+        let side = w.min(h);
+        let square = imageops::crop_imm(
+            &self.image,
+            (w - side) / 2,
+            (h - side) / 2,
+            side,
+            side,
+        )
+        .to_image();
+
+        imageops::resize(
+            &square,
+            size.max(1),
+            size.max(1),
+            FilterType::Triangle,
+        )
+    }
+}
+
+/// The luma a dark dot must stay under and a light dot must stay over.
+fn bounds(dark: f64, light: f64) -> (f64, f64) {
+    let mid = (dark + light) / 2.0;
+    let margin = (light - dark) * LUMA_MARGIN;
+    (mid - margin, mid + margin)
+}
+
+/// Gray level from 0 to 1 as a camera would binarize it (Rec. 601 luma of
+/// the sRGB values).
+fn luma(px: &Rgba<u8>) -> f64 {
+    let [r, g, b, _] = px.0;
+    (0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64) / 255.0
+}
+
+/// Mixes `color` into `target` just enough to satisfy `ok`.
+fn toward(
+    color: &Rgba<u8>,
+    target: &Rgba<u8>,
+    ok: impl Fn(f64) -> bool,
+) -> Rgba<u8> {
+    (0..=20)
+        .map(|i| mix(color, target, i as f64 / 20.0))
+        .find(|c| ok(luma(c)))
+        .unwrap_or(*target)
+}
+
+fn mix(a: &Rgba<u8>, b: &Rgba<u8>, t: f64) -> Rgba<u8> {
+    let mut out = *a;
+    for (o, (a, b)) in out.0.iter_mut().zip(a.0.iter().zip(b.0.iter())) {
+        *o = (*a as f64 * (1.0 - t) + *b as f64 * t).round() as u8;
+    }
+    out
+}
+
+/// Alpha-composites `fg` over `bg`, keeping the alpha of `bg`.
+fn over(fg: &Rgba<u8>, bg: &Rgba<u8>) -> Rgba<u8> {
+    let mut out = mix(bg, fg, fg.0[3] as f64 / 255.0);
+    out.0[3] = bg.0[3];
+    out
+}
+
+fn average(image: &RgbaImage, x: u32, y: u32, size: u32) -> Rgba<u8> {
+    let mut sum = [0u32; 4];
+    for py in y..y + size {
+        for px in x..x + size {
+            for (s, v) in sum.iter_mut().zip(image.get_pixel(px, py).0.iter()) {
+                *s += *v as u32;
+            }
+        }
+    }
+
+    let n = size * size;
+    Rgba([sum[0] / n, sum[1] / n, sum[2] / n, sum[3] / n].map(|v| v as u8))
+}
+
+fn fill(image: &mut RgbaImage, x: u32, y: u32, size: u32, color: Rgba<u8>) {
+    for py in y..y + size {
+        for px in x..x + size {
+            image.put_pixel(px, py, color);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HalftoneError {
+    Base64(base64::DecodeError),
+    Image(String),
+    /// Only raster output is drawn over the image.
+    Format,
+    /// Data modules are dots over the image, whatever the shapes.
+    Style,
+}
+
+impl fmt::Display for HalftoneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Base64(e) => write!(f, "invalid base64 image: {}", e),
+            Self::Image(e) => write!(f, "invalid image: {}", e),
+            Self::Format => {
+                f.write_str("only PNG and JPEG output is supported")
+            }
+            Self::Style => f.write_str(
+                "can't be combined with shapes, eye shapes or gradients",
+            ),
+        }
+    }
+}
+
+impl error::Error for HalftoneError {}
@@ -0,0 +1,142 @@
+//! Decoding input sent in an encoding, so that a URL path can carry any
+//! bytes, e.g. binary data or a `#`.
+
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::InvalidValue;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEncoding {
+    /// The standard alphabet, padding optional.
+    Base64,
+    /// The URL and filename safe alphabet, padding optional.
+    Base64Url,
+    /// Two digits per byte, in any case, whitespace ignored.
+    Hex,
+    /// `%XX` escapes, with the other characters taken as they are.
+    Percent,
+}
+
+impl FromStr for InputEncoding {
+    type Err = InvalidValue;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "base64" => Ok(Self::Base64),
+            "base64url" => Ok(Self::Base64Url),
+            "hex" => Ok(Self::Hex),
+            "percent" => Ok(Self::Percent),
+            _ => Err(InvalidValue::new(s, "base64, base64url, hex or percent")),
+        }
+    }
+}
+
+impl InputEncoding {
+    pub fn decode(&self, input: &[u8]) -> Result<Vec<u8>, InputError> {
+        match self {
+            Self::Base64 => base64_decode(input, base64::STANDARD_NO_PAD),
+            Self::Base64Url => base64_decode(input, base64::URL_SAFE_NO_PAD),
+            Self::Hex => hex_decode(input),
+            Self::Percent => percent_decode(input),
+        }
+    }
+}
+
+fn base64_decode(
+    input: &[u8],
+    config: base64::Config,
+) -> Result<Vec<u8>, InputError> {
+    let input: Vec<u8> = input
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    // Padding, if any, must make whole groups of four, so at most two.
+    let pad = input.iter().rev().take_while(|&&b| b == b'=').count();
+    let unpadded = &input[..input.len() - pad];
+    if pad > 2 || pad > 0 && !input.len().is_multiple_of(4) {
+        let error = base64::DecodeError::InvalidByte(unpadded.len(), b'=');
+        return Err(InputError::Base64(error));
+    }
+
+    base64::decode_config(unpadded, config).map_err(InputError::Base64)
+}
+
+fn hex_decode(input: &[u8]) -> Result<Vec<u8>, InputError> {
+    let digits: Vec<(usize, u8)> = input
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|(_, b)| !b.is_ascii_whitespace())
+        .collect();
+
+    let digit = |&(offset, b): &(usize, u8)| {
+        (b as char)
+            .to_digit(16)
+            .map(|d| d as u8)
+            .ok_or(InputError::Hex(offset))
+    };
+
+    if digits.len() % 2 == 1 {
+        return Err(InputError::HexLength);
+    }
+    digits
+        .chunks(2)
+        .map(|pair| Ok(digit(&pair[0])? << 4 | digit(&pair[1])?))
+        .collect()
+}
+
+fn percent_decode(input: &[u8]) -> Result<Vec<u8>, InputError> {
+    let mut decoded = Vec::with_capacity(input.len());
+
+    let mut i = 0;
+    while i < input.len() {
+        if input[i] == b'%' {
+            let byte = input
+                .get(i + 1..i + 3)
+                .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or(InputError::Percent(i))?;
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(input[i]);
+            i += 1;
+        }
+    }
+    Ok(decoded)
+}
+
+#[derive(Debug)]
+pub enum InputError {
+    Base64(base64::DecodeError),
+    /// A character that is not a hex digit, at this offset.
+    Hex(usize),
+    HexLength,
+    /// A `%` not followed by two hex digits, at this offset.
+    Percent(usize),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Base64(e) => write!(f, "invalid base64 input: {}", e),
+            Self::Hex(offset) => {
+                write!(f, "invalid hex input: not a hex digit at {}", offset)
+            }
+            Self::HexLength => {
+                f.write_str("invalid hex input: odd number of digits")
+            }
+            Self::Percent(offset) => write!(
+                f,
+                "invalid percent encoded input: malformed escape at {}",
+                offset
+            ),
+        }
+    }
+}
+
+impl error::Error for InputError {}
@@ -1,12 +1,120 @@
+mod accessibility;
+mod batch;
+mod capacity;
+mod color;
+mod error;
+mod frame;
 mod gen;
+mod gradient;
+mod halftone;
+mod input;
+mod json;
+mod lint;
+mod logo;
+mod matrix;
+mod meta;
+pub mod multipart;
+mod payload;
+mod pdf;
+mod physical;
+mod preset;
+mod render;
+mod sheet;
+mod svg;
+mod template;
+mod transform;
+mod zip;
 
+pub use batch::parse_rows;
+pub use batch::Batch;
+pub use batch::BatchError;
+pub use batch::BatchOutput;
+pub use batch::Row;
+pub use batch::MAX_BATCH_SIZE;
+pub use capacity::Estimate;
+pub use color::Color;
+pub use color::ColorError;
+pub use error::InvalidValue;
+pub use frame::CaptionPosition;
+pub use frame::Frame;
+pub use frame::DEFAULT_CAPTION;
+pub use gen::parse_margin;
+pub use gen::parse_module_size;
 pub use gen::Format;
+pub use gen::GenerateError;
 pub use gen::Generator;
 pub use gen::VersionType;
+pub use gen::MAX_MARGIN;
+pub use gen::MAX_MODULE_SIZE;
+pub use gen::MAX_SAMPLES;
+pub use gradient::ColorStop;
+pub use gradient::Gradient;
+pub use gradient::GradientError;
+pub use gradient::GradientKind;
+pub use halftone::Halftone;
+pub use halftone::HalftoneError;
+pub use input::InputEncoding;
+pub use input::InputError;
+pub use lint::Warning;
+pub use logo::parse_logo_size;
+pub use logo::Logo;
+pub use logo::LogoError;
+pub use logo::DEFAULT_LOGO_SIZE;
+pub use logo::MAX_LOGO_SIZE;
+pub use matrix::blocks;
+pub use matrix::codewords;
+pub use matrix::Area;
+pub use matrix::Kind;
+pub use matrix::Matrix;
+pub use payload::build_payload;
+pub use payload::Address;
+pub use payload::Bic;
+pub use payload::Bitcoin;
+pub use payload::Contact;
+pub use payload::ContactFormat;
+pub use payload::DateTime;
+pub use payload::Event;
+pub use payload::Fields;
+pub use payload::Geo;
+pub use payload::Iban;
+pub use payload::Mail;
+pub use payload::Otp;
+pub use payload::OtpAlgorithm;
+pub use payload::OtpKind;
+pub use payload::Payload;
+pub use payload::PayloadError;
+pub use payload::QrBill;
+pub use payload::Reference;
+pub use payload::Sepa;
+pub use payload::Sms;
+pub use payload::Tel;
+pub use payload::Upi;
+pub use payload::Wifi;
+pub use payload::WifiAuth;
+pub use physical::LengthUnit;
+pub use physical::PhysicalSize;
+pub use physical::PhysicalSizeError;
+pub use preset::Preset;
+pub use preset::PresetError;
+pub use preset::Presets;
+pub use preset::DEFAULT_PRESETS;
 pub use qrcode::EcLevel;
 pub use qrcode::QrCode;
 pub use qrcode::QrResult;
 pub use qrcode::Version;
+pub use render::EyeShape;
+pub use render::Shape;
+pub use sheet::Sheet;
+pub use sheet::SheetError;
+pub use sheet::Spacing;
+pub use template::Template;
+pub use template::TemplateError;
+pub use template::MAX_CODES;
+pub use transform::Rotation;
+pub use transform::Transform;
+pub use zip::archive;
+pub use zip::ArchiveError;
+pub use zip::Zip;
 
 pub const TEMPLATE: &str = include_str!("../../templates/base.html");
 pub const HELP: &str = include_str!("../../README.txt");
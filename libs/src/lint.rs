@@ -0,0 +1,147 @@
+use qrcode::QrCode;
+use std::fmt;
+
+use crate::color::Color;
+use crate::gen::Format;
+use crate::gen::Generator;
+
+/// WCAG 2.x level AA contrast ratio for normal text. Scanners are far less
+/// forgiving than human eyes, so this is a floor, not a target.
+pub const MIN_CONTRAST_RATIO: f64 = 4.5;
+
+/// Modules smaller than this (in pixels) get lost in camera blur and JPEG
+/// artifacts.
+pub const MIN_MODULE_SIZE: u32 = 4;
+
+/// The quiet zone the standard asks for, in modules, around normal and
+/// micro codes.
+pub const MIN_QUIET_ZONE: u32 = 4;
+pub const MIN_MICRO_QUIET_ZONE: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    LowContrast(f64),
+    /// The caption of the frame is hard to read.
+    LowFrameContrast(f64),
+    InvertedColors,
+    TransparentDarkColor(f64),
+    TransparentLightColor(f64),
+    SmallModules(u32),
+    SmallQuietZone {
+        margin: u32,
+        minimum: u32,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LowContrast(ratio) => write!(
+                f,
+                "low contrast ratio {:.2}:1 (minimum {}:1)",
+                ratio, MIN_CONTRAST_RATIO
+            ),
+            Self::LowFrameContrast(ratio) => write!(
+                f,
+                "low frame contrast ratio {:.2}:1 (minimum {}:1)",
+                ratio, MIN_CONTRAST_RATIO
+            ),
+            Self::InvertedColors => {
+                write!(f, "dark color is lighter than light color")
+            }
+            Self::TransparentDarkColor(alpha) => {
+                write!(f, "dark color is transparent (alpha {:.2})", alpha)
+            }
+            Self::TransparentLightColor(alpha) => {
+                write!(f, "light color is transparent (alpha {:.2})", alpha)
+            }
+            Self::SmallModules(size) => write!(
+                f,
+                "module size {}px is too small (minimum {}px)",
+                size, MIN_MODULE_SIZE
+            ),
+            Self::SmallQuietZone { margin, minimum } => write!(
+                f,
+                "quiet zone of {} modules is too small (minimum {})",
+                margin, minimum
+            ),
+        }
+    }
+}
+
+/// Contrast ratio as defined by WCAG 2.x, in the range 1.0..=21.0.
+pub fn contrast_ratio(a: &Color, b: &Color) -> f64 {
+    let (la, lb) = (a.luminance(), b.luminance());
+    (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+}
+
+/// Checks whether the code rendered by `gen` is likely to be scannable.
+pub fn lint(gen: &Generator, code: &QrCode) -> Vec<Warning> {
+    let mut warnings = vec![];
+
+    if matches!(gen.format, Format::Unicode | Format::PlainText) {
+        return warnings;
+    }
+
+    // The colors as drawn, so inverted codes are judged by what they look
+    // like. With a gradient, every stop has to work against the light color,
+    // and so do the eyes in their own color.
+    let (dark, light) = gen.colors();
+    let mut darks: Vec<Color> = match &gen.dark_gradient {
+        Some(gradient) => gradient.colors().cloned().collect(),
+        None => vec![dark],
+    };
+    darks.extend(gen.eye_color.clone());
+
+    let alpha = darks.iter().map(Color::alpha).fold(1.0, f64::min);
+    if alpha < 1.0 {
+        warnings.push(Warning::TransparentDarkColor(alpha));
+    }
+
+    if !light.is_opaque() {
+        warnings.push(Warning::TransparentLightColor(light.alpha()));
+    }
+
+    // Transparent colors are judged as they would look on a white page.
+    let light = light.over(&Color::white());
+    let darks: Vec<Color> = darks.iter().map(|d| d.over(&light)).collect();
+
+    if darks.iter().any(|d| d.luminance() > light.luminance()) {
+        warnings.push(Warning::InvertedColors);
+    }
+
+    let ratio = darks
+        .iter()
+        .map(|d| contrast_ratio(d, &light))
+        .fold(f64::INFINITY, f64::min);
+    if ratio < MIN_CONTRAST_RATIO {
+        warnings.push(Warning::LowContrast(ratio));
+    }
+
+    // The caption is drawn in the frame color on the light color, or the
+    // other way around, and has to be read as text.
+    if let Some(style) = gen.frame_style() {
+        let color = style.color.over(&light);
+        let ratio = contrast_ratio(&color, &light);
+        if ratio < MIN_CONTRAST_RATIO {
+            warnings.push(Warning::LowFrameContrast(ratio));
+        }
+    }
+
+    let size = gen.module_size(code);
+    if size < MIN_MODULE_SIZE {
+        warnings.push(Warning::SmallModules(size));
+    }
+
+    let margin = gen.margin(code);
+    let minimum = if code.version().is_micro() {
+        MIN_MICRO_QUIET_ZONE
+    } else {
+        MIN_QUIET_ZONE
+    };
+    if margin < minimum {
+        warnings.push(Warning::SmallQuietZone { margin, minimum });
+    }
+
+    warnings
+}
@@ -0,0 +1,287 @@
+use image::imageops;
+use image::imageops::FilterType;
+use image::ImageFormat;
+use image::RgbaImage;
+use qrcode::QrCode;
+use std::collections::HashSet;
+use std::error;
+use std::fmt;
+
+use crate::matrix;
+use crate::matrix::Area;
+use crate::matrix::Kind;
+use crate::matrix::Matrix;
+use crate::render::num;
+
+/// The default width of the logo, as a share of the code width.
+pub const DEFAULT_LOGO_SIZE: f64 = 0.2;
+
+/// Logos wider than this share of the code would cover too much of it even
+/// with the highest error correction level.
+pub const MAX_LOGO_SIZE: f64 = 0.4;
+
+/// The share of the error correction capacity a logo may use up. The rest
+/// is left for print defects, glare and camera noise.
+const ERROR_BUDGET: f64 = 0.6;
+
+/// Empty space between the logo and the modules around it, in modules.
+const PADDING: f64 = 0.5;
+
+/// An image composited into the center of the code.
+#[derive(Debug, Clone)]
+pub struct Logo {
+    image: RgbaImage,
+    data: Vec<u8>,
+    mime: &'static str,
+}
+
+impl Logo {
+    /// Decodes a PNG, JPEG, GIF, WebP or BMP image.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, LogoError> {
+        let format = image::guess_format(data)
+            .map_err(|e| LogoError::Image(e.to_string()))?;
+
+        let mime = match format {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Gif => "image/gif",
+            ImageFormat::WebP => "image/webp",
+            ImageFormat::Bmp => "image/bmp",
+            _ => return Err(LogoError::UnsupportedFormat),
+        };
+
+        let image = image::load_from_memory_with_format(data, format)
+            .map_err(|e| LogoError::Image(e.to_string()))?
+            .to_rgba8();
+
+        if image.width() == 0 || image.height() == 0 {
+            return Err(LogoError::Image("empty image".into()));
+        }
+
+        Ok(Self {
+            image,
+            data: data.to_vec(),
+            mime,
+        })
+    }
+
+    /// Decodes a base64 encoded image, optionally as a `data:` URI.
+    pub fn from_base64(s: &str) -> Result<Self, LogoError> {
+        let s = s.trim();
+        let s = match s.strip_prefix("data:") {
+            Some(uri) => uri.split_once(',').map(|(_, d)| d).unwrap_or(""),
+            None => s,
+        };
+
+        let data = base64::decode(s).map_err(LogoError::Base64)?;
+        Self::from_bytes(&data)
+    }
+
+    /// The original image as a `data:` URI, for SVG output.
+    pub fn data_uri(&self) -> String {
+        format!("data:{};base64,{}", self.mime, base64::encode(&self.data))
+    }
+
+    /// The area cleared for the logo in the middle of `code`, the logo
+    /// `size` of its width wide and the padding around it. Both sides are
+    /// rounded to the nearest length with the parity of the code so that the
+    /// area is centered on whole modules.
+    pub fn area(&self, code: &QrCode, size: f64) -> Area {
+        let n = code.width();
+        let parity = n % 2;
+        let fit = |len: f64| {
+            let len = len + 2.0 * PADDING - parity as f64;
+            let len = (len / 2.0).round().max(0.0) as usize * 2 + parity;
+            len.max(2 - parity).min(n)
+        };
+
+        let (w, h) = (self.image.width() as f64, self.image.height() as f64);
+        let long = n as f64 * size;
+        let (width, height) = if w >= h {
+            (fit(long), fit(long * h / w))
+        } else {
+            (fit(long * w / h), fit(long))
+        };
+
+        Area::centered(n, width, height)
+    }
+
+    /// Draws the logo scaled into `area` (moved by the quiet zone) of an
+    /// image with `module_size` pixels per module.
+    pub fn composite(
+        &self,
+        image: &mut RgbaImage,
+        area: Area,
+        module_size: u32,
+    ) {
+        let (x, y, width, height) = self.placement(area);
+        let scale = module_size as f64;
+
+        let logo = imageops::resize(
+            &self.image,
+            ((width * scale).round() as u32).max(1),
+            ((height * scale).round() as u32).max(1),
+            FilterType::Triangle,
+        );
+
+        imageops::overlay(
+            image,
+            &logo,
+            (x * scale).round() as u32,
+            (y * scale).round() as u32,
+        );
+    }
+
+    /// The `<image>` element placing the logo into `area` (moved by the
+    /// quiet zone) of an SVG in module units.
+    pub fn to_svg(&self, area: Area) -> String {
+        let (x, y, width, height) = self.placement(area);
+        format!(
+            r#"<image x="{}" y="{}" width="{}" height="{}" preserveAspectRatio="xMidYMid meet" xlink:href="{}"/>"#,
+            num(x),
+            num(y),
+            num(width),
+            num(height),
+            self.data_uri(),
+        )
+    }
+
+    /// The (x, y, width, height) of the logo in module units, fitted into
+    /// `area` with some padding and the aspect ratio kept.
+    fn placement(&self, area: Area) -> (f64, f64, f64, f64) {
+        let (aw, ah) = (
+            area.width as f64 - 2.0 * PADDING,
+            area.height as f64 - 2.0 * PADDING,
+        );
+        let (w, h) = (self.image.width() as f64, self.image.height() as f64);
+        let scale = (aw / w).min(ah / h).max(0.0);
+        let (width, height) = (w * scale, h * scale);
+
+        (
+            area.x as f64 + (area.width as f64 - width) / 2.0,
+            area.y as f64 + (area.height as f64 - height) / 2.0,
+            width,
+            height,
+        )
+    }
+}
+
+/// Checks that the code stays readable with `area` covered: no function
+/// pattern other than alignment patterns may be hidden, and the covered
+/// codewords of each block must fit in its error budget, as each block is
+/// corrected on its own.
+///
+/// Micro codes are refused: some of them end their data with a 4 bit
+/// codeword, which `matrix::codewords` doesn't know about, and they have
+/// little error correction to spare anyway.
+pub fn check(code: &QrCode, area: Area) -> Result<(), LogoError> {
+    if code.version().is_micro() {
+        return Err(LogoError::Micro);
+    }
+
+    let matrix = Matrix::new(code, 0);
+    let codewords = matrix::codewords(code);
+
+    let mut damaged = HashSet::new();
+    for (x, y) in area.positions() {
+        match matrix.kind(x, y) {
+            Kind::Finder | Kind::Timing | Kind::Info => {
+                return Err(LogoError::CoversFunctionPatterns);
+            }
+            _ => {}
+        }
+
+        if let Some(c) = codewords[y * code.width() + x] {
+            damaged.insert(c);
+        }
+    }
+
+    let (blocks, ec_per_block) = matrix::blocks(code);
+    let count = blocks.iter().max().map_or(1, |&b| b + 1);
+    let mut per_block = vec![0; count];
+    for c in damaged {
+        if let Some(&block) = blocks.get(c) {
+            per_block[block] += 1;
+        }
+    }
+
+    // Small single block codes can correct a few codewords less, which
+    // `max_allowed_errors` accounts for.
+    let allowed = if count == 1 {
+        code.max_allowed_errors()
+    } else {
+        ec_per_block / 2
+    };
+    let allowed = (allowed as f64 * ERROR_BUDGET) as usize;
+    let damaged = per_block.into_iter().max().unwrap_or(0);
+    if damaged > allowed {
+        return Err(LogoError::TooLarge { damaged, allowed });
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogoError {
+    Base64(base64::DecodeError),
+    Image(String),
+    UnsupportedFormat,
+    InvalidSize(String),
+    Micro,
+    CoversFunctionPatterns,
+    TooLarge { damaged: usize, allowed: usize },
+}
+
+impl fmt::Display for LogoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Base64(e) => write!(f, "invalid base64 logo: {}", e),
+            Self::Image(e) => write!(f, "invalid logo image: {}", e),
+            Self::UnsupportedFormat => {
+                write!(f, "unsupported logo format: expected PNG, JPEG, GIF, WebP or BMP")
+            }
+            Self::InvalidSize(s) => write!(
+                f,
+                "invalid logo size {:?}: expected a percentage of the code \
+                 width between 1 and {}",
+                s,
+                MAX_LOGO_SIZE * 100.0
+            ),
+            Self::Micro => write!(f, "logos can't be placed on micro codes"),
+            Self::CoversFunctionPatterns => {
+                write!(
+                    f,
+                    "logo is too large: it covers the finder, timing or format \
+                     patterns"
+                )
+            }
+            Self::TooLarge { damaged, allowed } => write!(
+                f,
+                "logo is too large: it hides {} codewords of an error \
+                 correction block, but only {} can be recovered; use a \
+                 smaller logo, a higher error correction level or a higher \
+                 version",
+                damaged, allowed
+            ),
+        }
+    }
+}
+
+impl error::Error for LogoError {}
+
+/// Parses a logo size like `20` or `20%` into a share of the code width.
+pub fn parse_logo_size(s: &str) -> Result<f64, LogoError> {
+    let err = || LogoError::InvalidSize(s.to_string());
+    let pct: f64 = s
+        .trim()
+        .trim_end_matches('%')
+        .trim()
+        .parse()
+        .map_err(|_| err())?;
+
+    let size = pct / 100.0;
+    if !(0.01..=MAX_LOGO_SIZE).contains(&size) {
+        return Err(err());
+    }
+    Ok(size)
+}
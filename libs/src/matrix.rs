@@ -0,0 +1,281 @@
+use qrcode::bits::Bits;
+use qrcode::ec::construct_codewords;
+use qrcode::Color as Module;
+use qrcode::QrCode;
+use qrcode::Version;
+
+/// What a module is part of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    QuietZone,
+    /// A finder pattern ("eye"), including its light separator.
+    Finder,
+    Timing,
+    Alignment,
+    /// Format and version information.
+    Info,
+    Data,
+}
+
+impl Kind {
+    /// Whether the module is part of a function pattern, i.e. one that
+    /// scanners rely on to locate and read the code rather than payload.
+    pub fn is_functional(self) -> bool {
+        !matches!(self, Self::QuietZone | Self::Data)
+    }
+}
+
+/// The module matrix of a code with its quiet zone drawn in.
+#[derive(Debug, Clone)]
+pub struct Matrix {
+    modules: Vec<Module>,
+    kinds: Vec<Kind>,
+    width: usize,
+    margin: usize,
+    finders: Vec<(usize, usize)>,
+    excavated: Option<Area>,
+}
+
+/// A rectangle of modules, in coordinates without the quiet zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Area {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Area {
+    /// An area of the given size in the middle of a code `width` modules
+    /// wide.
+    pub fn centered(code_width: usize, width: usize, height: usize) -> Self {
+        Self {
+            x: (code_width - width) / 2,
+            y: (code_width - height) / 2,
+            width,
+            height,
+        }
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.y..self.y + self.height).flat_map(move |y| {
+            (self.x..self.x + self.width).map(move |x| (x, y))
+        })
+    }
+}
+
+impl Matrix {
+    pub fn new(code: &QrCode, margin: usize) -> Self {
+        let inner = code.width();
+        let width = inner + 2 * margin;
+
+        let mut modules = vec![Module::Light; width * width];
+        let mut kinds = vec![Kind::QuietZone; width * width];
+        for (i, color) in code.to_colors().into_iter().enumerate() {
+            let (x, y) = (i % inner, i / inner);
+            let at = (y + margin) * width + x + margin;
+            modules[at] = color;
+            kinds[at] = kind(code, x, y);
+        }
+
+        let finders = if code.version().is_micro() {
+            vec![(margin, margin)]
+        } else {
+            vec![
+                (margin, margin),
+                (margin + inner - 7, margin),
+                (margin, margin + inner - 7),
+            ]
+        };
+
+        Self {
+            modules,
+            kinds,
+            width,
+            margin,
+            finders,
+            excavated: None,
+        }
+    }
+
+    /// Clears the modules of `area` to make room for an image on top.
+    pub fn excavate(&mut self, area: Area) {
+        for (x, y) in area.positions() {
+            let (x, y) = (x + self.margin, y + self.margin);
+            self.modules[y * self.width + x] = Module::Light;
+        }
+        self.excavated = Some(area);
+    }
+
+    /// The area cleared by `excavate`, moved by the quiet zone.
+    pub fn excavated(&self) -> Option<Area> {
+        self.excavated.map(|a| Area {
+            x: a.x + self.margin,
+            y: a.y + self.margin,
+            ..a
+        })
+    }
+
+    /// The number of modules per side, quiet zone included.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn margin(&self) -> usize {
+        self.margin
+    }
+
+    pub fn modules(&self) -> &[Module] {
+        &self.modules
+    }
+
+    /// The top left modules of the 7x7 finder patterns.
+    pub fn finders(&self) -> &[(usize, usize)] {
+        &self.finders
+    }
+
+    pub fn is_dark(&self, x: isize, y: isize) -> bool {
+        let w = self.width as isize;
+        if x < 0 || y < 0 || x >= w || y >= w {
+            return false;
+        }
+        self.modules[(y * w + x) as usize] == Module::Dark
+    }
+
+    pub fn kind(&self, x: usize, y: usize) -> Kind {
+        self.kinds[y * self.width + x]
+    }
+}
+
+/// Classifies a module, in coordinates without the quiet zone.
+fn kind(code: &QrCode, x: usize, y: usize) -> Kind {
+    let w = code.width();
+
+    match code.version() {
+        Version::Micro(_) => {
+            if x < 8 && y < 8 {
+                Kind::Finder
+            } else if x == 0 || y == 0 {
+                Kind::Timing
+            } else if x < 9 && y < 9 {
+                Kind::Info
+            } else {
+                Kind::Data
+            }
+        }
+
+        Version::Normal(v) => {
+            let finder =
+                (y < 8 && (x < 8 || x >= w - 8)) || (x < 8 && y >= w - 8);
+
+            let format =
+                (y < 9 && (x < 9 || x >= w - 8)) || (x < 9 && y >= w - 8);
+
+            let version = v >= 7
+                && ((x >= w - 11 && x < w - 8 && y < 6)
+                    || (y >= w - 11 && y < w - 8 && x < 6));
+
+            if finder {
+                Kind::Finder
+            } else if x == 6 || y == 6 {
+                Kind::Timing
+            } else if format || version {
+                Kind::Info
+            } else if code.is_functional(x, y) {
+                Kind::Alignment
+            } else {
+                Kind::Data
+            }
+        }
+    }
+}
+
+/// The index of the codeword each module carries a bit of, in the order
+/// they are placed (all the data codewords, then all the error correction
+/// codewords, both interleaved across the blocks). Function patterns and the
+/// remainder bits at the end don't belong to any codeword.
+///
+/// Follows the zigzag in `qrcode::canvas::DataModuleIter`: two columns at a
+/// time from the right, alternating up and down, skipping the vertical
+/// timing pattern. Unlike `QrCode::is_functional`, the version information
+/// is skipped too.
+///
+/// Every codeword is taken to be 8 bits, so the numbering is off after the
+/// 4 bit last data codeword of M1 and M3 micro codes.
+pub fn codewords(code: &QrCode) -> Vec<Option<usize>> {
+    let w = code.width() as isize;
+    let timing = if code.version().is_micro() { 0 } else { 6 };
+    let is_data = |x, y| kind(code, x, y) == Kind::Data;
+    let modules = (0..code.width() * code.width())
+        .filter(|i| is_data(i % code.width(), i / code.width()))
+        .count();
+    let total = modules / 8;
+
+    let mut codewords = vec![None; code.width() * code.width()];
+    let mut bit = 0;
+    let mut right = w - 1;
+    let mut upwards = true;
+
+    while right > 0 {
+        if right == timing {
+            right -= 1;
+        }
+
+        for i in 0..w {
+            let y = if upwards { w - 1 - i } else { i };
+            for x in [right, right - 1] {
+                let (x, y) = (x as usize, y as usize);
+                if is_data(x, y) {
+                    if bit / 8 < total {
+                        codewords[y * code.width() + x] = Some(bit / 8);
+                    }
+                    bit += 1;
+                }
+            }
+        }
+
+        upwards = !upwards;
+        right -= 2;
+    }
+
+    codewords
+}
+
+/// The error correction block of each codeword, in the order `codewords`
+/// numbers them, and the number of error correction codewords per block.
+///
+/// `qrcode` keeps its block table to itself, so the blocks are found from
+/// how the data codewords get interleaved: the first codeword of every
+/// block comes first, then the second, and so on. The positions take two
+/// bytes, so they are interleaved a byte at a time.
+pub fn blocks(code: &QrCode) -> (Vec<usize>, usize) {
+    let (version, ec_level) = (code.version(), code.error_correction_level());
+    let bits = Bits::new(version)
+        .max_len(ec_level)
+        .expect("the code has a valid version");
+    let len = bits.div_ceil(8);
+
+    let interleave = |byte: &dyn Fn(usize) -> u8| {
+        let raw: Vec<u8> = (0..len).map(byte).collect();
+        construct_codewords(&raw, version, ec_level)
+            .expect("the code has a valid version")
+    };
+    let (low, ec) = interleave(&|i| i as u8);
+    let (high, _) = interleave(&|i| (i >> 8) as u8);
+    let positions: Vec<usize> = low
+        .iter()
+        .zip(&high)
+        .map(|(&low, &high)| (high as usize) << 8 | low as usize)
+        .collect();
+
+    // Every block has at least two data codewords.
+    let count = positions.iter().position(|&p| p == 1).unwrap_or(1);
+    let starts = &positions[..count];
+
+    let blocks = positions
+        .iter()
+        .map(|&p| starts.iter().rposition(|&start| start <= p).unwrap_or(0))
+        .chain((0..ec.len()).map(|i| i % count))
+        .collect();
+    (blocks, ec.len() / count)
+}
@@ -0,0 +1,41 @@
+//! Post-processing of encoded images to add metadata the encoders of the
+//! `image` crate don't write.
+
+const PNG_SIGNATURE_LEN: usize = 8;
+const PNG_IHDR_LEN: usize = 4 + 4 + 13 + 4;
+
+/// Inserts a chunk right after the IHDR chunk of an encoded PNG.
+pub fn insert_png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    let mut chunk = Vec::with_capacity(data.len() + 12);
+    chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(kind);
+    chunk.extend_from_slice(data);
+
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&chunk[4..]);
+    chunk.extend_from_slice(&hasher.finalize().to_be_bytes());
+
+    let at = PNG_SIGNATURE_LEN + PNG_IHDR_LEN;
+    png.splice(at..at, chunk);
+}
+
+/// Marks an encoded PNG as sRGB, with the gAMA fallback recommended by the
+/// PNG spec for decoders that don't understand the sRGB chunk.
+pub fn png_srgb(png: &mut Vec<u8>) {
+    insert_png_chunk(png, b"gAMA", &45455u32.to_be_bytes());
+    // Rendering intent 0: perceptual.
+    insert_png_chunk(png, b"sRGB", &[0]);
+}
+
+/// Writes the resolution into an encoded PNG as a pHYs chunk.
+pub fn png_dpi(png: &mut Vec<u8>, dpi: u32) {
+    let ppm = (dpi as f64 / 0.0254).round() as u32;
+
+    let mut data = Vec::with_capacity(9);
+    data.extend_from_slice(&ppm.to_be_bytes());
+    data.extend_from_slice(&ppm.to_be_bytes());
+    // Unit specifier 1: meter.
+    data.push(1);
+
+    insert_png_chunk(png, b"pHYs", &data);
+}
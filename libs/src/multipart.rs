@@ -0,0 +1,114 @@
+//! A minimal `multipart/form-data` parser, enough for uploading the data
+//! and a logo in one request on both servers.
+
+use std::error;
+use std::fmt;
+
+#[derive(Debug, Clone, Default)]
+pub struct Part {
+    pub name: Option<String>,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// The boundary of a `multipart/form-data` content type, if it is one.
+pub fn boundary(content_type: &str) -> Option<String> {
+    let mut params = content_type.split(';');
+    let mime = params.next()?.trim();
+    if !mime.eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+
+    params.find_map(|p| {
+        let (key, value) = p.split_once('=')?;
+        if key.trim().eq_ignore_ascii_case("boundary") {
+            Some(value.trim().trim_matches('"').to_string())
+        } else {
+            None
+        }
+    })
+}
+
+pub fn parse(body: &[u8], boundary: &str) -> Result<Vec<Part>, MultipartError> {
+    let delimiter = format!("--{}", boundary).into_bytes();
+
+    let mut rest = match find(body, &delimiter) {
+        Some(i) => &body[i + delimiter.len()..],
+        None => return Err(MultipartError::MissingBoundary),
+    };
+
+    let mut parts = vec![];
+    loop {
+        if rest.starts_with(b"--") {
+            return Ok(parts);
+        }
+        rest = rest
+            .strip_prefix(b"\r\n")
+            .ok_or(MultipartError::Malformed)?;
+
+        let end = find(rest, b"\r\n\r\n").ok_or(MultipartError::Malformed)?;
+        let headers = std::str::from_utf8(&rest[..end])
+            .map_err(|_| MultipartError::Malformed)?;
+        rest = &rest[end + 4..];
+
+        let next = [b"\r\n", &delimiter[..]].concat();
+        let end = find(rest, &next).ok_or(MultipartError::Malformed)?;
+
+        let mut part = Part {
+            data: rest[..end].to_vec(),
+            ..Default::default()
+        };
+        rest = &rest[end + next.len()..];
+
+        for line in headers.split("\r\n") {
+            let (key, value) = match line.split_once(':') {
+                Some(kv) => kv,
+                None => continue,
+            };
+
+            if key.trim().eq_ignore_ascii_case("content-type") {
+                part.content_type = Some(value.trim().to_string());
+            } else if key.trim().eq_ignore_ascii_case("content-disposition") {
+                part.name = param(value, "name");
+                part.filename = param(value, "filename");
+            }
+        }
+
+        parts.push(part);
+    }
+}
+
+fn param(header: &str, key: &str) -> Option<String> {
+    header.split(';').skip(1).find_map(|p| {
+        let (k, v) = p.split_once('=')?;
+        if k.trim().eq_ignore_ascii_case(key) {
+            Some(v.trim().trim_matches('"').to_string())
+        } else {
+            None
+        }
+    })
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MultipartError {
+    MissingBoundary,
+    Malformed,
+}
+
+impl fmt::Display for MultipartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingBoundary => {
+                write!(f, "invalid multipart body: boundary not found")
+            }
+            Self::Malformed => write!(f, "invalid multipart body"),
+        }
+    }
+}
+
+impl error::Error for MultipartError {}
@@ -0,0 +1,171 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// The IBAN lengths of the countries taking part in SEPA.
+const IBAN_LENGTHS: &[(&str, usize)] = &[
+    ("AD", 24),
+    ("AT", 20),
+    ("BE", 16),
+    ("BG", 22),
+    ("CH", 21),
+    ("CY", 28),
+    ("CZ", 24),
+    ("DE", 22),
+    ("DK", 18),
+    ("EE", 20),
+    ("ES", 24),
+    ("FI", 18),
+    ("FR", 27),
+    ("GB", 22),
+    ("GI", 23),
+    ("GR", 27),
+    ("HR", 21),
+    ("HU", 28),
+    ("IE", 22),
+    ("IS", 26),
+    ("IT", 27),
+    ("LI", 21),
+    ("LT", 20),
+    ("LU", 20),
+    ("LV", 21),
+    ("MC", 27),
+    ("MT", 31),
+    ("NL", 18),
+    ("NO", 15),
+    ("PL", 28),
+    ("PT", 25),
+    ("RO", 24),
+    ("SE", 24),
+    ("SI", 19),
+    ("SK", 24),
+    ("SM", 27),
+    ("VA", 22),
+];
+
+/// The remainder of the ISO 7064 MOD 97-10 check over digits and letters,
+/// where `A` counts as 10 and `Z` as 35.
+fn mod97(s: &str) -> u32 {
+    s.chars().fold(0, |rest, c| match c.to_digit(36) {
+        Some(d) if d < 10 => (rest * 10 + d) % 97,
+        Some(d) => (rest * 100 + d) % 97,
+        None => rest,
+    })
+}
+
+/// Strips the spaces of the printed form and upper cases.
+fn electronic(s: &str) -> String {
+    s.chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// An International Bank Account Number of a SEPA country.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Iban(String);
+
+impl Iban {
+    pub fn country(&self) -> &str {
+        &self.0[..2]
+    }
+
+    /// Whether this is a Swiss QR-IBAN, which takes QR references only. Its
+    /// bank id is in the range 30000 to 31999.
+    pub fn is_qr_iban(&self) -> bool {
+        matches!(self.country(), "CH" | "LI")
+            && self.0[4..9]
+                .parse::<u32>()
+                .is_ok_and(|id| (30000..=31999).contains(&id))
+    }
+}
+
+impl FromStr for Iban {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let iban = electronic(s);
+        if iban.len() < 5 || !iban.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(format!("{:?} is not an IBAN", s));
+        }
+
+        let country = &iban[..2];
+        let length = IBAN_LENGTHS
+            .iter()
+            .find(|(c, _)| *c == country)
+            .map(|(_, length)| *length)
+            .ok_or_else(|| format!("{:?} is not a SEPA country", country))?;
+
+        if iban.len() != length {
+            return Err(format!(
+                "{} IBANs have {} characters, not {}",
+                country,
+                length,
+                iban.len()
+            ));
+        }
+
+        if mod97(&format!("{}{}", &iban[4..], &iban[..4])) != 1 {
+            return Err(format!("{:?} has a wrong check digit", s));
+        }
+
+        Ok(Self(iban))
+    }
+}
+
+impl fmt::Display for Iban {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A Business Identifier Code. Unlike an IBAN it has no check digits, so
+/// only its structure is checked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bic(String);
+
+impl FromStr for Bic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bic = electronic(s);
+        let letters = |s: &str| s.chars().all(|c| c.is_ascii_uppercase());
+        let alphanumeric =
+            |s: &str| s.chars().all(|c| c.is_ascii_alphanumeric());
+
+        let valid = matches!(bic.len(), 8 | 11)
+            && bic.is_ascii()
+            && letters(&bic[..6])
+            && alphanumeric(&bic[6..]);
+        if !valid {
+            return Err(format!(
+                "{:?} is not a BIC, expected 8 or 11 characters",
+                s
+            ));
+        }
+        Ok(Self(bic))
+    }
+}
+
+impl fmt::Display for Bic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Checks an ISO 11649 creditor reference, `RF` with two check digits and
+/// up to 21 digits and letters, returning its electronic format.
+pub fn creditor_reference(s: &str) -> Result<String, String> {
+    let reference = electronic(s);
+    let valid = (5..=25).contains(&reference.len())
+        && reference.chars().all(|c| c.is_ascii_alphanumeric())
+        && reference.starts_with("RF")
+        && reference[2..4].chars().all(|c| c.is_ascii_digit());
+
+    if !valid {
+        return Err(format!("{:?} is not an RF creditor reference", s));
+    }
+    if mod97(&format!("{}{}", &reference[4..], &reference[..4])) != 1 {
+        return Err(format!("{:?} has a wrong check digit", s));
+    }
+    Ok(reference)
+}
@@ -0,0 +1,243 @@
+use sha2::Digest;
+use sha2::Sha256;
+use std::fmt;
+
+use super::check_chars;
+use super::format_amount;
+use super::parse_amount;
+use super::percent_encode;
+use super::Fields;
+use super::PayloadError;
+
+/// All the bitcoin there will ever be, in satoshis.
+const MAX_AMOUNT: u64 = 21_000_000 * 100_000_000;
+
+const BECH32_CHARSET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+const BASE58_ALPHABET: &str =
+    "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// The checksum constants of BIP 173 for witness version 0 and of BIP 350
+/// for later versions.
+const BECH32: u32 = 1;
+const BECH32M: u32 = 0x2bc8_30a3;
+
+fn polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+
+    values.iter().fold(1, |check, &value| {
+        let top = check >> 25;
+        let check = (check & 0x01ff_ffff) << 5 ^ value as u32;
+        (0..5)
+            .filter(|i| (top >> i) & 1 == 1)
+            .fold(check, |check, i| check ^ GENERATOR[i])
+    })
+}
+
+/// Regroups bits, e.g. the 5 bit groups of bech32 into bytes. `None` if
+/// more than 4 bits are left over or they are not zero.
+fn convert_bits(data: &[u8], from: u32, to: u32) -> Option<Vec<u8>> {
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut converted = vec![];
+    for &value in data {
+        acc = acc << from | value as u32;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            converted.push((acc >> bits & ((1 << to) - 1)) as u8);
+        }
+    }
+    if bits >= from || acc & ((1 << bits) - 1) != 0 {
+        return None;
+    }
+    Some(converted)
+}
+
+/// Whether an address is a segregated witness one, rather than legacy.
+fn is_segwit(address: &str) -> bool {
+    let prefix = address.get(..3).unwrap_or_default();
+    prefix.eq_ignore_ascii_case("bc1") || prefix.eq_ignore_ascii_case("tb1")
+}
+
+/// Checks a segregated witness address of BIP 173 or BIP 350.
+fn check_segwit(address: &str) -> Result<(), String> {
+    let lower = address.to_lowercase();
+    if address != lower && address != address.to_uppercase() {
+        return Err("mixes upper and lower case".into());
+    }
+
+    let (hrp, data) = lower.rsplit_once('1').ok_or("has no separator")?;
+    if !matches!(hrp, "bc" | "tb") {
+        return Err(format!("{:?} is not a bitcoin network", hrp));
+    }
+    let data = data
+        .chars()
+        .map(|c| BECH32_CHARSET.find(c).map(|i| i as u8))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or("holds a character bech32 does not use")?;
+    if data.len() < 7 || lower.len() > 90 {
+        return Err("has a wrong length".into());
+    }
+
+    let mut values: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+    values.push(0);
+    values.extend(hrp.bytes().map(|b| b & 31));
+    values.extend(&data);
+
+    let version = data[0];
+    let constant = if version == 0 { BECH32 } else { BECH32M };
+    if version > 16 || polymod(&values) != constant {
+        return Err("has a wrong checksum".into());
+    }
+
+    let program = convert_bits(&data[1..data.len() - 6], 5, 8)
+        .ok_or("has a malformed witness program")?;
+    let length = program.len();
+    if !(2..=40).contains(&length)
+        || version == 0 && length != 20 && length != 32
+    {
+        return Err("has a witness program of a wrong length".into());
+    }
+    Ok(())
+}
+
+/// Checks a legacy pay to public key hash or script hash address.
+fn check_base58(address: &str) -> Result<(), String> {
+    let mut bytes: Vec<u8> = vec![];
+    for c in address.chars() {
+        let mut carry = BASE58_ALPHABET
+            .find(c)
+            .ok_or("holds a character base58 does not use")?
+            as u32;
+        for byte in bytes.iter_mut().rev() {
+            carry += *byte as u32 * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+    let zeros = address.chars().take_while(|&c| c == '1').count();
+    let mut decoded = vec![0; zeros];
+    decoded.extend(bytes);
+
+    if decoded.len() != 25 {
+        return Err("has a wrong length".into());
+    }
+    if !matches!(decoded[0], 0x00 | 0x05 | 0x6f | 0xc4) {
+        return Err("is not of a bitcoin network".into());
+    }
+    let hash = Sha256::digest(Sha256::digest(&decoded[..21]));
+    if hash[..4] != decoded[21..] {
+        return Err("has a wrong checksum".into());
+    }
+    Ok(())
+}
+
+/// A BIP 21 payment request, as the `bitcoin:` URIs wallets open.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bitcoin {
+    pub address: String,
+    /// In satoshis, left to the payer if not given.
+    pub amount: Option<u64>,
+    /// The payee.
+    pub label: Option<String>,
+    pub message: Option<String>,
+}
+
+impl Bitcoin {
+    /// Reads the `address`, `amount` in BTC, `label` and `message` fields.
+    pub fn from_fields(fields: &Fields) -> Result<Self, PayloadError> {
+        let text = |name: &'static str| {
+            fields
+                .get(name)
+                .map(|value| {
+                    let value = value.trim();
+                    check_chars(name, value, |c| !c.is_control())?;
+                    Ok(value.to_string())
+                })
+                .transpose()
+        };
+
+        let address = fields.require("address")?.trim();
+        let checked = if is_segwit(address) {
+            check_segwit(address)
+        } else {
+            check_base58(address)
+        };
+        checked.map_err(|e| {
+            PayloadError::invalid(
+                "address",
+                format!("{:?} is not a bitcoin address, it {}", address, e),
+            )
+        })?;
+
+        let amount = match fields.get("amount") {
+            Some(amount) => {
+                let satoshis = parse_amount(amount.trim(), 8)
+                    .map_err(|e| PayloadError::invalid("amount", e))?;
+                if satoshis == 0 || satoshis > MAX_AMOUNT {
+                    return Err(PayloadError::invalid(
+                        "amount",
+                        "expected 0.00000001 to 21000000",
+                    ));
+                }
+                Some(satoshis)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            address: address.to_string(),
+            amount,
+            label: text("label")?,
+            message: text("message")?,
+        })
+    }
+}
+
+/// The `bitcoin:` URI. A bare segwit address is written in upper case to
+/// fit the smaller alphanumeric mode, as BIP 173 suggests for QR codes.
+impl fmt::Display for Bitcoin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bare = self.amount.is_none()
+            && self.label.is_none()
+            && self.message.is_none();
+        if bare && is_segwit(&self.address) {
+            return write!(f, "BITCOIN:{}", self.address.to_uppercase());
+        }
+
+        let address = if is_segwit(&self.address) {
+            self.address.to_lowercase()
+        } else {
+            self.address.clone()
+        };
+        write!(f, "bitcoin:{}", address)?;
+
+        let mut params = vec![];
+        if let Some(amount) = self.amount {
+            let amount = format_amount(amount, 8);
+            let amount = amount.trim_end_matches('0').trim_end_matches('.');
+            params.push(format!("amount={}", amount));
+        }
+        if let Some(label) = &self.label {
+            params.push(format!("label={}", percent_encode(label)));
+        }
+        if let Some(message) = &self.message {
+            params.push(format!("message={}", percent_encode(message)));
+        }
+        if !params.is_empty() {
+            write!(f, "?{}", params.join("&"))?;
+        }
+        Ok(())
+    }
+}
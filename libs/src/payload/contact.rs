@@ -0,0 +1,221 @@
+use std::str::FromStr;
+
+use super::check_chars;
+use super::check_email;
+use super::check_url;
+use super::escape;
+use super::escape_text;
+use super::fold;
+use super::Fields;
+use super::PayloadError;
+use crate::error::InvalidValue;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ContactFormat {
+    /// Read by the most phones.
+    #[default]
+    VCard3,
+    VCard4,
+    MeCard,
+    /// Whichever of the others comes out shortest.
+    Smallest,
+}
+
+impl FromStr for ContactFormat {
+    type Err = InvalidValue;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "vcard" | "vcard3" | "vcard 3.0" => Ok(Self::VCard3),
+            "vcard4" | "vcard 4.0" => Ok(Self::VCard4),
+            "mecard" => Ok(Self::MeCard),
+            "smallest" => Ok(Self::Smallest),
+            _ => {
+                Err(InvalidValue::new(s, "vcard3, vcard4, mecard or smallest"))
+            }
+        }
+    }
+}
+
+/// A business card.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Contact {
+    /// The full name, `First Last` or `Last, First`.
+    pub name: String,
+    pub org: Option<String>,
+    pub title: Option<String>,
+    pub phones: Vec<String>,
+    pub emails: Vec<String>,
+    pub address: Option<String>,
+    pub url: Option<String>,
+}
+
+impl Contact {
+    /// Reads the `name`, `org`, `title`, `phone`, `email`, `address` and
+    /// `url` fields. Phones and emails may repeat, also as `phones` and
+    /// `emails`.
+    pub fn from_fields(fields: &Fields) -> Result<Self, PayloadError> {
+        let text = |name| fields.get(name).map(|v| v.trim().to_string());
+        let list = |one, many| {
+            fields
+                .all(one)
+                .chain(fields.all(many))
+                .map(|v| v.trim().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let contact = Self {
+            name: fields.require("name")?.trim().to_string(),
+            org: text("org"),
+            title: text("title"),
+            phones: list("phone", "phones"),
+            emails: list("email", "emails"),
+            address: text("address"),
+            url: text("url"),
+        };
+
+        // Line breaks are escaped in the text fields, nothing else that
+        // would end a line is let through.
+        let text_fields = [
+            ("name", Some(&contact.name)),
+            ("org", contact.org.as_ref()),
+            ("title", contact.title.as_ref()),
+            ("address", contact.address.as_ref()),
+        ];
+        for (name, value) in text_fields.iter() {
+            if let Some(value) = value {
+                check_chars(name, value, |c| {
+                    c == '\r' || c == '\n' || !c.is_control()
+                })?;
+            }
+        }
+
+        for phone in &contact.phones {
+            let valid = phone.chars().enumerate().all(|(i, c)| {
+                c.is_ascii_digit() || " -().".contains(c) || c == '+' && i == 0
+            });
+            if !valid || !phone.chars().any(|c| c.is_ascii_digit()) {
+                return Err(PayloadError::invalid(
+                    "phone",
+                    format!("{:?} is not a phone number", phone),
+                ));
+            }
+        }
+
+        for email in &contact.emails {
+            check_email("email", email)?;
+        }
+
+        if let Some(url) = &contact.url {
+            check_url("url", url)?;
+        }
+
+        Ok(contact)
+    }
+
+    pub fn encode(&self, format: ContactFormat) -> String {
+        match format {
+            ContactFormat::VCard3 => self.vcard("3.0"),
+            ContactFormat::VCard4 => self.vcard("4.0"),
+            ContactFormat::MeCard => self.mecard(),
+            ContactFormat::Smallest => {
+                let candidates =
+                    [self.vcard("3.0"), self.vcard("4.0"), self.mecard()];
+                candidates
+                    .iter()
+                    .min_by_key(|c| c.len())
+                    .cloned()
+                    .unwrap_or_default()
+            }
+        }
+    }
+
+    /// The family name and the given names.
+    fn split_name(&self) -> (&str, &str) {
+        match self.name.split_once(',') {
+            Some((family, given)) => (family.trim(), given.trim()),
+            None => match self.name.rsplit_once(' ') {
+                Some((given, family)) => (family.trim(), given.trim()),
+                None => (self.name.as_str(), ""),
+            },
+        }
+    }
+
+    /// A vCard as of RFC 2426 (3.0) or RFC 6350 (4.0).
+    fn vcard(&self, version: &str) -> String {
+        let (family, given) = self.split_name();
+
+        let mut lines = vec![
+            "BEGIN:VCARD".to_string(),
+            format!("VERSION:{}", version),
+            format!("N:{};{};;;", escape_text(family), escape_text(given)),
+            format!(
+                "FN:{}",
+                escape_text(format!("{} {}", given, family).trim())
+            ),
+        ];
+        if let Some(org) = &self.org {
+            lines.push(format!("ORG:{}", escape_text(org)));
+        }
+        if let Some(title) = &self.title {
+            lines.push(format!("TITLE:{}", escape_text(title)));
+        }
+        for phone in &self.phones {
+            lines.push(format!("TEL:{}", phone));
+        }
+        for email in &self.emails {
+            lines.push(format!("EMAIL:{}", email));
+        }
+        if let Some(address) = &self.address {
+            lines.push(format!("ADR:;;{};;;;", escape_text(address)));
+        }
+        if let Some(url) = &self.url {
+            lines.push(format!("URL:{}", url));
+        }
+        lines.push("END:VCARD".into());
+
+        lines
+            .iter()
+            .map(|l| fold(l))
+            .collect::<Vec<_>>()
+            .join("\r\n")
+    }
+
+    /// A MeCard, as introduced by NTT Docomo. It has no field for the job
+    /// title, so that goes into the note.
+    fn mecard(&self) -> String {
+        let text = |s: &str| {
+            escape(s, &[';', ',', ':'])
+                .replace("\r\n", " ")
+                .replace(&['\r', '\n'][..], " ")
+        };
+        let (family, given) = self.split_name();
+
+        let mut mecard = format!("MECARD:N:{}", text(family));
+        if !given.is_empty() {
+            mecard.push_str(&format!(",{}", text(given)));
+        }
+        mecard.push(';');
+
+        if let Some(org) = &self.org {
+            mecard.push_str(&format!("ORG:{};", text(org)));
+        }
+        for phone in &self.phones {
+            mecard.push_str(&format!("TEL:{};", text(phone)));
+        }
+        for email in &self.emails {
+            mecard.push_str(&format!("EMAIL:{};", text(email)));
+        }
+        if let Some(address) = &self.address {
+            mecard.push_str(&format!("ADR:{};", text(address)));
+        }
+        if let Some(url) = &self.url {
+            mecard.push_str(&format!("URL:{};", text(url)));
+        }
+        if let Some(title) = &self.title {
+            mecard.push_str(&format!("NOTE:{};", text(title)));
+        }
+        mecard.push(';');
+        mecard
+    }
+}
@@ -0,0 +1,339 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use super::escape_text;
+use super::fold;
+use super::Fields;
+use super::PayloadError;
+
+/// The recurrence rule parts of RFC 5545.
+const RRULE_PARTS: &[&str] = &[
+    "FREQ",
+    "UNTIL",
+    "COUNT",
+    "INTERVAL",
+    "BYSECOND",
+    "BYMINUTE",
+    "BYHOUR",
+    "BYDAY",
+    "BYMONTHDAY",
+    "BYYEARDAY",
+    "BYWEEKNO",
+    "BYMONTH",
+    "BYSETPOS",
+    "WKST",
+];
+
+const FREQUENCIES: &[&str] = &[
+    "SECONDLY", "MINUTELY", "HOURLY", "DAILY", "WEEKLY", "MONTHLY", "YEARLY",
+];
+
+/// A date, or a date and a time of day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    /// Hours, minutes and seconds, `None` for all day.
+    pub time: Option<(u8, u8, u8)>,
+    /// Whether the time is in UTC rather than in the event's time zone.
+    pub utc: bool,
+}
+
+impl DateTime {
+    fn days_in_month(year: u16, month: u8) -> u8 {
+        let leap = year.is_multiple_of(4)
+            && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+        match month {
+            2 if leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+
+    fn next_day(mut self) -> Self {
+        self.day += 1;
+        if self.day > Self::days_in_month(self.year, self.month) {
+            self.day = 1;
+            self.month += 1;
+        }
+        if self.month > 12 {
+            self.month = 1;
+            self.year += 1;
+        }
+        self
+    }
+}
+
+/// Reads `2024-05-04`, `2024-05-04T09:30`, `2024-05-04 09:30:00` or
+/// `20240504T093000`, each with an optional `Z` for UTC.
+impl FromStr for DateTime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || {
+            format!(
+                "{:?} is not a date, expected YYYY-MM-DD or \
+                 YYYY-MM-DDTHH:MM[:SS][Z]",
+                s
+            )
+        };
+
+        let trimmed = s.trim();
+        let (value, utc) = match trimmed.strip_suffix(['Z', 'z']) {
+            Some(value) => (value, true),
+            None => (trimmed, false),
+        };
+
+        let compact: String =
+            value.chars().filter(|c| !matches!(c, '-' | ':')).collect();
+        let (date, time) = match compact.split_once(['T', 't', ' ']) {
+            Some((date, time)) => (date, Some(time)),
+            None => (compact.as_str(), None),
+        };
+
+        let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+        if date.len() != 8 || !digits(date) {
+            return Err(err());
+        }
+        let number = |range: std::ops::Range<usize>, s: &str| {
+            s[range].parse::<u8>().map_err(|_| err())
+        };
+
+        let year: u16 = date[..4].parse().map_err(|_| err())?;
+        let month = number(4..6, date)?;
+        let day = number(6..8, date)?;
+        if !(1..=12).contains(&month)
+            || day == 0
+            || day > Self::days_in_month(year, month)
+        {
+            return Err(format!("{:?} is not a valid date", s));
+        }
+
+        let time = match time {
+            None if utc => return Err(err()),
+            None => None,
+            Some(time) if !digits(time) => return Err(err()),
+            Some(time) if time.len() == 4 || time.len() == 6 => {
+                let seconds = if time.len() == 6 {
+                    number(4..6, time)?
+                } else {
+                    0
+                };
+                let time = (number(0..2, time)?, number(2..4, time)?, seconds);
+                if time.0 > 23 || time.1 > 59 || time.2 > 60 {
+                    return Err(format!("{:?} is not a valid time", s));
+                }
+                Some(time)
+            }
+            Some(_) => return Err(err()),
+        };
+
+        Ok(Self {
+            year,
+            month,
+            day,
+            time,
+            utc,
+        })
+    }
+}
+
+/// The iCalendar value, `20240504` or `20240504T093000Z`.
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}{:02}{:02}", self.year, self.month, self.day)?;
+        if let Some((h, m, s)) = self.time {
+            write!(f, "T{:02}{:02}{:02}", h, m, s)?;
+        }
+        if self.utc {
+            f.write_str("Z")?;
+        }
+        Ok(())
+    }
+}
+
+/// A calendar event, as the `VEVENT` component of RFC 5545 that phones
+/// offer to add to the calendar. The `UID` and `DTSTAMP` a calendar file
+/// needs are left to the calendar app.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub summary: String,
+    pub start: DateTime,
+    /// The last day of an all day event, or the end of a timed one.
+    pub end: Option<DateTime>,
+    /// An IANA time zone, e.g. `Europe/Berlin`, for times not in UTC.
+    pub timezone: Option<String>,
+    pub location: Option<String>,
+    pub description: Option<String>,
+    /// An `RRULE` value, e.g. `FREQ=WEEKLY;COUNT=4`.
+    pub recurrence: Option<String>,
+}
+
+impl Event {
+    /// Reads the `summary`, `start`, `end`, `tz`, `location`,
+    /// `description` and `rrule` fields.
+    pub fn from_fields(fields: &Fields) -> Result<Self, PayloadError> {
+        let text = |name| fields.get(name).map(|v| v.trim().to_string());
+        let date = |name| {
+            fields
+                .get(name)
+                .map(|v| v.parse().map_err(|e| PayloadError::invalid(name, e)))
+                .transpose()
+        };
+
+        let event = Self {
+            summary: fields.require("summary")?.trim().to_string(),
+            start: date("start")?.ok_or(PayloadError::Missing("start"))?,
+            end: date("end")?,
+            timezone: text("tz"),
+            location: text("location"),
+            description: text("description"),
+            recurrence: fields.get("rrule").map(|r| {
+                let r = r.trim();
+                r.strip_prefix("RRULE:").unwrap_or(r).to_uppercase()
+            }),
+        };
+        event.validate()?;
+        Ok(event)
+    }
+
+    fn validate(&self) -> Result<(), PayloadError> {
+        let all_day = self.start.time.is_none();
+
+        if let Some(end) = &self.end {
+            if end.time.is_none() != all_day {
+                return Err(PayloadError::invalid(
+                    "end",
+                    "give both start and end with a time, or neither",
+                ));
+            }
+            if end.utc != self.start.utc {
+                return Err(PayloadError::invalid(
+                    "end",
+                    "give both start and end in UTC, or neither",
+                ));
+            }
+            let order = end.cmp(&self.start);
+            if order == Ordering::Less || order == Ordering::Equal && !all_day {
+                return Err(PayloadError::invalid("end", "before the start"));
+            }
+        }
+
+        if let Some(tz) = &self.timezone {
+            if all_day || self.start.utc {
+                return Err(PayloadError::invalid(
+                    "tz",
+                    "only used with local times",
+                ));
+            }
+            let valid = tz.len() <= 64
+                && tz
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "/_-+".contains(c));
+            if !valid {
+                return Err(PayloadError::invalid(
+                    "tz",
+                    format!("{:?} is not a time zone", tz),
+                ));
+            }
+        }
+
+        if let Some(rule) = &self.recurrence {
+            validate_rrule(rule)
+                .map_err(|e| PayloadError::invalid("rrule", e))?;
+        }
+
+        Ok(())
+    }
+
+    fn date_line(&self, name: &str, date: &DateTime) -> String {
+        match (&date.time, &self.timezone) {
+            (None, _) => format!("{};VALUE=DATE:{}", name, date),
+            (Some(_), Some(tz)) => format!("{};TZID={}:{}", name, tz, date),
+            (Some(_), None) => format!("{}:{}", name, date),
+        }
+    }
+}
+
+/// The `BEGIN:VEVENT` ... `END:VEVENT` payload.
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines = vec![
+            "BEGIN:VEVENT".to_string(),
+            format!("SUMMARY:{}", escape_text(&self.summary)),
+            self.date_line("DTSTART", &self.start),
+        ];
+
+        // The end of an all day event is the day after, exclusive.
+        match self.end {
+            Some(end) if end.time.is_none() => {
+                lines.push(self.date_line("DTEND", &end.next_day()))
+            }
+            Some(end) => lines.push(self.date_line("DTEND", &end)),
+            None => {}
+        }
+
+        if let Some(location) = &self.location {
+            lines.push(format!("LOCATION:{}", escape_text(location)));
+        }
+        if let Some(description) = &self.description {
+            lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+        }
+        if let Some(rule) = &self.recurrence {
+            lines.push(format!("RRULE:{}", rule));
+        }
+        lines.push("END:VEVENT".into());
+
+        let lines: Vec<String> = lines.iter().map(|l| fold(l)).collect();
+        f.write_str(&lines.join("\r\n"))
+    }
+}
+
+fn validate_rrule(rule: &str) -> Result<(), String> {
+    let mut names = vec![];
+
+    for part in rule.split(';') {
+        let (name, value) = part
+            .split_once('=')
+            .ok_or_else(|| format!("expected NAME=VALUE, got {:?}", part))?;
+
+        if !RRULE_PARTS.contains(&name) {
+            return Err(format!("unknown part {:?}", name));
+        }
+        if names.contains(&name) {
+            return Err(format!("{} is given twice", name));
+        }
+        names.push(name);
+
+        // Nothing that would end the line, or the rule part.
+        let plain = !value.is_empty()
+            && value.chars().all(|c| {
+                c.is_ascii_uppercase()
+                    || c.is_ascii_digit()
+                    || ",+-".contains(c)
+            });
+        let valid = plain
+            && match name {
+                "FREQ" => FREQUENCIES.contains(&value),
+                "COUNT" | "INTERVAL" => {
+                    value.parse::<u32>().is_ok_and(|n| n > 0)
+                }
+                "UNTIL" => value.parse::<DateTime>().is_ok(),
+                _ => true,
+            };
+        if !valid {
+            return Err(format!("invalid {} {:?}", name, value));
+        }
+    }
+
+    if !names.contains(&"FREQ") {
+        return Err("FREQ is required".into());
+    }
+    if names.contains(&"COUNT") && names.contains(&"UNTIL") {
+        return Err("COUNT and UNTIL exclude each other".into());
+    }
+    Ok(())
+}
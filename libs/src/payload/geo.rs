@@ -0,0 +1,65 @@
+use std::fmt;
+
+use super::percent_encode;
+use super::Fields;
+use super::PayloadError;
+
+/// Reads a coordinate in decimal degrees, e.g. `-33.8568`, keeping it as
+/// written to not add float noise.
+fn coordinate(
+    fields: &Fields,
+    name: &'static str,
+    max: f64,
+) -> Result<String, PayloadError> {
+    let value = fields.require(name)?.trim();
+    let value = value.strip_prefix('+').unwrap_or(value);
+
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, "0"));
+    let decimal = !whole.is_empty()
+        && !fraction.is_empty()
+        && whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit());
+
+    match value.parse::<f64>() {
+        Ok(degrees) if decimal && degrees.abs() <= max => Ok(value.to_string()),
+        _ => Err(PayloadError::invalid(
+            name,
+            format!("{:?} is not in degrees from -{} to {}", value, max, max),
+        )),
+    }
+}
+
+/// A place on the map, as the `geo:` URIs of RFC 5870 that map apps open.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Geo {
+    /// Decimal degrees, as written.
+    pub latitude: String,
+    pub longitude: String,
+    /// A search or place name, shown by some apps instead of the position.
+    pub query: Option<String>,
+}
+
+impl Geo {
+    /// Reads the `lat`, `lon` and `query` fields.
+    pub fn from_fields(fields: &Fields) -> Result<Self, PayloadError> {
+        Ok(Self {
+            latitude: coordinate(fields, "lat", 90.0)?,
+            longitude: coordinate(fields, "lon", 180.0)?,
+            query: fields.get("query").map(|q| q.trim().to_string()),
+        })
+    }
+}
+
+/// The `geo:lat,lon?q=query` URI.
+impl fmt::Display for Geo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "geo:{},{}", self.latitude, self.longitude)?;
+        if let Some(query) = &self.query {
+            write!(f, "?q={}", percent_encode(query))?;
+        }
+        Ok(())
+    }
+}
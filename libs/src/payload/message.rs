@@ -0,0 +1,194 @@
+use std::fmt;
+
+use super::check_chars;
+use super::check_email;
+use super::percent_encode;
+use super::Fields;
+use super::PayloadError;
+
+/// Normalizes a phone number in international format, e.g.
+/// `+49 (30) 1234-567` or `0049 30 1234567`, to E.164, `+49301234567`.
+fn e164(field: &'static str, number: &str) -> Result<String, PayloadError> {
+    let compact: String = number
+        .chars()
+        .filter(|c| !c.is_whitespace() && !"-().".contains(*c))
+        .collect();
+
+    let digits = compact
+        .strip_prefix('+')
+        .or_else(|| compact.strip_prefix("00"))
+        .ok_or_else(|| {
+            PayloadError::invalid(
+                field,
+                format!(
+                    "{:?} is not in international format, start it with + \
+                     and the country code",
+                    number
+                ),
+            )
+        })?;
+
+    let valid = (3..=15).contains(&digits.len())
+        && !digits.starts_with('0')
+        && digits.chars().all(|c| c.is_ascii_digit());
+    if !valid {
+        return Err(PayloadError::invalid(
+            field,
+            format!("{:?} is not an E.164 phone number", number),
+        ));
+    }
+    Ok(format!("+{}", digits))
+}
+
+/// A phone number to call, as the `tel:` URIs of RFC 3966.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tel {
+    /// E.164, e.g. `+15550100`.
+    pub number: String,
+}
+
+impl Tel {
+    /// Reads the `number` field.
+    pub fn from_fields(fields: &Fields) -> Result<Self, PayloadError> {
+        Ok(Self {
+            number: e164("number", fields.require("number")?)?,
+        })
+    }
+}
+
+/// The `tel:+15550100` URI.
+impl fmt::Display for Tel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "tel:{}", self.number)
+    }
+}
+
+/// A text message to send, with the number and text filled in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sms {
+    /// E.164, or a short code of up to 8 digits.
+    pub number: String,
+    pub message: Option<String>,
+    /// Whether to send it as a multimedia message.
+    pub mms: bool,
+}
+
+impl Sms {
+    /// Reads the `number` and `message` fields.
+    pub fn from_fields(fields: &Fields) -> Result<Self, PayloadError> {
+        let number = fields.require("number")?.trim();
+        let short_code = number.len() <= 8
+            && !number.starts_with('0')
+            && number.chars().all(|c| c.is_ascii_digit());
+        let number = if short_code {
+            number.to_string()
+        } else {
+            e164("number", number)?
+        };
+
+        let message = fields
+            .get("message")
+            .map(|m| m.trim().replace("\r\n", "\n"));
+        if let Some(message) = &message {
+            check_chars("message", message, |c| c == '\n' || !c.is_control())?;
+        }
+
+        Ok(Self {
+            number,
+            message,
+            mms: false,
+        })
+    }
+}
+
+/// The `SMSTO:number:message` payload, or `MMSTO:` for multimedia.
+impl fmt::Display for Sms {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scheme = if self.mms { "MMSTO" } else { "SMSTO" };
+        write!(f, "{}:{}", scheme, self.number)?;
+        if let Some(message) = &self.message {
+            write!(f, ":{}", message)?;
+        }
+        Ok(())
+    }
+}
+
+/// An email to send, as the `mailto:` URIs of RFC 6068.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Mail {
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub bcc: Vec<String>,
+    pub subject: Option<String>,
+    pub body: Option<String>,
+}
+
+impl Mail {
+    /// Reads the `to`, `cc`, `bcc`, `subject` and `body` fields. Addresses
+    /// may repeat.
+    pub fn from_fields(fields: &Fields) -> Result<Self, PayloadError> {
+        let addresses = |name: &'static str| {
+            fields
+                .all(name)
+                .map(|address| {
+                    let address = address.trim();
+                    check_email(name, address)?;
+                    Ok(address.to_string())
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        let mail = Self {
+            to: addresses("to")?,
+            cc: addresses("cc")?,
+            bcc: addresses("bcc")?,
+            subject: fields.get("subject").map(|s| s.trim().to_string()),
+            // Line breaks are CRLF in mail.
+            body: fields
+                .get("body")
+                .map(|b| b.trim().replace("\r\n", "\n").replace('\n', "\r\n")),
+        };
+
+        if mail.to.is_empty() {
+            return Err(PayloadError::Missing("to"));
+        }
+        if let Some(subject) = &mail.subject {
+            check_chars("subject", subject, |c| !c.is_control())?;
+        }
+        Ok(mail)
+    }
+}
+
+/// The `mailto:a@example.com?subject=...&body=...` URI, with all text
+/// percent encoded.
+impl fmt::Display for Mail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |addresses: &[String]| {
+            addresses
+                .iter()
+                .map(|a| percent_encode(a))
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        write!(f, "mailto:{}", list(&self.to))?;
+
+        let mut params = vec![];
+        if !self.cc.is_empty() {
+            params.push(format!("cc={}", list(&self.cc)));
+        }
+        if !self.bcc.is_empty() {
+            params.push(format!("bcc={}", list(&self.bcc)));
+        }
+        if let Some(subject) = &self.subject {
+            params.push(format!("subject={}", percent_encode(subject)));
+        }
+        if let Some(body) = &self.body {
+            params.push(format!("body={}", percent_encode(body)));
+        }
+        if !params.is_empty() {
+            write!(f, "?{}", params.join("&"))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,491 @@
+//! Builders for the structured payloads phones act on when scanning, e.g.
+//! joining a WiFi network, so that nobody has to get their syntax and
+//! escaping right by hand.
+
+mod bank;
+mod bitcoin;
+mod contact;
+mod event;
+mod geo;
+mod message;
+mod otp;
+mod qrbill;
+mod sepa;
+mod upi;
+mod wifi;
+
+pub use bank::Bic;
+pub use bank::Iban;
+pub use bitcoin::Bitcoin;
+pub use contact::Contact;
+pub use contact::ContactFormat;
+pub use event::DateTime;
+pub use event::Event;
+pub use geo::Geo;
+pub use message::Mail;
+pub use message::Sms;
+pub use message::Tel;
+pub use otp::Otp;
+pub use otp::OtpAlgorithm;
+pub use otp::OtpKind;
+pub use qrbill::Address;
+pub use qrbill::QrBill;
+pub use qrbill::Reference;
+pub use sepa::Sepa;
+pub use upi::Upi;
+pub use wifi::Wifi;
+pub use wifi::WifiAuth;
+
+use qrcode::EcLevel;
+use std::error;
+use std::fmt;
+use std::iter::FromIterator;
+use std::str::FromStr;
+
+use crate::gen::Generator;
+use crate::json;
+use crate::json::Value;
+use crate::logo::Logo;
+
+/// vCard and iCalendar lines longer than this many bytes are folded.
+const LINE_LENGTH: usize = 75;
+
+/// Builds the payload of the given kind, e.g. `wifi`, or `None` if there is
+/// no such kind.
+pub fn build_payload(
+    kind: &str,
+    fields: &Fields,
+) -> Option<Result<Payload, PayloadError>> {
+    let payload = match kind {
+        "wifi" => Wifi::from_fields(fields).map(|wifi| wifi.to_string().into()),
+        "contact" => Contact::from_fields(fields).and_then(|contact| {
+            let format = fields.parse("format")?.unwrap_or_default();
+            Ok(contact.encode(format).into())
+        }),
+        "event" => {
+            Event::from_fields(fields).map(|event| event.to_string().into())
+        }
+        "geo" => Geo::from_fields(fields).map(|geo| geo.to_string().into()),
+        "tel" => Tel::from_fields(fields).map(|tel| tel.to_string().into()),
+        "sms" => Sms::from_fields(fields).map(|sms| sms.to_string().into()),
+        "mms" => Sms::from_fields(fields)
+            .map(|sms| Sms { mms: true, ..sms }.to_string().into()),
+        "mailto" => {
+            Mail::from_fields(fields).map(|mail| mail.to_string().into())
+        }
+        "otp" => Otp::from_fields(fields).map(|otp| otp.to_string().into()),
+        "sepa" => Sepa::from_fields(fields).map(|sepa| sepa.payload()),
+        "qrbill" => QrBill::from_fields(fields).map(|bill| bill.payload()),
+        "upi" => Upi::from_fields(fields).map(|upi| upi.to_string().into()),
+        "bitcoin" => Bitcoin::from_fields(fields)
+            .map(|bitcoin| bitcoin.to_string().into()),
+        _ => return None,
+    };
+    Some(payload)
+}
+
+/// The input built from fields, with what its spec requires of the code.
+#[derive(Debug, Clone)]
+pub struct Payload {
+    pub data: String,
+    pub ec_level: Option<EcLevel>,
+    /// A mark required in the middle of the code, and its width as a share
+    /// of the code width.
+    pub logo: Option<(Logo, f64)>,
+}
+
+impl Payload {
+    /// Makes `gen` follow the spec, whatever the request asked for.
+    pub fn configure(&self, gen: &mut Generator) {
+        if let Some(ec_level) = self.ec_level {
+            gen.error_correction_level = Some(ec_level);
+        }
+        if let Some((logo, size)) = &self.logo {
+            gen.logo = Some(logo.clone());
+            gen.logo_size = Some(*size);
+        }
+    }
+}
+
+impl From<String> for Payload {
+    fn from(data: String) -> Self {
+        Self {
+            data,
+            ec_level: None,
+            logo: None,
+        }
+    }
+}
+
+/// Named values from a URL query string or an url encoded form body.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Fields(Vec<(String, String)>);
+
+impl Fields {
+    /// Parses a query string or a form body. Malformed escapes are kept as
+    /// they are.
+    pub fn from_query(query: &str) -> Self {
+        let fields = query
+            .trim_start_matches('?')
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (decode(key), decode(value))
+            })
+            .collect();
+
+        Self(fields)
+    }
+
+    /// Reads the members of a JSON object. Arrays become repeated fields.
+    pub fn from_json(json: &str) -> Result<Self, PayloadError> {
+        let members = match json::parse(json) {
+            Ok(Value::Object(members)) => members,
+            Ok(_) => {
+                return Err(PayloadError::Json("expected an object".into()))
+            }
+            Err(e) => return Err(PayloadError::Json(e.to_string())),
+        };
+
+        let mut fields = vec![];
+        for (name, value) in members {
+            let values = match value {
+                Value::Array(items) => items,
+                value => vec![value],
+            };
+
+            for value in values {
+                let value = match value {
+                    Value::Null => continue,
+                    Value::Bool(b) => b.to_string(),
+                    Value::Number(n) => n,
+                    Value::String(s) => s,
+                    Value::Array(_) | Value::Object(_) => {
+                        return Err(PayloadError::Json(format!(
+                            "{:?} holds nested values",
+                            name
+                        )))
+                    }
+                };
+                fields.push((name.clone(), value));
+            }
+        }
+
+        Ok(Self(fields))
+    }
+
+    /// Reads a JSON or an url encoded form body, telling them apart by the
+    /// content type, or by the body itself when there is none.
+    pub fn from_body(
+        content_type: Option<&str>,
+        body: &[u8],
+    ) -> Result<Self, PayloadError> {
+        let body = String::from_utf8_lossy(body);
+        let json = match content_type {
+            Some(content_type) => content_type
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .eq_ignore_ascii_case("application/json"),
+            None => body.trim_start().starts_with('{'),
+        };
+
+        if json {
+            Self::from_json(&body)
+        } else {
+            Ok(Self::from_query(body.trim()))
+        }
+    }
+
+    /// The first value of a field, unless it is empty.
+    pub fn get<'a>(&'a self, name: &str) -> Option<&'a str> {
+        self.0
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+            .find(|value| !value.is_empty())
+    }
+
+    /// All the non-empty values of a field, in order.
+    pub fn all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.0
+            .iter()
+            .filter(move |(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+            .filter(|value| !value.is_empty())
+    }
+
+    /// All the fields, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    pub fn require(&self, name: &'static str) -> Result<&str, PayloadError> {
+        self.get(name).ok_or(PayloadError::Missing(name))
+    }
+
+    /// Parses a field, if it is present.
+    pub fn parse<T>(
+        &self,
+        name: &'static str,
+    ) -> Result<Option<T>, PayloadError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.get(name)
+            .map(|value| {
+                value.trim().parse().map_err(|e: T::Err| {
+                    PayloadError::Invalid {
+                        field: name,
+                        message: e.to_string(),
+                    }
+                })
+            })
+            .transpose()
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Fields {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+/// Decodes `application/x-www-form-urlencoded` text.
+fn decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match hex {
+                    Some(b) => {
+                        decoded.push(b);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Puts a backslash before each of the `special` characters and the
+/// backslash itself.
+fn escape(s: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c == '\\' || special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Escapes a TEXT value of vCard and iCalendar.
+fn escape_text(s: &str) -> String {
+    escape(s, &[';', ','])
+        .replace("\r\n", "\\n")
+        .replace(&['\r', '\n'][..], "\\n")
+}
+
+/// Breaks a vCard or iCalendar content line into lines of at most
+/// [`LINE_LENGTH`] bytes, each continuation starting with a space. Never
+/// splits a character.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
+
+/// Percent encodes a URI query value.
+fn percent_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => encoded.push(b as char),
+            b'-' | b'.' | b'_' | b'~' | b'@' => encoded.push(b as char),
+            b => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    encoded
+}
+
+/// Reads a decimal amount with up to `decimals` digits after the point, in
+/// minor units, e.g. cents.
+fn parse_amount(s: &str, decimals: usize) -> Result<u64, String> {
+    let err =
+        || format!("{:?} is not an amount with up to {} decimals", s, decimals);
+
+    let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+    let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if whole.is_empty() && fraction.is_empty()
+        || !digits(whole)
+        || !digits(fraction)
+        || fraction.len() > decimals
+        || whole.len() > 12
+    {
+        return Err(err());
+    }
+
+    let whole: u64 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().map_err(|_| err())?
+    };
+    let fraction: u64 = format!("{:0<width$}", fraction, width = decimals)
+        .parse()
+        .unwrap_or(0);
+    Ok(whole * 10u64.pow(decimals as u32) + fraction)
+}
+
+/// Writes minor units as a decimal amount with `decimals` digits after the
+/// point.
+fn format_amount(minor: u64, decimals: usize) -> String {
+    let unit = 10u64.pow(decimals as u32);
+    format!(
+        "{}.{:0width$}",
+        minor / unit,
+        minor % unit,
+        width = decimals
+    )
+}
+
+/// Checks that a field is at most `max` characters long.
+fn check_length(
+    field: &'static str,
+    value: &str,
+    max: usize,
+) -> Result<(), PayloadError> {
+    let length = value.chars().count();
+    if length > max {
+        return Err(PayloadError::invalid(
+            field,
+            format!("{} characters long, at most {} allowed", length, max),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that a field holds only the characters its spec allows.
+fn check_chars(
+    field: &'static str,
+    value: &str,
+    allowed: impl Fn(char) -> bool,
+) -> Result<(), PayloadError> {
+    match value.chars().find(|&c| !allowed(c)) {
+        Some(c) => Err(PayloadError::invalid(
+            field,
+            format!("character {:?} is not allowed", c),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Checks that a field holds something like an email address, with
+/// nothing that would end the line or the value it is written to.
+fn check_email(field: &'static str, email: &str) -> Result<(), PayloadError> {
+    let plain = |s: &str| {
+        !s.is_empty()
+            && !s
+                .chars()
+                .any(|c| c.is_whitespace() || c.is_control() || c == ';')
+    };
+    match email.split_once('@') {
+        Some((user, host))
+            if plain(user) && plain(host) && host.contains('.') =>
+        {
+            Ok(())
+        }
+        _ => Err(PayloadError::invalid(
+            field,
+            format!("{:?} is not an email address", email),
+        )),
+    }
+}
+
+/// Checks that a field holds an http or https URL.
+fn check_url(field: &'static str, url: &str) -> Result<(), PayloadError> {
+    let lower = url.to_ascii_lowercase();
+    let rest = lower
+        .strip_prefix("https://")
+        .or_else(|| lower.strip_prefix("http://"));
+    let host = rest.and_then(|rest| rest.split(&['/', '?', '#'][..]).next());
+    match host {
+        Some(host)
+            if !host.is_empty()
+                && !url
+                    .chars()
+                    .any(|c| c.is_whitespace() || c.is_control()) =>
+        {
+            Ok(())
+        }
+        _ => Err(PayloadError::invalid(
+            field,
+            format!("{:?} is not an http or https URL", url),
+        )),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PayloadError {
+    Json(String),
+    Missing(&'static str),
+    Invalid {
+        field: &'static str,
+        message: String,
+    },
+}
+
+impl PayloadError {
+    fn invalid(field: &'static str, message: impl Into<String>) -> Self {
+        Self::Invalid {
+            field,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for PayloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(message) => f.write_str(message),
+            Self::Missing(field) => write!(f, "missing field {:?}", field),
+            Self::Invalid { field, message } => {
+                write!(f, "invalid field {:?}: {}", field, message)
+            }
+        }
+    }
+}
+
+impl error::Error for PayloadError {}
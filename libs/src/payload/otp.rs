@@ -0,0 +1,215 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::percent_encode;
+use super::Fields;
+use super::PayloadError;
+use crate::error::InvalidValue;
+
+/// The shortest secret authenticator apps take, 80 bits.
+const MIN_SECRET_LENGTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OtpKind {
+    /// Time based, RFC 6238.
+    #[default]
+    Totp,
+    /// Counter based, RFC 4226.
+    Hotp,
+}
+
+impl FromStr for OtpKind {
+    type Err = InvalidValue;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "totp" => Ok(Self::Totp),
+            "hotp" => Ok(Self::Hotp),
+            _ => Err(InvalidValue::new(s, "totp or hotp")),
+        }
+    }
+}
+
+impl fmt::Display for OtpKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Totp => "totp",
+            Self::Hotp => "hotp",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OtpAlgorithm {
+    #[default]
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl FromStr for OtpAlgorithm {
+    type Err = InvalidValue;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_uppercase().replace('-', "").as_str() {
+            "SHA1" => Ok(Self::Sha1),
+            "SHA256" => Ok(Self::Sha256),
+            "SHA512" => Ok(Self::Sha512),
+            _ => Err(InvalidValue::new(s, "SHA1, SHA256 or SHA512")),
+        }
+    }
+}
+
+impl fmt::Display for OtpAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Sha1 => "SHA1",
+            Self::Sha256 => "SHA256",
+            Self::Sha512 => "SHA512",
+        })
+    }
+}
+
+/// Checks an RFC 4648 Base32 secret, returning it without spaces and
+/// padding, in upper case.
+fn secret(s: &str) -> Result<String, String> {
+    let secret: String = s
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '=')
+        .collect::<String>()
+        .to_uppercase();
+
+    if let Some(c) =
+        secret.chars().find(|c| !matches!(c, 'A'..='Z' | '2'..='7'))
+    {
+        return Err(format!("character {:?} is not Base32", c));
+    }
+    // The lengths that leave no partial byte behind.
+    if !matches!(secret.len() % 8, 0 | 2 | 4 | 5 | 7) {
+        return Err(format!("{} characters are not whole bytes", secret.len()));
+    }
+    if secret.len() < MIN_SECRET_LENGTH {
+        return Err(format!(
+            "{} characters long, at least {} required",
+            secret.len(),
+            MIN_SECRET_LENGTH
+        ));
+    }
+    Ok(secret)
+}
+
+/// A one-time password secret to provision, as the `otpauth://` URIs
+/// authenticator apps scan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Otp {
+    pub kind: OtpKind,
+    /// The service, e.g. `Example`.
+    pub issuer: Option<String>,
+    /// The user, e.g. `jane@example.com`.
+    pub account: String,
+    /// Base32, upper case, without padding.
+    pub secret: String,
+    pub algorithm: OtpAlgorithm,
+    /// 6, 7 or 8.
+    pub digits: u8,
+    /// Seconds a time based password is valid, 30 if not given.
+    pub period: Option<u32>,
+    /// The initial counter of a counter based password.
+    pub counter: Option<u64>,
+}
+
+impl Otp {
+    /// Reads the `type`, `issuer`, `account`, `secret`, `algorithm`,
+    /// `digits`, `period` and `counter` fields.
+    pub fn from_fields(fields: &Fields) -> Result<Self, PayloadError> {
+        let otp = Self {
+            kind: fields.parse("type")?.unwrap_or_default(),
+            issuer: fields.get("issuer").map(|i| i.trim().to_string()),
+            account: fields.require("account")?.trim().to_string(),
+            secret: secret(fields.require("secret")?)
+                .map_err(|e| PayloadError::invalid("secret", e))?,
+            algorithm: fields.parse("algorithm")?.unwrap_or_default(),
+            digits: fields.parse("digits")?.unwrap_or(6),
+            period: fields.parse("period")?,
+            counter: fields.parse("counter")?,
+        };
+        otp.validate()?;
+        Ok(otp)
+    }
+
+    fn validate(&self) -> Result<(), PayloadError> {
+        // The colon separates the issuer from the account in the label.
+        if self.issuer.as_ref().is_some_and(|i| i.contains(':')) {
+            return Err(PayloadError::invalid("issuer", "contains a colon"));
+        }
+        if self.account.contains(':') {
+            return Err(PayloadError::invalid("account", "contains a colon"));
+        }
+        if !(6..=8).contains(&self.digits) {
+            return Err(PayloadError::invalid("digits", "expected 6, 7 or 8"));
+        }
+
+        match self.kind {
+            OtpKind::Totp => {
+                if self.period == Some(0) {
+                    return Err(PayloadError::invalid(
+                        "period",
+                        "expected at least 1 second",
+                    ));
+                }
+                if self.counter.is_some() {
+                    return Err(PayloadError::invalid(
+                        "counter",
+                        "only used with hotp, set type",
+                    ));
+                }
+            }
+            OtpKind::Hotp => {
+                if self.period.is_some() {
+                    return Err(PayloadError::invalid(
+                        "period",
+                        "only used with totp, set type",
+                    ));
+                }
+                if self.counter.is_none() {
+                    return Err(PayloadError::Missing("counter"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `otpauth://totp/Issuer:account?secret=...&issuer=Issuer` URI. The
+/// algorithm, digits and period are left out when they are the defaults,
+/// as some apps reject parameters they don't support.
+impl fmt::Display for Otp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "otpauth://{}/", self.kind)?;
+        if let Some(issuer) = &self.issuer {
+            write!(f, "{}:", percent_encode(issuer))?;
+        }
+        write!(
+            f,
+            "{}?secret={}",
+            percent_encode(&self.account),
+            self.secret
+        )?;
+        if let Some(issuer) = &self.issuer {
+            write!(f, "&issuer={}", percent_encode(issuer))?;
+        }
+        if self.algorithm != OtpAlgorithm::Sha1 {
+            write!(f, "&algorithm={}", self.algorithm)?;
+        }
+        if self.digits != 6 {
+            write!(f, "&digits={}", self.digits)?;
+        }
+        if let Some(counter) = self.counter {
+            write!(f, "&counter={}", counter)?;
+        }
+        match self.period {
+            Some(period) if period != 30 => write!(f, "&period={}", period),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,355 @@
+use image::codecs::png::PngEncoder;
+use image::ColorType;
+use image::EncodableLayout;
+use image::Rgba;
+use image::RgbaImage;
+use lazy_static::lazy_static;
+use qrcode::EcLevel;
+
+use super::bank::creditor_reference;
+use super::bank::Iban;
+use super::check_chars;
+use super::check_length;
+use super::format_amount;
+use super::parse_amount;
+use super::Fields;
+use super::Payload;
+use super::PayloadError;
+use crate::logo::Logo;
+
+/// The largest payload, what version 25 holds at level M.
+const MAX_CHARS: usize = 997;
+
+/// The largest amount, in cents.
+const MAX_AMOUNT: u64 = 99_999_999_999;
+
+/// The Swiss cross is 7 mm wide on a code of 46 mm.
+const CROSS_SIZE: f64 = 7.0 / 46.0;
+
+lazy_static! {
+    /// The white cross of the Swiss flag on a black square, its arms a
+    /// sixth longer than wide.
+    static ref SWISS_CROSS: Logo = {
+        const UNIT: u32 = 8;
+        let arm = |u: u32| (13..19).contains(&u);
+        let span = |u: u32| (6..26).contains(&u);
+
+        let image = RgbaImage::from_fn(32 * UNIT, 32 * UNIT, |x, y| {
+            let (u, v) = (x / UNIT, y / UNIT);
+            if arm(u) && span(v) || arm(v) && span(u) {
+                Rgba([255, 255, 255, 255])
+            } else {
+                Rgba([0, 0, 0, 255])
+            }
+        });
+
+        let mut png = vec![];
+        PngEncoder::new(&mut png)
+            .encode(image.as_bytes(), image.width(), image.height(), ColorType::Rgba8)
+            .expect("the Swiss cross encodes");
+        Logo::from_bytes(&png).expect("the Swiss cross decodes")
+    };
+}
+
+/// The characters the Swiss payment standards allow: Basic Latin, Latin-1
+/// Supplement, Latin Extended-A, `Ș ș Ț ț` and `€`.
+fn is_latin(c: char) -> bool {
+    matches!(c,
+        '\u{20}'..='\u{7e}'
+        | '\u{a0}'..='\u{17f}'
+        | '\u{218}'..='\u{21b}'
+        | '\u{20ac}')
+}
+
+/// A structured postal address.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Address {
+    pub name: String,
+    pub street: Option<String>,
+    pub building: Option<String>,
+    pub postcode: String,
+    pub town: String,
+    /// An ISO 3166 country code, e.g. `CH`.
+    pub country: String,
+}
+
+/// The address fields of the creditor.
+const CREDITOR: [&str; 6] =
+    ["name", "street", "building", "postcode", "town", "country"];
+
+/// The address fields of the debtor.
+const DEBTOR: [&str; 6] = [
+    "debtor_name",
+    "debtor_street",
+    "debtor_building",
+    "debtor_postcode",
+    "debtor_town",
+    "debtor_country",
+];
+
+impl Address {
+    /// Reads the name, street, building, postcode, town and country fields
+    /// of `keys`, `None` if there is no name.
+    fn from_fields(
+        fields: &Fields,
+        keys: [&'static str; 6],
+    ) -> Result<Option<Self>, PayloadError> {
+        let [name, street, building, postcode, town, country] = keys;
+        let text = |key: &'static str, max| {
+            fields
+                .get(key)
+                .map(|value| {
+                    let value = value.trim();
+                    check_length(key, value, max)?;
+                    check_chars(key, value, is_latin)?;
+                    Ok(value.to_string())
+                })
+                .transpose()
+        };
+        let required = |key: &'static str, max| {
+            text(key, max)?.ok_or(PayloadError::Missing(key))
+        };
+
+        let name = match text(name, 70)? {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+
+        let address = Self {
+            name,
+            street: text(street, 70)?,
+            building: text(building, 16)?,
+            postcode: required(postcode, 16)?,
+            town: required(town, 35)?,
+            country: required(country, 2)?.to_uppercase(),
+        };
+
+        let code = address.country.len() == 2
+            && address.country.chars().all(|c| c.is_ascii_uppercase());
+        if !code {
+            return Err(PayloadError::invalid(
+                country,
+                format!("{:?} is not a country code", address.country),
+            ));
+        }
+
+        Ok(Some(address))
+    }
+
+    fn lines(address: Option<&Self>) -> Vec<String> {
+        match address {
+            Some(a) => vec![
+                "S".into(),
+                a.name.clone(),
+                a.street.clone().unwrap_or_default(),
+                a.building.clone().unwrap_or_default(),
+                a.postcode.clone(),
+                a.town.clone(),
+                a.country.clone(),
+            ],
+            None => vec![String::new(); 7],
+        }
+    }
+}
+
+/// How the payment is referenced.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reference {
+    /// 27 digits, for QR-IBANs.
+    Qr(String),
+    /// An ISO 11649 creditor reference.
+    Creditor(String),
+    None,
+}
+
+impl Reference {
+    /// Checks the 27 digits and their recursive modulo 10 check digit.
+    fn qr(s: &str) -> Result<Self, String> {
+        const TABLE: [u32; 10] = [0, 9, 4, 6, 8, 2, 7, 1, 3, 5];
+
+        let digits: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+        if digits.len() != 27 || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("{:?} is not a QR reference of 27 digits", s));
+        }
+
+        let values: Vec<u32> =
+            digits.chars().filter_map(|c| c.to_digit(10)).collect();
+        let carry = values[..26]
+            .iter()
+            .fold(0, |carry, d| TABLE[((carry + d) % 10) as usize]);
+        if (10 - carry) % 10 != values[26] {
+            return Err(format!("{:?} has a wrong check digit", s));
+        }
+        Ok(Self::Qr(digits))
+    }
+}
+
+/// A Swiss QR-bill, as of the Swiss Implementation Guidelines for the
+/// QR-bill, version 2.3 with structured addresses only.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QrBill {
+    /// A Swiss or Liechtenstein IBAN or QR-IBAN.
+    pub iban: Iban,
+    pub creditor: Address,
+    /// In cents, left to the payer if not given.
+    pub amount: Option<u64>,
+    /// `CHF` or `EUR`.
+    pub currency: String,
+    pub debtor: Option<Address>,
+    pub reference: Reference,
+    pub message: Option<String>,
+    /// Structured billing information for the debtor's software.
+    pub billing: Option<String>,
+}
+
+impl QrBill {
+    /// Reads the `iban`, `amount`, `currency`, `reference`, `message` and
+    /// `billing` fields, the creditor's `name`, `street`, `building`,
+    /// `postcode`, `town` and `country`, and the same of the debtor as
+    /// `debtor_name` and so on.
+    pub fn from_fields(fields: &Fields) -> Result<Self, PayloadError> {
+        let iban: Iban =
+            fields.parse("iban")?.ok_or(PayloadError::Missing("iban"))?;
+        if !matches!(iban.country(), "CH" | "LI") {
+            return Err(PayloadError::invalid(
+                "iban",
+                "expected a Swiss or Liechtenstein IBAN",
+            ));
+        }
+
+        let amount = match fields.get("amount") {
+            Some(amount) => {
+                let cents = parse_amount(amount.trim(), 2)
+                    .map_err(|e| PayloadError::invalid("amount", e))?;
+                if cents == 0 || cents > MAX_AMOUNT {
+                    return Err(PayloadError::invalid(
+                        "amount",
+                        "expected 0.01 to 999999999.99",
+                    ));
+                }
+                Some(cents)
+            }
+            None => None,
+        };
+
+        let currency = fields
+            .get("currency")
+            .map(|c| c.trim().to_uppercase())
+            .unwrap_or_else(|| "CHF".into());
+        if currency != "CHF" && currency != "EUR" {
+            return Err(PayloadError::invalid(
+                "currency",
+                "expected CHF or EUR",
+            ));
+        }
+
+        let reference = match (fields.get("reference"), iban.is_qr_iban()) {
+            (Some(r), true) => Reference::qr(r)
+                .map_err(|e| PayloadError::invalid("reference", e))?,
+            (None, true) => return Err(PayloadError::Missing("reference")),
+            (Some(r), false) => creditor_reference(r)
+                .map(Reference::Creditor)
+                .map_err(|e| PayloadError::invalid("reference", e))?,
+            (None, false) => Reference::None,
+        };
+
+        let text = |name: &'static str| {
+            fields
+                .get(name)
+                .map(|value| {
+                    let value = value.trim();
+                    check_chars(name, value, is_latin)?;
+                    Ok(value.to_string())
+                })
+                .transpose()
+        };
+
+        let bill = Self {
+            iban,
+            creditor: Address::from_fields(fields, CREDITOR)?
+                .ok_or(PayloadError::Missing("name"))?,
+            amount,
+            currency,
+            debtor: Address::from_fields(fields, DEBTOR)?,
+            reference,
+            message: text("message")?,
+            billing: text("billing")?,
+        };
+
+        // The message and the billing information share one field on the
+        // printed bill.
+        let shared = bill
+            .message
+            .iter()
+            .chain(&bill.billing)
+            .map(|s| s.chars().count());
+        let shared: usize = shared.sum();
+        if shared > 140 {
+            return Err(PayloadError::invalid(
+                "message",
+                format!(
+                    "{} characters with the billing information, at most 140 allowed",
+                    shared
+                ),
+            ));
+        }
+
+        let chars = bill.data().chars().count();
+        if chars > MAX_CHARS {
+            return Err(PayloadError::invalid(
+                "message",
+                format!(
+                    "{} characters in total, at most {} allowed",
+                    chars, MAX_CHARS
+                ),
+            ));
+        }
+
+        Ok(bill)
+    }
+
+    fn data(&self) -> String {
+        let (kind, reference) = match &self.reference {
+            Reference::Qr(r) => ("QRR", r.as_str()),
+            Reference::Creditor(r) => ("SCOR", r.as_str()),
+            Reference::None => ("NON", ""),
+        };
+
+        let mut lines = vec![
+            "SPC".to_string(),
+            // Version and UTF-8.
+            "0200".into(),
+            "1".into(),
+            self.iban.to_string(),
+        ];
+        lines.extend(Address::lines(Some(&self.creditor)));
+        // The ultimate creditor is reserved for future use.
+        lines.extend(Address::lines(None));
+        lines.push(
+            self.amount
+                .map(|cents| format_amount(cents, 2))
+                .unwrap_or_default(),
+        );
+        lines.push(self.currency.clone());
+        lines.extend(Address::lines(self.debtor.as_ref()));
+        lines.push(kind.into());
+        lines.push(reference.into());
+        lines.push(self.message.clone().unwrap_or_default());
+        lines.push("EPD".into());
+        if let Some(billing) = &self.billing {
+            lines.push(billing.clone());
+        }
+
+        lines.join("\n")
+    }
+
+    /// The payload, to be encoded at level M with the Swiss cross in the
+    /// middle as the spec requires.
+    pub fn payload(&self) -> Payload {
+        Payload {
+            data: self.data(),
+            ec_level: Some(EcLevel::M),
+            logo: Some((SWISS_CROSS.clone(), CROSS_SIZE)),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use qrcode::EcLevel;
+
+use super::bank::creditor_reference;
+use super::bank::Bic;
+use super::bank::Iban;
+use super::check_chars;
+use super::check_length;
+use super::format_amount;
+use super::parse_amount;
+use super::Fields;
+use super::Payload;
+use super::PayloadError;
+
+/// The largest payload EPC069-12 allows, what version 13 holds at level M.
+const MAX_BYTES: usize = 331;
+
+/// The largest amount, in cents.
+const MAX_AMOUNT: u64 = 99_999_999_999;
+
+/// A SEPA credit transfer as of EPC069-12, the "GiroCode" banking apps of
+/// the euro area read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sepa {
+    /// The beneficiary.
+    pub name: String,
+    pub iban: Iban,
+    /// Optional within the EEA.
+    pub bic: Option<Bic>,
+    /// In euro cents.
+    pub amount: Option<u64>,
+    /// An ISO 20022 purpose code, e.g. `CHAR` for charity.
+    pub purpose: Option<String>,
+    /// An ISO 11649 creditor reference in its electronic format, exclusive
+    /// with `text`.
+    pub reference: Option<String>,
+    pub text: Option<String>,
+    /// A note to the payer.
+    pub info: Option<String>,
+}
+
+impl Sepa {
+    /// Reads the `name`, `iban`, `bic`, `amount`, `purpose`, `reference`,
+    /// `text` and `info` fields.
+    pub fn from_fields(fields: &Fields) -> Result<Self, PayloadError> {
+        let text = |name: &'static str, max| {
+            fields
+                .get(name)
+                .map(|value| {
+                    let value = value.trim();
+                    check_length(name, value, max)?;
+                    check_chars(name, value, |c| !c.is_control())?;
+                    Ok(value.to_string())
+                })
+                .transpose()
+        };
+
+        let amount = match fields.get("amount") {
+            Some(amount) => {
+                let cents = parse_amount(amount.trim(), 2)
+                    .map_err(|e| PayloadError::invalid("amount", e))?;
+                if cents == 0 || cents > MAX_AMOUNT {
+                    return Err(PayloadError::invalid(
+                        "amount",
+                        "expected 0.01 to 999999999.99",
+                    ));
+                }
+                Some(cents)
+            }
+            None => None,
+        };
+
+        let sepa = Self {
+            name: text("name", 70)?.ok_or(PayloadError::Missing("name"))?,
+            iban: fields.parse("iban")?.ok_or(PayloadError::Missing("iban"))?,
+            bic: fields.parse("bic")?,
+            amount,
+            purpose: text("purpose", 4)?.map(|p| p.to_uppercase()),
+            reference: fields
+                .get("reference")
+                .map(creditor_reference)
+                .transpose()
+                .map_err(|e| PayloadError::invalid("reference", e))?,
+            text: text("text", 140)?,
+            info: text("info", 70)?,
+        };
+
+        if let Some(purpose) = &sepa.purpose {
+            check_chars("purpose", purpose, |c| c.is_ascii_alphanumeric())?;
+        }
+        if sepa.reference.is_some() && sepa.text.is_some() {
+            return Err(PayloadError::invalid(
+                "text",
+                "give either a reference or a text",
+            ));
+        }
+
+        let bytes = sepa.data().len();
+        if bytes > MAX_BYTES {
+            return Err(PayloadError::invalid(
+                "text",
+                format!(
+                    "{} bytes in total, at most {} allowed",
+                    bytes, MAX_BYTES
+                ),
+            ));
+        }
+
+        Ok(sepa)
+    }
+
+    fn data(&self) -> String {
+        let optional =
+            |value: &Option<String>| value.clone().unwrap_or_default();
+
+        let lines = [
+            "BCD".to_string(),
+            // Version and UTF-8.
+            "002".into(),
+            "1".into(),
+            "SCT".into(),
+            self.bic.as_ref().map(|b| b.to_string()).unwrap_or_default(),
+            self.name.clone(),
+            self.iban.to_string(),
+            self.amount
+                .map(|cents| format!("EUR{}", format_amount(cents, 2)))
+                .unwrap_or_default(),
+            optional(&self.purpose),
+            optional(&self.reference),
+            optional(&self.text),
+            optional(&self.info),
+        ];
+
+        // Trailing empty lines may be left out.
+        lines.join("\n").trim_end_matches('\n').to_string()
+    }
+
+    /// The payload, to be encoded at level M as the spec requires.
+    pub fn payload(&self) -> Payload {
+        Payload {
+            data: self.data(),
+            ec_level: Some(EcLevel::M),
+            logo: None,
+        }
+    }
+}
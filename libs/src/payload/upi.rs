@@ -0,0 +1,133 @@
+use std::fmt;
+
+use super::check_chars;
+use super::check_length;
+use super::format_amount;
+use super::parse_amount;
+use super::percent_encode;
+use super::Fields;
+use super::PayloadError;
+
+/// The largest amount, in paise.
+const MAX_AMOUNT: u64 = 9_999_999_999;
+
+/// Checks a virtual payment address, e.g. `shop@upi`.
+fn is_vpa(s: &str) -> bool {
+    match s.split_once('@') {
+        Some((handle, provider)) => {
+            (2..=256).contains(&handle.len())
+                && handle
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "._-".contains(c))
+                && (2..=64).contains(&provider.len())
+                && provider.chars().all(|c| c.is_ascii_alphabetic())
+        }
+        None => false,
+    }
+}
+
+/// A payment request of India's Unified Payments Interface, as the
+/// `upi://pay` links of the NPCI linking specification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Upi {
+    /// The payee's virtual payment address, e.g. `shop@upi`.
+    pub vpa: String,
+    /// The payee.
+    pub name: String,
+    /// In paise, left to the payer if not given.
+    pub amount: Option<u64>,
+    pub note: Option<String>,
+    /// The merchant's transaction reference.
+    pub reference: Option<String>,
+    /// The merchant category code, 4 digits.
+    pub mcc: Option<String>,
+}
+
+impl Upi {
+    /// Reads the `vpa`, `name`, `amount`, `note`, `reference` and `mcc`
+    /// fields.
+    pub fn from_fields(fields: &Fields) -> Result<Self, PayloadError> {
+        let text = |name: &'static str, max| {
+            fields
+                .get(name)
+                .map(|value| {
+                    let value = value.trim();
+                    check_length(name, value, max)?;
+                    check_chars(name, value, |c| !c.is_control())?;
+                    Ok(value.to_string())
+                })
+                .transpose()
+        };
+
+        let vpa = fields.require("vpa")?.trim().to_string();
+        if !is_vpa(&vpa) {
+            return Err(PayloadError::invalid(
+                "vpa",
+                format!("{:?} is not a payment address, e.g. shop@upi", vpa),
+            ));
+        }
+
+        let amount = match fields.get("amount") {
+            Some(amount) => {
+                let paise = parse_amount(amount.trim(), 2)
+                    .map_err(|e| PayloadError::invalid("amount", e))?;
+                if paise == 0 || paise > MAX_AMOUNT {
+                    return Err(PayloadError::invalid(
+                        "amount",
+                        "expected 0.01 to 99999999.99",
+                    ));
+                }
+                Some(paise)
+            }
+            None => None,
+        };
+
+        let mcc = fields.get("mcc").map(|mcc| mcc.trim().to_string());
+        if let Some(mcc) = &mcc {
+            if mcc.len() != 4 || !mcc.chars().all(|c| c.is_ascii_digit()) {
+                return Err(PayloadError::invalid(
+                    "mcc",
+                    format!(
+                        "{:?} is not a merchant category code of 4 digits",
+                        mcc
+                    ),
+                ));
+            }
+        }
+
+        Ok(Self {
+            vpa,
+            name: text("name", 99)?.ok_or(PayloadError::Missing("name"))?,
+            amount,
+            note: text("note", 80)?,
+            reference: text("reference", 35)?,
+            mcc,
+        })
+    }
+}
+
+/// The `upi://pay?pa=...` link.
+impl fmt::Display for Upi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "upi://pay?pa={}&pn={}",
+            percent_encode(&self.vpa),
+            percent_encode(&self.name)
+        )?;
+        if let Some(amount) = self.amount {
+            write!(f, "&am={}", format_amount(amount, 2))?;
+        }
+        f.write_str("&cu=INR")?;
+        if let Some(note) = &self.note {
+            write!(f, "&tn={}", percent_encode(note))?;
+        }
+        if let Some(reference) = &self.reference {
+            write!(f, "&tr={}", percent_encode(reference))?;
+        }
+        if let Some(mcc) = &self.mcc {
+            write!(f, "&mc={}", mcc)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::escape;
+use super::Fields;
+use super::PayloadError;
+use crate::error::InvalidValue;
+
+/// Characters with a meaning in the `WIFI:` syntax.
+const SPECIAL: &[char] = &[';', ',', ':'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum WifiAuth {
+    /// WPA and WPA2 personal.
+    #[default]
+    Wpa,
+    /// WPA3 personal.
+    Sae,
+    Wep,
+    /// An open network.
+    Nopass,
+}
+
+impl FromStr for WifiAuth {
+    type Err = InvalidValue;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "wpa" | "wpa2" => Ok(Self::Wpa),
+            "sae" | "wpa3" => Ok(Self::Sae),
+            "wep" => Ok(Self::Wep),
+            "nopass" | "none" | "open" => Ok(Self::Nopass),
+            _ => Err(InvalidValue::new(s, "WPA, WPA2, WPA3, WEP or nopass")),
+        }
+    }
+}
+
+impl fmt::Display for WifiAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Wpa => "WPA",
+            Self::Sae => "SAE",
+            Self::Wep => "WEP",
+            Self::Nopass => "nopass",
+        })
+    }
+}
+
+/// The credentials of a WiFi network, as understood by the cameras of
+/// Android and iOS.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Wifi {
+    pub ssid: String,
+    pub auth: WifiAuth,
+    pub password: Option<String>,
+    pub hidden: bool,
+}
+
+impl Wifi {
+    /// Reads the `ssid`, `auth`, `password` and `hidden` fields.
+    pub fn from_fields(fields: &Fields) -> Result<Self, PayloadError> {
+        let wifi = Self {
+            ssid: fields.require("ssid")?.to_string(),
+            auth: fields.parse("auth")?.unwrap_or_default(),
+            password: fields.get("password").map(String::from),
+            hidden: fields.parse("hidden")?.unwrap_or(false),
+        };
+        wifi.validate()?;
+        Ok(wifi)
+    }
+
+    fn validate(&self) -> Result<(), PayloadError> {
+        if self.ssid.len() > 32 {
+            return Err(PayloadError::invalid("ssid", "longer than 32 bytes"));
+        }
+
+        let password = match (self.auth, &self.password) {
+            (WifiAuth::Nopass, None) => return Ok(()),
+            (WifiAuth::Nopass, Some(_)) => {
+                return Err(PayloadError::invalid(
+                    "password",
+                    "open networks have none, set auth",
+                ))
+            }
+            (_, None) => return Err(PayloadError::Missing("password")),
+            (_, Some(password)) => password,
+        };
+
+        let hex = password.chars().all(|c| c.is_ascii_hexdigit());
+        let valid = match self.auth {
+            WifiAuth::Wpa | WifiAuth::Sae => {
+                (8..=63).contains(&password.len())
+                    || password.len() == 64 && hex
+            }
+            WifiAuth::Wep => {
+                matches!(password.len(), 5 | 13)
+                    || matches!(password.len(), 10 | 26) && hex
+            }
+            WifiAuth::Nopass => true,
+        };
+
+        if valid {
+            Ok(())
+        } else if self.auth == WifiAuth::Wep {
+            Err(PayloadError::invalid(
+                "password",
+                "expected 5 or 13 characters, or 10 or 26 hex digits",
+            ))
+        } else {
+            Err(PayloadError::invalid(
+                "password",
+                "expected 8 to 63 characters, or 64 hex digits",
+            ))
+        }
+    }
+}
+
+/// The `WIFI:T:WPA;S:ssid;P:password;H:true;;` payload.
+impl fmt::Display for Wifi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "WIFI:T:{};S:{};", self.auth, escape(&self.ssid, SPECIAL))?;
+        if let Some(password) = &self.password {
+            write!(f, "P:{};", escape(password, SPECIAL))?;
+        }
+        if self.hidden {
+            f.write_str("H:true;")?;
+        }
+        f.write_str(";")
+    }
+}
@@ -0,0 +1,228 @@
+//! Just enough PDF to print codes: pages of images, written as they are
+//! added. The images are deflated, with their transparency as a soft mask.
+
+use image::RgbaImage;
+use std::fmt::Write;
+
+use crate::color::Color;
+use crate::frame::Text;
+use crate::render::num;
+
+/// Points per inch, the unit of PDF.
+pub const POINTS_PER_INCH: f64 = 72.0;
+
+/// The catalog and the page tree come first, so that they can be referred
+/// to before the pages are known.
+const CATALOG: usize = 1;
+const PAGES: usize = 2;
+
+/// An image added to a document, to be drawn on its pages.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Image {
+    id: usize,
+}
+
+/// A page of `width` by `height` points, drawn from the top left.
+#[derive(Debug, Clone, Default)]
+pub struct Page {
+    width: f64,
+    height: f64,
+    images: Vec<usize>,
+    content: String,
+}
+
+impl Page {
+    pub fn new(width: f64, height: f64) -> Self {
+        Self {
+            width,
+            height,
+            ..Default::default()
+        }
+    }
+
+    /// Draws the image into the box at `(x, y)`, from the top left, of
+    /// `width` by `height` points.
+    pub fn draw(
+        &mut self,
+        image: Image,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    ) {
+        if !self.images.contains(&image.id) {
+            self.images.push(image.id);
+        }
+
+        // PDF counts up from the bottom left.
+        writeln!(
+            self.content,
+            "q {} 0 0 {} {} {} cm /Im{} Do Q",
+            num(width),
+            num(height),
+            num(x),
+            num(self.height - y - height),
+            image.id,
+        )
+        .unwrap();
+    }
+
+    /// Fills the outlines of the text in `color`.
+    pub fn text(&mut self, text: &Text, color: &Color) {
+        let [r, g, b, _] = color.to_rgba().0;
+        let rgb = |c: u8| num(c as f64 / 255.0);
+        writeln!(
+            self.content,
+            "q {} {} {} rg {}Q",
+            rgb(r),
+            rgb(g),
+            rgb(b),
+            text.to_pdf(self.height),
+        )
+        .unwrap();
+    }
+}
+
+/// A PDF document.
+#[derive(Debug)]
+pub struct Pdf {
+    /// The objects, by number from 1.
+    objects: Vec<Vec<u8>>,
+    pages: Vec<usize>,
+}
+
+impl Default for Pdf {
+    fn default() -> Self {
+        Self {
+            objects: vec![vec![]; PAGES],
+            pages: vec![],
+        }
+    }
+}
+
+impl Pdf {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn add(&mut self, object: Vec<u8>) -> usize {
+        self.objects.push(object);
+        self.objects.len()
+    }
+
+    /// Adds an image, to be drawn on any number of pages.
+    pub fn image(&mut self, image: &RgbaImage) -> Image {
+        let (width, height) = image.dimensions();
+        let pixels = image.pixels();
+
+        let color: Vec<u8> = pixels
+            .clone()
+            .flat_map(|p| [p.0[0], p.0[1], p.0[2]])
+            .collect();
+        let mut dict = format!(
+            "/Type /XObject /Subtype /Image /Width {} /Height {} \
+             /ColorSpace /DeviceRGB /BitsPerComponent 8",
+            width, height
+        );
+
+        if pixels.clone().any(|p| p.0[3] < u8::MAX) {
+            let alpha: Vec<u8> = pixels.map(|p| p.0[3]).collect();
+            let mask = self.add(stream(
+                &format!(
+                    "/Type /XObject /Subtype /Image /Width {} /Height {} \
+                     /ColorSpace /DeviceGray /BitsPerComponent 8",
+                    width, height
+                ),
+                &alpha,
+            ));
+            write!(dict, " /SMask {} 0 R", mask).unwrap();
+        }
+
+        Image {
+            id: self.add(stream(&dict, &color)),
+        }
+    }
+
+    pub fn add_page(&mut self, page: Page) {
+        let contents = self.add(stream("", page.content.as_bytes()));
+
+        let images: String = page
+            .images
+            .iter()
+            .map(|id| format!(" /Im{} {} 0 R", id, id))
+            .collect();
+        let page = format!(
+            "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] \
+             /Resources << /XObject <<{} >> >> /Contents {} 0 R >>",
+            PAGES,
+            num(page.width),
+            num(page.height),
+            images,
+            contents,
+        );
+
+        let id = self.add(page.into_bytes());
+        self.pages.push(id);
+    }
+
+    /// The bytes of the objects added so far.
+    pub fn size(&self) -> usize {
+        self.objects.iter().map(Vec::len).sum()
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        self.objects[CATALOG - 1] =
+            format!("<< /Type /Catalog /Pages {} 0 R >>", PAGES).into_bytes();
+
+        let kids: Vec<String> =
+            self.pages.iter().map(|id| format!("{} 0 R", id)).collect();
+        self.objects[PAGES - 1] = format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            self.pages.len()
+        )
+        .into_bytes();
+
+        // The binary comment tells tools the file isn't text.
+        let mut data = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(self.objects.len());
+        for (i, object) in self.objects.iter().enumerate() {
+            offsets.push(data.len());
+            data.extend(format!("{} 0 obj\n", i + 1).as_bytes());
+            data.extend(object);
+            data.extend(b"\nendobj\n");
+        }
+
+        let xref = data.len();
+        let mut table = format!(
+            "xref\n0 {}\n0000000000 65535 f \n",
+            self.objects.len() + 1
+        );
+        for offset in offsets {
+            writeln!(table, "{:010} 00000 n ", offset).unwrap();
+        }
+        write!(
+            table,
+            "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.objects.len() + 1,
+            CATALOG,
+            xref
+        )
+        .unwrap();
+
+        data.extend(table.as_bytes());
+        data
+    }
+}
+
+/// A stream object of the dictionary entries and the data, deflated.
+fn stream(dict: &str, data: &[u8]) -> Vec<u8> {
+    let data = miniz_oxide::deflate::compress_to_vec_zlib(data, 6);
+
+    let dict = format!("{} /Filter /FlateDecode /Length {}", dict, data.len());
+    let mut object =
+        format!("<< {} >>\nstream\n", dict.trim_start()).into_bytes();
+    object.extend(data);
+    object.extend(b"\nendstream");
+    object
+}
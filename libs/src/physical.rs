@@ -0,0 +1,78 @@
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Used when a physical size is requested without a DPI.
+pub const DEFAULT_DPI: u32 = 300;
+
+const MM_PER_INCH: f64 = 25.4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LengthUnit {
+    Millimeters,
+    Centimeters,
+    Inches,
+}
+
+/// The printed width (and height) of the code, e.g. `30mm` or `1.5in`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhysicalSize {
+    pub length: f64,
+    pub unit: LengthUnit,
+}
+
+impl PhysicalSize {
+    pub fn to_inches(&self) -> f64 {
+        match self.unit {
+            LengthUnit::Millimeters => self.length / MM_PER_INCH,
+            LengthUnit::Centimeters => self.length * 10.0 / MM_PER_INCH,
+            LengthUnit::Inches => self.length,
+        }
+    }
+
+    pub fn to_pixels(&self, dpi: u32) -> u32 {
+        (self.to_inches() * dpi as f64).round() as u32
+    }
+}
+
+impl FromStr for PhysicalSize {
+    type Err = PhysicalSizeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || PhysicalSizeError(s.to_string());
+        let s = s.trim().to_lowercase();
+
+        let (length, unit) = if let Some(l) = s.strip_suffix("mm") {
+            (l, LengthUnit::Millimeters)
+        } else if let Some(l) = s.strip_suffix("cm") {
+            (l, LengthUnit::Centimeters)
+        } else if let Some(l) = s.strip_suffix("in") {
+            (l, LengthUnit::Inches)
+        } else {
+            return Err(err());
+        };
+
+        let length: f64 = length.trim().parse().map_err(|_| err())?;
+        if !length.is_finite() || length <= 0.0 {
+            return Err(err());
+        }
+
+        Ok(Self { length, unit })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhysicalSizeError(String);
+
+impl fmt::Display for PhysicalSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid physical size {:?}: expected a positive number followed \
+             by mm, cm or in",
+            self.0
+        )
+    }
+}
+
+impl error::Error for PhysicalSizeError {}
@@ -0,0 +1,216 @@
+//! Named styles bundling parameters that would otherwise be sent as headers
+//! with every request. The servers apply a style as if its parameters were
+//! headers, so that the request headers can override them one by one.
+
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+/// The styles compiled into the servers.
+pub const DEFAULT_PRESETS: &str = include_str!("../presets.conf");
+
+/// The parameters a style can set, as their header names without the
+/// `x-qr-` prefix.
+const PARAMETERS: &[&str] = &[
+    "width",
+    "height",
+    "min-width",
+    "min-height",
+    "max-width",
+    "max-height",
+    "dark-color",
+    "light-color",
+    "dark-gradient",
+    "version-type",
+    "version-number",
+    "ec-level",
+    "quiet-zone",
+    "shape",
+    "eye-shape",
+    "eye-dot-shape",
+    "eye-color",
+    "logo",
+    "logo-size",
+    "frame",
+    "frame-text",
+    "frame-position",
+    "frame-color",
+    "halftone",
+    "invert",
+    "rotate",
+    "mirror",
+    "module-size",
+    "margin",
+    "physical-size",
+    "dpi",
+    "srgb",
+    "alt-text",
+    "accessible",
+    "scalable",
+];
+
+/// A named set of parameters.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Preset {
+    /// Header names (`x-qr-dark-color`) and their values.
+    pub headers: Vec<(String, String)>,
+}
+
+impl Preset {
+    pub fn get(&self, header: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(header))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The headers and values of the parameters a request doesn't set
+    /// itself, `is_set` telling which headers it sends.
+    pub fn unset(&self, is_set: impl Fn(&str) -> bool) -> Vec<(&str, &str)> {
+        self.headers
+            .iter()
+            .filter(|(name, _)| !is_set(name))
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Presets(BTreeMap<String, Preset>);
+
+impl Presets {
+    /// Looks up a style by its case insensitive name.
+    pub fn find(&self, name: &str) -> Result<&Preset, PresetError> {
+        self.0.get(&name.trim().to_lowercase()).ok_or_else(|| {
+            PresetError::UnknownStyle {
+                name: name.trim().to_string(),
+                available: self.names().map(String::from).collect(),
+            }
+        })
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+}
+
+impl Default for Presets {
+    fn default() -> Self {
+        DEFAULT_PRESETS.parse().expect("default presets are valid")
+    }
+}
+
+impl FromStr for Presets {
+    type Err = PresetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut presets = BTreeMap::new();
+        let mut current: Option<(String, Preset)> = None;
+
+        for (i, line) in s.lines().enumerate() {
+            let line_no = i + 1;
+            let syntax = |message: &str| PresetError::Syntax {
+                line: line_no,
+                message: message.to_string(),
+            };
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';')
+            {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[') {
+                let name = name
+                    .strip_suffix(']')
+                    .ok_or_else(|| syntax("missing ']'"))?
+                    .trim()
+                    .to_lowercase();
+
+                if name.is_empty() {
+                    return Err(syntax("empty style name"));
+                }
+                if let Some((name, preset)) = current.take() {
+                    presets.insert(name, preset);
+                }
+                if presets.contains_key(&name) {
+                    return Err(PresetError::Duplicate(name));
+                }
+
+                current = Some((name, Preset::default()));
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| syntax("expected 'parameter = value'"))?;
+
+            let key = key.trim().to_lowercase();
+            let key = key.strip_prefix("x-qr-").unwrap_or(&key);
+            if !PARAMETERS.contains(&key) {
+                return Err(PresetError::UnknownParameter {
+                    line: line_no,
+                    name: key.to_string(),
+                });
+            }
+
+            let (_, preset) = current
+                .as_mut()
+                .ok_or_else(|| syntax("parameter outside of a [style]"))?;
+
+            let header = format!("x-qr-{}", key);
+            preset.headers.retain(|(name, _)| *name != header);
+            preset.headers.push((header, value.trim().to_string()));
+        }
+
+        if let Some((name, preset)) = current {
+            presets.insert(name, preset);
+        }
+
+        Ok(Self(presets))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PresetError {
+    Syntax {
+        line: usize,
+        message: String,
+    },
+    UnknownParameter {
+        line: usize,
+        name: String,
+    },
+    Duplicate(String),
+    UnknownStyle {
+        name: String,
+        available: Vec<String>,
+    },
+}
+
+impl fmt::Display for PresetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax { line, message } => {
+                write!(f, "invalid styles, line {}: {}", line, message)
+            }
+            Self::UnknownParameter { line, name } => write!(
+                f,
+                "invalid styles, line {}: unknown parameter {:?}",
+                line, name
+            ),
+            Self::Duplicate(name) => {
+                write!(f, "invalid styles: {:?} is defined twice", name)
+            }
+            Self::UnknownStyle { name, available } => write!(
+                f,
+                "unknown style {:?}, expected one of: {}",
+                name,
+                available.join(", ")
+            ),
+        }
+    }
+}
+
+impl error::Error for PresetError {}
@@ -0,0 +1,618 @@
+//! Renders the module matrix with styled modules, as SVG paths or as an
+//! anti-aliased raster image. Plain square modules are left to the `qrcode`
+//! renderers.
+
+use image::Rgba;
+use image::RgbaImage;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::color::Color;
+use crate::error::InvalidValue;
+use crate::gradient::Gradient;
+use crate::logo::Logo;
+use crate::matrix::Area;
+use crate::matrix::Kind;
+use crate::matrix::Matrix;
+
+/// Corner radius of `Shape::Rounded`, in modules.
+const ROUNDED_RADIUS: f64 = 0.3;
+
+/// Radius of `Shape::Circle`, in modules. Slightly smaller than half a module
+/// so that neighbouring dots don't touch.
+const CIRCLE_RADIUS: f64 = 0.45;
+
+/// Radius of the concave fillets `Shape::Liquid` draws between modules.
+const FILLET_RADIUS: f64 = 0.25;
+
+/// Corner radius of the outer edge of a rounded eye ring, in modules. The
+/// inner edge is one module less so the ring keeps its thickness.
+const EYE_ROUNDED_RADIUS: f64 = 2.0;
+
+/// Corner radius of a rounded eye dot, in modules.
+const EYE_DOT_ROUNDED_RADIUS: f64 = 1.0;
+
+/// Samples per pixel along each axis when rasterizing.
+pub(crate) const SUPERSAMPLING: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Shape {
+    #[default]
+    Square,
+    Rounded,
+    Circle,
+    Diamond,
+    Liquid,
+}
+
+impl FromStr for Shape {
+    type Err = InvalidValue;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "square" => Ok(Self::Square),
+            "rounded" => Ok(Self::Rounded),
+            "circle" | "dot" => Ok(Self::Circle),
+            "diamond" => Ok(Self::Diamond),
+            "liquid" => Ok(Self::Liquid),
+            _ => Err(InvalidValue::new(
+                s,
+                "square, rounded, circle, dot, diamond or liquid",
+            )),
+        }
+    }
+}
+
+/// The shape of the outer ring or the inner dot of a finder pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EyeShape {
+    #[default]
+    Square,
+    Rounded,
+    Circle,
+    Diamond,
+}
+
+impl FromStr for EyeShape {
+    type Err = InvalidValue;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "square" => Ok(Self::Square),
+            "rounded" => Ok(Self::Rounded),
+            "circle" => Ok(Self::Circle),
+            "diamond" => Ok(Self::Diamond),
+            _ => {
+                Err(InvalidValue::new(s, "square, rounded, circle or diamond"))
+            }
+        }
+    }
+}
+
+/// How the modules of a code are drawn.
+#[derive(Debug, Clone)]
+pub struct Style {
+    pub shape: Shape,
+    pub eye_shape: Option<EyeShape>,
+    pub eye_dot_shape: Option<EyeShape>,
+    pub dark: Color,
+    pub light: Color,
+    pub eye_color: Option<Color>,
+    /// Fills the dark modules instead of `dark`.
+    pub gradient: Option<Gradient>,
+    /// Drawn over the area excavated from the matrix.
+    pub logo: Option<Logo>,
+}
+
+impl Style {
+    /// Whether plain dark and light squares are enough, so the `qrcode`
+    /// renderers can be used as is.
+    pub fn is_plain(&self) -> bool {
+        self.shape == Shape::Square
+            && self.eye_shape.is_none()
+            && self.eye_dot_shape.is_none()
+            && self.eye_color.is_none()
+            && self.gradient.is_none()
+            && self.logo.is_none()
+    }
+
+    /// Whether the finder patterns are drawn as whole shapes rather than
+    /// module by module.
+    fn has_eyes(&self) -> bool {
+        self.eye_shape.is_some() || self.eye_dot_shape.is_some()
+    }
+}
+
+/// Which color a primitive is filled with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Paint {
+    Dark,
+    Eye,
+}
+
+/// A filled shape, in module coordinates.
+#[derive(Debug, Clone, PartialEq)]
+enum Primitive {
+    /// A square with the corners (top left, top right, bottom right, bottom
+    /// left) rounded by the given radii.
+    Rect {
+        x: f64,
+        y: f64,
+        size: f64,
+        radii: [f64; 4],
+    },
+    Circle {
+        cx: f64,
+        cy: f64,
+        r: f64,
+    },
+    Diamond {
+        x: f64,
+        y: f64,
+        size: f64,
+    },
+    /// Fills the concave gap at a corner of a light module whose two
+    /// neighbours at that corner are dark.
+    Fillet {
+        x: f64,
+        y: f64,
+        corner: usize,
+        r: f64,
+    },
+    /// The first shape with the second one cut out of it.
+    Ring(Box<Primitive>, Box<Primitive>),
+}
+
+/// Offsets of the corners of a unit square, in the same order as the radii
+/// of `Primitive::Rect`.
+const CORNERS: [(f64, f64); 4] =
+    [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+
+impl Primitive {
+    fn module(x: usize, y: usize, radii: [f64; 4]) -> Self {
+        Self::Rect {
+            x: x as f64,
+            y: y as f64,
+            size: 1.0,
+            radii,
+        }
+    }
+
+    fn eye(shape: EyeShape, x: usize, y: usize, size: usize, r: f64) -> Self {
+        let (x, y, size) = (x as f64, y as f64, size as f64);
+        match shape {
+            EyeShape::Square => Self::Rect {
+                x,
+                y,
+                size,
+                radii: [0.0; 4],
+            },
+            EyeShape::Rounded => Self::Rect {
+                x,
+                y,
+                size,
+                radii: [r; 4],
+            },
+            EyeShape::Circle => Self::Circle {
+                cx: x + size / 2.0,
+                cy: y + size / 2.0,
+                r: size / 2.0,
+            },
+            EyeShape::Diamond => Self::Diamond { x, y, size },
+        }
+    }
+
+    /// The modules the shape touches, as `(x0, y0, x1, y1)` with the end
+    /// exclusive.
+    fn bounds(&self) -> (usize, usize, usize, usize) {
+        let (x, y, size) = match *self {
+            Self::Rect { x, y, size, .. } => (x, y, size),
+            Self::Circle { cx, cy, r } => (cx - r, cy - r, 2.0 * r),
+            Self::Diamond { x, y, size } => (x, y, size),
+            Self::Fillet { x, y, .. } => (x, y, 1.0),
+            Self::Ring(ref outer, _) => return outer.bounds(),
+        };
+
+        (
+            x.floor().max(0.0) as usize,
+            y.floor().max(0.0) as usize,
+            (x + size).ceil() as usize,
+            (y + size).ceil() as usize,
+        )
+    }
+
+    fn contains(&self, u: f64, v: f64) -> bool {
+        match *self {
+            Self::Rect { x, y, size, radii } => {
+                if u < x || v < y || u > x + size || v > y + size {
+                    return false;
+                }
+                CORNERS.iter().zip(radii.iter()).all(|(&(cx, cy), &r)| {
+                    // Inside the corner box, the point must also be inside
+                    // the circle that rounds it.
+                    let (cx, cy) = (x + cx * size, y + cy * size);
+                    let (dx, dy) = ((u - cx).abs(), (v - cy).abs());
+                    if dx >= r || dy >= r {
+                        return true;
+                    }
+                    let (dx, dy) = (r - dx, r - dy);
+                    dx * dx + dy * dy <= r * r
+                })
+            }
+
+            Self::Circle { cx, cy, r } => {
+                let (dx, dy) = (u - cx, v - cy);
+                dx * dx + dy * dy <= r * r
+            }
+
+            Self::Diamond { x, y, size } => {
+                let half = size / 2.0;
+                (u - x - half).abs() + (v - y - half).abs() <= half
+            }
+
+            Self::Fillet { x, y, corner, r } => {
+                let (cx, cy) = CORNERS[corner];
+                let (dx, dy) = ((u - x - cx).abs(), (v - y - cy).abs());
+                if dx > r || dy > r {
+                    return false;
+                }
+                let (dx, dy) = (r - dx, r - dy);
+                dx * dx + dy * dy >= r * r
+            }
+
+            Self::Ring(ref outer, ref inner) => {
+                outer.contains(u, v) && !inner.contains(u, v)
+            }
+        }
+    }
+
+    fn write_svg(&self, d: &mut String) {
+        match *self {
+            Self::Rect { x, y, size, radii } => {
+                let [tl, tr, br, bl] = radii;
+                let (x1, y1) = (x + size, y + size);
+                write!(d, "M{} {}", num(x + tl), num(y)).unwrap();
+                write!(d, "H{}", num(x1 - tr)).unwrap();
+                arc(d, tr, x1, y + tr, true);
+                write!(d, "V{}", num(y1 - br)).unwrap();
+                arc(d, br, x1 - br, y1, true);
+                write!(d, "H{}", num(x + bl)).unwrap();
+                arc(d, bl, x, y1 - bl, true);
+                write!(d, "V{}", num(y + tl)).unwrap();
+                arc(d, tl, x + tl, y, true);
+                d.push('Z');
+            }
+
+            Self::Circle { cx, cy, r } => {
+                write!(
+                    d,
+                    "M{} {}a{r} {r} 0 1 0 {d} 0a{r} {r} 0 1 0 -{d} 0Z",
+                    num(cx - r),
+                    num(cy),
+                    r = num(r),
+                    d = num(2.0 * r),
+                )
+                .unwrap();
+            }
+
+            Self::Diamond { x, y, size } => {
+                let half = size / 2.0;
+                write!(
+                    d,
+                    "M{} {}L{} {}L{} {}L{} {}Z",
+                    num(x + half),
+                    num(y),
+                    num(x + size),
+                    num(y + half),
+                    num(x + half),
+                    num(y + size),
+                    num(x),
+                    num(y + half),
+                )
+                .unwrap();
+            }
+
+            Self::Fillet { x, y, corner, r } => {
+                let (cx, cy) = CORNERS[corner];
+                let (sx, sy) = (1.0 - 2.0 * cx, 1.0 - 2.0 * cy);
+                let (px, py) = (x + cx, y + cy);
+                write!(d, "M{} {}", num(px), num(py)).unwrap();
+                write!(d, "H{}", num(px + sx * r)).unwrap();
+                // Concave, so the sweep direction alternates with the corner.
+                arc(d, r, px, py + sy * r, corner % 2 == 1);
+                d.push('Z');
+            }
+
+            // With the even-odd fill rule, the inner shape becomes a hole.
+            Self::Ring(ref outer, ref inner) => {
+                outer.write_svg(d);
+                inner.write_svg(d);
+            }
+        }
+    }
+}
+
+fn arc(d: &mut String, r: f64, x: f64, y: f64, clockwise: bool) {
+    if r > 0.0 {
+        write!(
+            d,
+            "A{r} {r} 0 0 {} {} {}",
+            clockwise as u8,
+            num(x),
+            num(y),
+            r = num(r)
+        )
+        .unwrap();
+    }
+}
+
+/// Formats a coordinate without float noise.
+pub(crate) fn num(v: f64) -> String {
+    let s = format!("{:.3}", v);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" {
+        "0".into()
+    } else {
+        s.into()
+    }
+}
+
+/// The module matrix laid out as primitives in a given style.
+pub struct Canvas {
+    width: usize,
+    primitives: Vec<(Primitive, Paint)>,
+    logo_area: Option<Area>,
+}
+
+impl Canvas {
+    pub fn new(matrix: &Matrix, style: &Style) -> Self {
+        let eye_paint = if style.eye_color.is_some() {
+            Paint::Eye
+        } else {
+            Paint::Dark
+        };
+
+        let mut primitives = vec![];
+        for y in 0..matrix.width() {
+            for x in 0..matrix.width() {
+                let paint = match matrix.kind(x, y) {
+                    Kind::Finder if style.has_eyes() => continue,
+                    Kind::Finder => eye_paint,
+                    _ => Paint::Dark,
+                };
+
+                for p in module_primitives(matrix, style.shape, x, y) {
+                    primitives.push((p, paint));
+                }
+            }
+        }
+
+        if style.has_eyes() {
+            let ring = style.eye_shape.unwrap_or_default();
+            let dot = style.eye_dot_shape.unwrap_or_default();
+            let r = EYE_ROUNDED_RADIUS;
+
+            for &(x, y) in matrix.finders() {
+                let outer = Primitive::eye(ring, x, y, 7, r);
+                let inner = Primitive::eye(ring, x + 1, y + 1, 5, r - 1.0);
+                let ring = Primitive::Ring(Box::new(outer), Box::new(inner));
+                primitives.push((ring, eye_paint));
+
+                let dot = Primitive::eye(
+                    dot,
+                    x + 2,
+                    y + 2,
+                    3,
+                    EYE_DOT_ROUNDED_RADIUS,
+                );
+                primitives.push((dot, eye_paint));
+            }
+        }
+
+        Self {
+            width: matrix.width(),
+            primitives,
+            logo_area: matrix.excavated(),
+        }
+    }
+
+    fn path(&self, paint: Paint) -> String {
+        let mut d = String::new();
+        for (p, _) in self.primitives.iter().filter(|(_, pt)| *pt == paint) {
+            p.write_svg(&mut d);
+        }
+        d
+    }
+
+    /// Renders an SVG using module units for the view box, scaled to
+    /// `module_size` pixels per module.
+    pub fn svg(&self, style: &Style, module_size: u32) -> String {
+        let n = self.width as f64;
+        let (defs, fg) = match &style.gradient {
+            Some(gradient) => (
+                format!("<defs>{}</defs>", gradient.to_svg("dark", n)),
+                "url(#dark)".to_string(),
+            ),
+            None => (String::new(), style.dark.to_svg()),
+        };
+
+        let mut svg = format!(
+            concat!(
+                r#"<?xml version="1.0" standalone="yes"?>"#,
+                r#"<svg xmlns="http://www.w3.org/2000/svg""#,
+                r#" version="1.1" width="{w}" height="{w}""#,
+                r#" viewBox="0 0 {n} {n}"{xlink}>{defs}"#,
+                r#"<rect x="0" y="0" width="{n}" height="{n}" fill="{bg}"/>"#,
+                r#"<path fill="{fg}" fill-rule="evenodd" d="{d}"/>"#,
+            ),
+            w = self.width as u32 * module_size,
+            n = self.width,
+            xlink = if style.logo.is_some() {
+                r#" xmlns:xlink="http://www.w3.org/1999/xlink""#
+            } else {
+                ""
+            },
+            defs = defs,
+            bg = style.light.to_svg(),
+            fg = fg,
+            d = self.path(Paint::Dark),
+        );
+
+        if let Some(eye) = &style.eye_color {
+            write!(
+                svg,
+                r#"<path fill="{}" fill-rule="evenodd" d="{}"/>"#,
+                eye.to_svg(),
+                self.path(Paint::Eye),
+            )
+            .unwrap();
+        }
+
+        if let (Some(logo), Some(area)) = (&style.logo, self.logo_area) {
+            svg.push_str(&logo.to_svg(area));
+        }
+
+        svg.push_str("</svg>");
+        svg
+    }
+
+    /// Renders an anti-aliased image with `module_size` pixels per module.
+    pub fn raster(&self, style: &Style, module_size: u32) -> RgbaImage {
+        let width = self.width;
+
+        // The primitives touching each module, so that a pixel only tests
+        // the few that can cover it.
+        let mut cells = vec![vec![]; width * width];
+        for (i, (p, _)) in self.primitives.iter().enumerate() {
+            let (x0, y0, x1, y1) = p.bounds();
+            for y in y0..y1.min(width) {
+                for x in x0..x1.min(width) {
+                    cells[y * width + x].push(i);
+                }
+            }
+        }
+
+        let dark = style.dark.to_rgba();
+        let light = style.light.to_rgba();
+        let eye = style.eye_color.as_ref().map_or(dark, Color::to_rgba);
+
+        let size = width as u32 * module_size;
+        let samples = (SUPERSAMPLING * SUPERSAMPLING) as f64;
+
+        let mut image = RgbaImage::from_fn(size, size, |px, py| {
+            let (mx, my) = (px / module_size, py / module_size);
+            let cell = &cells[my as usize * width + mx as usize];
+            if cell.is_empty() {
+                return light;
+            }
+
+            let (mut dark_hits, mut eye_hits) = (0, 0);
+            for j in 0..SUPERSAMPLING {
+                for i in 0..SUPERSAMPLING {
+                    let sub = |p: u32, s: u32| {
+                        (p as f64 + (s as f64 + 0.5) / SUPERSAMPLING as f64)
+                            / module_size as f64
+                    };
+                    let (u, v) = (sub(px, i), sub(py, j));
+                    let hit = cell
+                        .iter()
+                        .map(|&i| &self.primitives[i])
+                        .find(|(p, _)| p.contains(u, v));
+
+                    match hit {
+                        Some((_, Paint::Dark)) => dark_hits += 1,
+                        Some((_, Paint::Eye)) => eye_hits += 1,
+                        None => {}
+                    }
+                }
+            }
+
+            let dark = match &style.gradient {
+                Some(gradient) => {
+                    let (x, y) = (px as f64 + 0.5, py as f64 + 0.5);
+                    gradient.color_at(x, y, size as f64)
+                }
+                None => dark,
+            };
+
+            let pixel = blend(&dark, &light, dark_hits as f64 / samples);
+            blend(&eye, &pixel, eye_hits as f64 / samples)
+        });
+
+        if let (Some(logo), Some(area)) = (&style.logo, self.logo_area) {
+            logo.composite(&mut image, area, module_size);
+        }
+
+        image
+    }
+}
+
+fn module_primitives(
+    matrix: &Matrix,
+    shape: Shape,
+    x: usize,
+    y: usize,
+) -> Vec<Primitive> {
+    let (ix, iy) = (x as isize, y as isize);
+    let dark = matrix.is_dark(ix, iy);
+
+    match shape {
+        Shape::Square if dark => vec![Primitive::module(x, y, [0.0; 4])],
+        Shape::Rounded if dark => {
+            vec![Primitive::module(x, y, [ROUNDED_RADIUS; 4])]
+        }
+        Shape::Circle if dark => vec![Primitive::Circle {
+            cx: x as f64 + 0.5,
+            cy: y as f64 + 0.5,
+            r: CIRCLE_RADIUS,
+        }],
+        Shape::Diamond if dark => vec![Primitive::Diamond {
+            x: x as f64,
+            y: y as f64,
+            size: 1.0,
+        }],
+
+        Shape::Liquid => {
+            let up = matrix.is_dark(ix, iy - 1);
+            let right = matrix.is_dark(ix + 1, iy);
+            let down = matrix.is_dark(ix, iy + 1);
+            let left = matrix.is_dark(ix - 1, iy);
+            let corners =
+                [(up, left), (up, right), (down, right), (down, left)];
+
+            if dark {
+                let mut radii = [0.0; 4];
+                for (r, (a, b)) in radii.iter_mut().zip(corners.iter()) {
+                    if !a && !b {
+                        *r = 0.5;
+                    }
+                }
+                vec![Primitive::module(x, y, radii)]
+            } else {
+                corners
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (a, b))| *a && *b)
+                    .map(|(corner, _)| Primitive::Fillet {
+                        x: x as f64,
+                        y: y as f64,
+                        corner,
+                        r: FILLET_RADIUS,
+                    })
+                    .collect()
+            }
+        }
+
+        _ => vec![],
+    }
+}
+
+/// Mixes `fg` into `bg` by `coverage`.
+fn blend(fg: &Rgba<u8>, bg: &Rgba<u8>, coverage: f64) -> Rgba<u8> {
+    let mut out = *bg;
+    for (o, (f, b)) in out.0.iter_mut().zip(fg.0.iter().zip(bg.0.iter())) {
+        *o =
+            (*f as f64 * coverage + *b as f64 * (1.0 - coverage)).round() as u8;
+    }
+    out
+}
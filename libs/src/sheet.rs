@@ -0,0 +1,538 @@
+//! Label sheets: the codes of a batch laid out in a grid of labels, to
+//! print on standard sticker sheets, with an optional caption under each
+//! code. Everything is in points, from the top left.
+
+use qrcode::QrCode;
+use std::error;
+use std::fmt;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::frame::Text;
+use crate::gen::Format;
+use crate::gen::GenerateError;
+use crate::gen::Generator;
+use crate::pdf::Page;
+use crate::pdf::Pdf;
+use crate::pdf::POINTS_PER_INCH;
+use crate::physical::LengthUnit;
+use crate::physical::PhysicalSize;
+use crate::render::num;
+use crate::svg;
+use crate::zip::Zip;
+
+const IN: f64 = POINTS_PER_INCH;
+const MM: f64 = POINTS_PER_INCH / 25.4;
+
+/// The most labels a custom grid may have across or down.
+const MAX_GRID: usize = 50;
+
+/// The caption under a code takes this share of the label height.
+const CAPTION_SHARE: f64 = 0.2;
+
+/// The caption text keeps this share of the label width clear on each
+/// side.
+const CAPTION_INSET: f64 = 0.05;
+
+/// The page sizes of custom grids, by name.
+const PAGES: &[(&str, f64, f64)] = &[
+    ("a3", 297.0 * MM, 420.0 * MM),
+    ("a4", 210.0 * MM, 297.0 * MM),
+    ("a5", 148.0 * MM, 210.0 * MM),
+    ("letter", 8.5 * IN, 11.0 * IN),
+    ("legal", 8.5 * IN, 14.0 * IN),
+];
+
+/// Avery sheets, by product code, as printed on the box.
+const AVERY: &[(&str, Sheet)] = &[
+    (
+        "5160",
+        letter(3, 10, (2.625, 1.0), (0.5, 0.1875), (0.0, 0.125)),
+    ),
+    (
+        "5161",
+        letter(2, 10, (4.0, 1.0), (0.5, 0.156_25), (0.0, 0.1875)),
+    ),
+    (
+        "5163",
+        letter(2, 5, (4.0, 2.0), (0.5, 0.156_25), (0.0, 0.1875)),
+    ),
+    ("5167", letter(4, 20, (1.75, 0.5), (0.5, 0.3), (0.0, 0.3))),
+    ("l7160", a4(3, 7, (63.5, 38.1), (15.15, 7.21), (0.0, 2.54))),
+    ("l7162", a4(2, 8, (99.1, 33.9), (12.9, 4.65), (0.0, 2.5))),
+    ("l7163", a4(2, 7, (99.1, 38.1), (15.15, 4.65), (0.0, 2.5))),
+    ("l7651", a4(5, 13, (38.1, 21.2), (10.7, 4.67), (0.0, 2.54))),
+];
+
+/// A Letter sheet, lengths in inches: the label, the margin and the
+/// gutter, each vertical then horizontal but the label.
+const fn letter(
+    columns: usize,
+    rows: usize,
+    label: (f64, f64),
+    margin: (f64, f64),
+    gutter: (f64, f64),
+) -> Sheet {
+    sheet((8.5, 11.0), columns, rows, label, margin, gutter, IN)
+}
+
+/// An A4 sheet, lengths in millimeters, as [`letter`].
+const fn a4(
+    columns: usize,
+    rows: usize,
+    label: (f64, f64),
+    margin: (f64, f64),
+    gutter: (f64, f64),
+) -> Sheet {
+    sheet((210.0, 297.0), columns, rows, label, margin, gutter, MM)
+}
+
+const fn sheet(
+    page: (f64, f64),
+    columns: usize,
+    rows: usize,
+    label: (f64, f64),
+    margin: (f64, f64),
+    gutter: (f64, f64),
+    unit: f64,
+) -> Sheet {
+    Sheet {
+        width: page.0 * unit,
+        height: page.1 * unit,
+        columns,
+        rows,
+        label_width: label.0 * unit,
+        label_height: label.1 * unit,
+        top: margin.0 * unit,
+        left: margin.1 * unit,
+        row_gap: gutter.0 * unit,
+        column_gap: gutter.1 * unit,
+        custom: false,
+    }
+}
+
+/// Reads a length in mm, cm, in or pt, as points.
+fn length(s: &str) -> Option<f64> {
+    let s = s.trim().to_lowercase();
+    let (number, unit) =
+        [("mm", MM), ("cm", 10.0 * MM), ("in", IN), ("pt", 1.0)]
+            .iter()
+            .find_map(|&(suffix, unit)| Some((s.strip_suffix(suffix)?, unit)))
+            .or_else(|| Some((s.as_str(), 1.0)).filter(|_| s == "0"))?;
+
+    let number: f64 = number.trim().parse().ok()?;
+    if number.is_finite() && number >= 0.0 {
+        Some(number * unit)
+    } else {
+        None
+    }
+}
+
+/// The space along both axes, as one length or two, vertical first, e.g.
+/// `10mm` or `15mm 10mm`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Spacing {
+    pub vertical: f64,
+    pub horizontal: f64,
+}
+
+impl FromStr for Spacing {
+    type Err = SheetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || SheetError::Spacing(s.to_string());
+        let lengths: Vec<f64> = s
+            .split_whitespace()
+            .map(|l| length(l).ok_or_else(err))
+            .collect::<Result<_, _>>()?;
+
+        match lengths[..] {
+            [both] => Ok(Self {
+                vertical: both,
+                horizontal: both,
+            }),
+            [vertical, horizontal] => Ok(Self {
+                vertical,
+                horizontal,
+            }),
+            _ => Err(err()),
+        }
+    }
+}
+
+/// A page of labels in a grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sheet {
+    pub width: f64,
+    pub height: f64,
+    pub columns: usize,
+    pub rows: usize,
+    pub label_width: f64,
+    pub label_height: f64,
+    /// From the edges of the page to the first label.
+    pub top: f64,
+    pub left: f64,
+    /// Between the labels.
+    pub row_gap: f64,
+    pub column_gap: f64,
+    /// Whether the labels fill the page within the margins, rather than
+    /// matching a product.
+    custom: bool,
+}
+
+impl FromStr for Sheet {
+    type Err = SheetError;
+
+    /// An Avery product code, e.g. `avery-5160` or `L7160`, or a custom
+    /// grid of columns by rows on a page, e.g. `a4:3x8`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || SheetError::Sheet(s.to_string());
+        let name = s.trim().to_lowercase();
+
+        let code = name.strip_prefix("avery").unwrap_or(&name);
+        let code = code.trim_start_matches(['-', ' ']);
+        if let Some((_, sheet)) = AVERY.iter().find(|(c, _)| *c == code) {
+            return Ok(*sheet);
+        }
+
+        let (page, grid) = name.split_once(':').ok_or_else(err)?;
+        let &(_, width, height) = PAGES
+            .iter()
+            .find(|(p, _, _)| *p == page.trim())
+            .ok_or_else(err)?;
+
+        let (columns, rows) = grid.split_once('x').ok_or_else(err)?;
+        let count = |n: &str| {
+            n.trim()
+                .parse()
+                .ok()
+                .filter(|n| (1..=MAX_GRID).contains(n))
+                .ok_or_else(err)
+        };
+
+        Self::grid(
+            (width, height),
+            count(columns)?,
+            count(rows)?,
+            Spacing::default(),
+            Spacing::default(),
+        )
+    }
+}
+
+impl Sheet {
+    /// Labels filling a page of `width` by `height` within the margin,
+    /// with gutters between them.
+    pub fn grid(
+        (width, height): (f64, f64),
+        columns: usize,
+        rows: usize,
+        margin: Spacing,
+        gutter: Spacing,
+    ) -> Result<Self, SheetError> {
+        if columns == 0 || rows == 0 {
+            return Err(SheetError::NoRoom);
+        }
+        let label = |page: f64, margin: f64, gutter: f64, count: usize| {
+            (page - 2.0 * margin - gutter * (count - 1) as f64) / count as f64
+        };
+        let sheet = Self {
+            width,
+            height,
+            columns,
+            rows,
+            label_width: label(
+                width,
+                margin.horizontal,
+                gutter.horizontal,
+                columns,
+            ),
+            label_height: label(height, margin.vertical, gutter.vertical, rows),
+            top: margin.vertical,
+            left: margin.horizontal,
+            row_gap: gutter.vertical,
+            column_gap: gutter.horizontal,
+            custom: true,
+        };
+
+        if sheet.label_width <= 0.0 || sheet.label_height <= 0.0 {
+            return Err(SheetError::NoRoom);
+        }
+        Ok(sheet)
+    }
+
+    /// Changes the margin or the gutters of a custom grid, keeping the
+    /// others. Those of a product are fixed.
+    pub fn space(
+        self,
+        margin: Option<Spacing>,
+        gutter: Option<Spacing>,
+    ) -> Result<Self, SheetError> {
+        if margin.is_none() && gutter.is_none() {
+            return Ok(self);
+        }
+        if !self.custom {
+            return Err(SheetError::Fixed);
+        }
+
+        Self::grid(
+            (self.width, self.height),
+            self.columns,
+            self.rows,
+            margin.unwrap_or(Spacing {
+                vertical: self.top,
+                horizontal: self.left,
+            }),
+            gutter.unwrap_or(Spacing {
+                vertical: self.row_gap,
+                horizontal: self.column_gap,
+            }),
+        )
+    }
+
+    pub fn labels(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    /// The top left corner of the nth label of a page, row by row.
+    pub fn label(&self, n: usize) -> (f64, f64) {
+        let (column, row) = (n % self.columns, n / self.columns);
+        (
+            self.left + column as f64 * (self.label_width + self.column_gap),
+            self.top + row as f64 * (self.label_height + self.row_gap),
+        )
+    }
+}
+
+enum Pages {
+    Pdf(Pdf, Page),
+    /// The content of each page.
+    Svg(Vec<String>),
+}
+
+/// Codes laid out on as many sheets as they take, as the pages of a PDF
+/// document or SVG documents.
+pub struct Sheets {
+    sheet: Sheet,
+    /// Whether the labels leave room for a caption under the code.
+    captions: bool,
+    pages: Pages,
+    count: usize,
+}
+
+impl Sheets {
+    pub fn new(sheet: Sheet, pdf: bool, captions: bool) -> Self {
+        let pages = if pdf {
+            Pages::Pdf(Pdf::new(), Page::new(sheet.width, sheet.height))
+        } else {
+            Pages::Svg(vec![String::new()])
+        };
+        Self {
+            sheet,
+            captions,
+            pages,
+            count: 0,
+        }
+    }
+
+    /// Lays out `code`, the code of `input`, on the next label, centered,
+    /// with the caption under it, in the dark color. `gen` must be in the
+    /// format of `format()`, and the logo, if any, must have passed
+    /// `check_logo`.
+    pub fn add(
+        &mut self,
+        gen: &Generator,
+        input: &[u8],
+        code: &QrCode,
+        caption: Option<&str>,
+    ) -> Result<(), GenerateError> {
+        let sheet = &self.sheet;
+        let n = self.count % sheet.labels();
+        if n == 0 && self.count > 0 {
+            match &mut self.pages {
+                Pages::Pdf(pdf, page) => pdf.add_page(std::mem::replace(
+                    page,
+                    Page::new(sheet.width, sheet.height),
+                )),
+                Pages::Svg(pages) => pages.push(String::new()),
+            }
+        }
+        self.count += 1;
+
+        let (x, y) = sheet.label(n);
+        let (width, height) = (sheet.label_width, sheet.label_height);
+        let band = if self.captions {
+            height * CAPTION_SHARE
+        } else {
+            0.0
+        };
+        let size = width.min(height - band);
+        let top = y + (height - size - band) / 2.0;
+
+        // Framed codes aren't square, so they are fitted into the square.
+        let place = |w: f64, h: f64| {
+            let scale = if w > 0.0 && h > 0.0 {
+                size / w.max(h)
+            } else {
+                0.0
+            };
+            let (w, h) = if scale > 0.0 {
+                (w * scale, h * scale)
+            } else {
+                (size, size)
+            };
+            (x + (width - w) / 2.0, top + (size - h) / 2.0, w, h)
+        };
+
+        let text = caption.map(|caption| {
+            let inset = width * CAPTION_INSET;
+            Text::new(caption, x + inset, top + size, width - 2.0 * inset, band)
+        });
+        let color = gen.dark_color();
+
+        match &mut self.pages {
+            Pages::Pdf(pdf, page) => {
+                // Rendered at the printed size, so that it is sharp.
+                let gen = Generator {
+                    physical_size: Some(PhysicalSize {
+                        length: size / POINTS_PER_INCH,
+                        unit: LengthUnit::Inches,
+                    }),
+                    ..gen.clone()
+                };
+                let image = gen.image(code)?;
+                let (x, y, w, h) =
+                    place(image.width() as f64, image.height() as f64);
+                let image = pdf.image(&image);
+                page.draw(image, x, y, w, h);
+
+                if let Some(text) = &text {
+                    page.text(text, &color);
+                }
+            }
+            Pages::Svg(pages) => {
+                let code = String::from_utf8_lossy(&gen.render(input, code)?)
+                    .trim_end()
+                    .to_string();
+                let (w, h) = svg::size(&code);
+                let (x, y, w, h) = place(w as f64, h as f64);
+
+                let content = pages.last_mut().expect("there is a page");
+                content.push_str(&svg::nest(&code, x, y, w, h));
+                if let Some(text) = &text {
+                    write!(
+                        content,
+                        r#"<path fill="{}" d="{}"/>"#,
+                        color.to_svg(),
+                        text.to_svg()
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The format the codes are drawn in.
+    pub fn format(&self) -> Format {
+        match self.pages {
+            Pages::Pdf(..) => Format::Png,
+            Pages::Svg(_) => Format::Svg,
+        }
+    }
+
+    /// The PDF document, the SVG document of a single page, or a ZIP
+    /// archive of those of many, as `page-1.svg` and so on.
+    pub fn finish(self) -> Vec<u8> {
+        let sheet = self.sheet;
+        match self.pages {
+            Pages::Pdf(mut pdf, page) => {
+                pdf.add_page(page);
+                pdf.finish()
+            }
+            Pages::Svg(pages) => {
+                let svg = |content: &str| {
+                    format!(
+                        concat!(
+                            r#"<?xml version="1.0" standalone="yes"?>"#,
+                            r#"<svg xmlns="http://www.w3.org/2000/svg""#,
+                            r#" version="1.1" width="{}mm" height="{}mm""#,
+                            r#" viewBox="0 0 {} {}">{}</svg>"#,
+                            "\n",
+                        ),
+                        num(sheet.width / MM),
+                        num(sheet.height / MM),
+                        num(sheet.width),
+                        num(sheet.height),
+                        content,
+                    )
+                };
+
+                if let [page] = &pages[..] {
+                    return svg(page).into_bytes();
+                }
+                let width = pages.len().to_string().len();
+                let mut zip = Zip::new();
+                for (i, page) in pages.iter().enumerate() {
+                    let name =
+                        format!("page-{:0width$}.svg", i + 1, width = width);
+                    zip.add(&name, svg(page).as_bytes());
+                }
+                zip.finish()
+            }
+        }
+    }
+
+    /// The bytes of the pages laid out so far.
+    pub fn size(&self) -> usize {
+        match &self.pages {
+            Pages::Pdf(pdf, _) => pdf.size(),
+            Pages::Svg(pages) => pages.iter().map(String::len).sum(),
+        }
+    }
+
+    pub fn is_pdf(&self) -> bool {
+        matches!(&self.pages, Pages::Pdf(..))
+    }
+
+    /// Whether `finish` makes a ZIP archive of SVG pages.
+    pub fn is_zip(&self) -> bool {
+        matches!(&self.pages, Pages::Svg(pages) if pages.len() > 1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SheetError {
+    Sheet(String),
+    Spacing(String),
+    NoRoom,
+    Fixed,
+}
+
+impl fmt::Display for SheetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sheet(s) => write!(
+                f,
+                "invalid sheet {:?}: expected an Avery code, e.g. 5160 or \
+                 L7160, or a page and a grid, e.g. a4:3x8",
+                s
+            ),
+            Self::Spacing(s) => write!(
+                f,
+                "invalid spacing {:?}: expected one or two lengths in mm, \
+                 cm, in or pt, e.g. 10mm or 15mm 10mm",
+                s
+            ),
+            Self::NoRoom => {
+                f.write_str("the margins and gutters leave no room for labels")
+            }
+            Self::Fixed => f.write_str(
+                "the margins and gutters of a product sheet are fixed, use \
+                 a custom grid",
+            ),
+        }
+    }
+}
+
+impl error::Error for SheetError {}
@@ -0,0 +1,225 @@
+//! Compact SVG output for plain codes, and helpers for wrapping SVG
+//! documents into each other.
+//!
+//! Instead of a square per dark module, every connected dark area becomes a
+//! single polygon, traced along its edges. Holes are traced the other way
+//! around, so that they stay empty with the default nonzero fill rule.
+
+use qrcode::Color as Module;
+use std::fmt::Write;
+
+use crate::color::Color;
+use crate::render::num;
+
+/// Renders the modules, quiet zone included, with `module_width` by
+/// `module_height` pixels per module.
+pub fn render(
+    modules: &[Module],
+    width: usize,
+    (module_width, module_height): (u32, u32),
+    dark: &Color,
+    light: &Color,
+) -> String {
+    let mut svg = format!(
+        concat!(
+            r#"<?xml version="1.0" standalone="yes"?>"#,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1""#,
+            r#" width="{}" height="{}""#,
+        ),
+        width as u32 * module_width,
+        width as u32 * module_height,
+    );
+
+    // Stretched like the other renderers when asked for a size of another
+    // aspect ratio.
+    if module_width != module_height {
+        svg.push_str(r#" preserveAspectRatio="none""#);
+    }
+
+    write!(
+        svg,
+        concat!(
+            r#" viewBox="0 0 {w} {w}" shape-rendering="crispEdges">"#,
+            r#"<rect x="0" y="0" width="{w}" height="{w}" fill="{bg}"/>"#,
+            r#"<path fill="{fg}" d="{d}"/></svg>"#,
+        ),
+        w = width,
+        bg = light.to_svg(),
+        fg = dark.to_svg(),
+        d = contours(modules, width),
+    )
+    .unwrap();
+
+    svg
+}
+
+const RIGHT: u8 = 0;
+const DOWN: u8 = 1;
+const LEFT: u8 = 2;
+const UP: u8 = 3;
+
+/// The outlines of the dark areas as path data, clockwise for the outer
+/// edges and counterclockwise for the holes.
+fn contours(modules: &[Module], width: usize) -> String {
+    let dark = |x: isize, y: isize| {
+        x >= 0
+            && y >= 0
+            && (x as usize) < width
+            && (y as usize) < width
+            && modules[y as usize * width + x as usize] == Module::Dark
+    };
+
+    // The edges between dark and light modules, by the corner they start
+    // at, as a bit per direction. Walking them keeps the dark side on the
+    // right.
+    let corners = width + 1;
+    let mut edges = vec![0u8; corners * corners];
+    for y in 0..width {
+        for x in 0..width {
+            let (ix, iy) = (x as isize, y as isize);
+            if !dark(ix, iy) {
+                continue;
+            }
+            if !dark(ix, iy - 1) {
+                edges[y * corners + x] |= 1 << RIGHT;
+            }
+            if !dark(ix + 1, iy) {
+                edges[y * corners + x + 1] |= 1 << DOWN;
+            }
+            if !dark(ix, iy + 1) {
+                edges[(y + 1) * corners + x + 1] |= 1 << LEFT;
+            }
+            if !dark(ix - 1, iy) {
+                edges[(y + 1) * corners + x] |= 1 << UP;
+            }
+        }
+    }
+
+    let mut d = String::new();
+    for start in 0..edges.len() {
+        // The first corner left in row order is a corner of its outline.
+        // Two areas touching diagonally share it, so there may be two.
+        while edges[start] != 0 {
+            let (sx, sy) = (start % corners, start / corners);
+            write!(d, "M{} {}", sx, sy).unwrap();
+
+            let mut at = start;
+            let mut heading = RIGHT;
+            let mut steps: Vec<(u8, usize)> = vec![];
+            loop {
+                heading = turn(edges[at], heading);
+                edges[at] &= !(1 << heading);
+
+                match steps.last_mut() {
+                    Some((last, len)) if *last == heading => *len += 1,
+                    _ => steps.push((heading, 1)),
+                }
+
+                at = match heading {
+                    RIGHT => at + 1,
+                    DOWN => at + corners,
+                    LEFT => at - 1,
+                    _ => at - corners,
+                };
+                if at == start {
+                    break;
+                }
+            }
+
+            // The closing side is implied.
+            steps.pop();
+            for (heading, len) in steps {
+                match heading {
+                    RIGHT => write!(d, "h{}", len),
+                    DOWN => write!(d, "v{}", len),
+                    LEFT => write!(d, "h-{}", len),
+                    _ => write!(d, "v-{}", len),
+                }
+                .unwrap();
+            }
+            d.push('z');
+        }
+    }
+
+    d
+}
+
+/// Picks the way on from a corner with the `edges` leaving it, preferring a
+/// right turn so that areas touching only at a corner are traced apart.
+fn turn(edges: u8, heading: u8) -> u8 {
+    [
+        (heading + 1) % 4,
+        heading,
+        (heading + 3) % 4,
+        (heading + 2) % 4,
+    ]
+    .iter()
+    .copied()
+    .find(|h| edges & (1 << h) != 0)
+    .unwrap_or(heading)
+}
+
+/// Drops the fixed size of an SVG document so that it scales to fit.
+pub fn scalable(svg: &str) -> String {
+    let (attributes, content) = split(svg);
+    let start = svg.find("<svg").unwrap_or(0);
+
+    let mut result = svg[..start].to_string();
+    result.push_str("<svg");
+    for (name, value) in attributes {
+        if name != "width" && name != "height" {
+            write!(result, r#" {}="{}""#, name, value).unwrap();
+        }
+    }
+    result.push_str(content);
+    result
+}
+
+/// Turns a standalone SVG document into an element placed at `(x, y)` and
+/// scaled to `width` by `height` units.
+pub fn nest(svg: &str, x: f64, y: f64, width: f64, height: f64) -> String {
+    let (attributes, content) = split(svg);
+
+    let mut attrs = String::new();
+    for (name, value) in attributes {
+        match name {
+            "width" => write!(attrs, r#" width="{}""#, num(width)),
+            "height" => write!(attrs, r#" height="{}""#, num(height)),
+            _ => write!(attrs, r#" {}="{}""#, name, value),
+        }
+        .unwrap();
+    }
+
+    format!(r#"<svg x="{}" y="{}"{}{}"#, num(x), num(y), attrs, content)
+}
+
+/// The width and height of an SVG document.
+pub fn size(svg: &str) -> (usize, usize) {
+    let (attributes, _) = split(svg);
+    let get = |key| {
+        attributes
+            .iter()
+            .find(|(name, _)| *name == key)
+            .and_then(|(_, value)| value.parse().ok())
+            .unwrap_or_default()
+    };
+    (get("width"), get("height"))
+}
+
+/// The attributes of the root element, and everything after them.
+fn split(svg: &str) -> (Vec<(&str, &str)>, &str) {
+    let start = svg.find("<svg").map_or(0, |i| i + "<svg".len());
+    let svg = &svg[start..];
+    let end = svg.find('>').unwrap_or(0);
+    let (mut tag, content) = svg.split_at(end);
+
+    let mut attributes = vec![];
+    while let Some(eq) = tag.find("=\"") {
+        let name = tag[..eq].trim();
+        let value_end = tag[eq + 2..].find('"').map_or(0, |i| i + eq + 2);
+        attributes.push((name, &tag[eq + 2..value_end]));
+        tag = &tag[value_end + 1..];
+    }
+
+    (attributes, content)
+}
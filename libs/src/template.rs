@@ -0,0 +1,235 @@
+//! Inputs with placeholders, e.g. `https://ex.com/item/{{id}}`, filled in
+//! from fields, and serial numbers, e.g. `{{seq:0001..0500}}`, that make one
+//! input of each number.
+
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::payload::Fields;
+
+/// The most codes a template may expand to.
+pub const MAX_CODES: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Variable(String),
+    Sequence(Sequence),
+}
+
+/// The numbers from `start` to `end`, zero padded to the width of `start`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sequence {
+    start: u64,
+    end: u64,
+    width: usize,
+}
+
+impl Sequence {
+    /// Counted in `u64`, as `usize` may not hold 18 digit ranges.
+    fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    fn nth(&self, n: usize) -> String {
+        format!("{:0width$}", self.start + n as u64, width = self.width)
+    }
+}
+
+impl FromStr for Sequence {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || TemplateError::Sequence(s.to_string());
+
+        let (start, end) = s.split_once("..").ok_or_else(err)?;
+        let (start, end) = (start.trim(), end.trim());
+        let digits = |s: &str| {
+            !s.is_empty()
+                && s.len() <= 18
+                && s.chars().all(|c| c.is_ascii_digit())
+        };
+        if !digits(start) || !digits(end) {
+            return Err(err());
+        }
+
+        let sequence = Self {
+            start: start.parse().map_err(|_| err())?,
+            end: end.parse().map_err(|_| err())?,
+            width: start.len(),
+        };
+        if sequence.start > sequence.end {
+            return Err(err());
+        }
+        Ok(sequence)
+    }
+}
+
+/// An input with `{{name}}` placeholders for fields and `{{seq:1..10}}`
+/// placeholders for serial numbers. Several sequences count up together,
+/// so they must be of the same length.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl FromStr for Template {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = vec![];
+        let mut rest = s;
+
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+            let end = rest[start..]
+                .find("}}")
+                .ok_or(TemplateError::Unclosed(s.len() - rest.len() + start))?;
+            let name = rest[start + 2..start + end].trim();
+
+            let part = match name.strip_prefix("seq:") {
+                Some(range) => Part::Sequence(range.parse()?),
+                None => {
+                    let valid = !name.is_empty()
+                        && name
+                            .chars()
+                            .all(|c| c.is_alphanumeric() || "_-.".contains(c));
+                    if !valid {
+                        return Err(TemplateError::Placeholder(
+                            name.to_string(),
+                        ));
+                    }
+                    Part::Variable(name.to_string())
+                }
+            };
+            parts.push(part);
+            rest = &rest[start + end + 2..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+
+        let template = Self { parts };
+        template.count()?;
+        Ok(template)
+    }
+}
+
+impl Template {
+    /// Reads the `template` field and fills it in with the other fields.
+    pub fn expand(
+        fields: &Fields,
+    ) -> Result<Vec<(String, String)>, TemplateError> {
+        let template: Self = fields
+            .get("template")
+            .ok_or(TemplateError::NoTemplate)?
+            .parse()?;
+        template.render(fields)
+    }
+
+    fn sequences(&self) -> impl Iterator<Item = &Sequence> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Sequence(sequence) => Some(sequence),
+            _ => None,
+        })
+    }
+
+    /// How many inputs the template makes.
+    pub fn count(&self) -> Result<usize, TemplateError> {
+        let mut lengths = self.sequences().map(Sequence::len);
+        let count = lengths.next().unwrap_or(1);
+        if lengths.any(|length| length != count) {
+            return Err(TemplateError::Lengths);
+        }
+        if count > MAX_CODES as u64 {
+            return Err(TemplateError::TooMany(count));
+        }
+        Ok(count as usize)
+    }
+
+    /// Fills in the placeholders, making one input of each serial number.
+    /// The inputs are named after the number of the first sequence, or are
+    /// numbered if there is none.
+    pub fn render(
+        &self,
+        fields: &Fields,
+    ) -> Result<Vec<(String, String)>, TemplateError> {
+        let count = self.count()?;
+        for part in &self.parts {
+            if let Part::Variable(name) = part {
+                fields
+                    .get(name)
+                    .ok_or_else(|| TemplateError::Missing(name.clone()))?;
+            }
+        }
+
+        let width = count.to_string().len();
+        let mut inputs = Vec::with_capacity(count);
+        for n in 0..count {
+            let mut input = String::new();
+            for part in &self.parts {
+                match part {
+                    Part::Text(text) => input.push_str(text),
+                    Part::Variable(name) => {
+                        input.push_str(fields.get(name).unwrap_or_default())
+                    }
+                    Part::Sequence(sequence) => {
+                        input.push_str(&sequence.nth(n))
+                    }
+                }
+            }
+
+            let name = match self.sequences().next() {
+                Some(sequence) => sequence.nth(n),
+                None => format!("{:0width$}", n + 1, width = width),
+            };
+            inputs.push((name, input));
+        }
+        Ok(inputs)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateError {
+    NoTemplate,
+    /// A `{{` without `}}`, at this offset.
+    Unclosed(usize),
+    Placeholder(String),
+    Sequence(String),
+    Lengths,
+    TooMany(u64),
+    Missing(String),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoTemplate => f.write_str("missing field \"template\""),
+            Self::Unclosed(offset) => {
+                write!(f, "placeholder at {} is not closed with }}}}", offset)
+            }
+            Self::Placeholder(name) => {
+                write!(f, "invalid placeholder {{{{{}}}}}", name)
+            }
+            Self::Sequence(range) => write!(
+                f,
+                "invalid sequence {:?}: expected e.g. seq:0001..0500",
+                range
+            ),
+            Self::Lengths => f.write_str(
+                "sequences count up together, give them the same length",
+            ),
+            Self::TooMany(count) => write!(
+                f,
+                "{} codes, at most {} allowed per request",
+                count, MAX_CODES
+            ),
+            Self::Missing(name) => write!(f, "missing variable {:?}", name),
+        }
+    }
+}
+
+impl error::Error for TemplateError {}
@@ -0,0 +1,134 @@
+//! Rotating and mirroring the finished output, frame and all. Inverting the
+//! colors happens earlier, by swapping the dark and the light color.
+
+use image::imageops;
+use image::RgbaImage;
+use std::str::FromStr;
+
+use crate::error::InvalidValue;
+use crate::svg;
+
+/// A clockwise rotation.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Rotation {
+    #[default]
+    None,
+    Quarter,
+    Half,
+    ThreeQuarters,
+}
+
+impl FromStr for Rotation {
+    type Err = InvalidValue;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || InvalidValue::new(s, "0, 90, 180 or 270");
+        let degrees: i32 = s
+            .trim()
+            .trim_end_matches("deg")
+            .trim()
+            .parse()
+            .map_err(|_| err())?;
+
+        match degrees.rem_euclid(360) {
+            0 => Ok(Self::None),
+            90 => Ok(Self::Quarter),
+            180 => Ok(Self::Half),
+            270 => Ok(Self::ThreeQuarters),
+            _ => Err(err()),
+        }
+    }
+}
+
+/// A horizontal mirror followed by a rotation.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Transform {
+    pub rotation: Rotation,
+    pub mirror: bool,
+}
+
+impl Transform {
+    pub fn is_identity(&self) -> bool {
+        self.rotation == Rotation::None && !self.mirror
+    }
+
+    /// Where the item at `(x, y)` of a `width` by `height` grid ends up.
+    fn apply(
+        &self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> (usize, usize) {
+        let x = if self.mirror { width - 1 - x } else { x };
+
+        match self.rotation {
+            Rotation::None => (x, y),
+            Rotation::Quarter => (height - 1 - y, x),
+            Rotation::Half => (width - 1 - x, height - 1 - y),
+            Rotation::ThreeQuarters => (y, width - 1 - x),
+        }
+    }
+
+    /// Transforms a square grid of modules, for the text renderers.
+    pub fn modules<T: Copy>(&self, modules: &[T], width: usize) -> Vec<T> {
+        let mut result = modules.to_vec();
+        for (i, &module) in modules.iter().enumerate() {
+            let (x, y) = self.apply(i % width, i / width, width, width);
+            result[y * width + x] = module;
+        }
+        result
+    }
+
+    pub fn raster(&self, image: RgbaImage) -> RgbaImage {
+        let image = if self.mirror {
+            imageops::flip_horizontal(&image)
+        } else {
+            image
+        };
+
+        match self.rotation {
+            Rotation::None => image,
+            Rotation::Quarter => imageops::rotate90(&image),
+            Rotation::Half => imageops::rotate180(&image),
+            Rotation::ThreeQuarters => imageops::rotate270(&image),
+        }
+    }
+
+    /// Wraps a standalone SVG document into a transformed one.
+    pub fn svg(&self, svg: &str) -> String {
+        let (w, h) = svg::size(svg);
+
+        // Transforms apply right to left.
+        let mut transform = match self.rotation {
+            Rotation::None => String::new(),
+            Rotation::Quarter => format!("translate({} 0) rotate(90) ", h),
+            Rotation::Half => format!("translate({} {}) rotate(180) ", w, h),
+            Rotation::ThreeQuarters => {
+                format!("translate(0 {}) rotate(270) ", w)
+            }
+        };
+        if self.mirror {
+            transform.push_str(&format!("translate({} 0) scale(-1 1)", w));
+        }
+
+        let (width, height) = match self.rotation {
+            Rotation::Quarter | Rotation::ThreeQuarters => (h, w),
+            _ => (w, h),
+        };
+
+        format!(
+            concat!(
+                r#"<?xml version="1.0" standalone="yes"?>"#,
+                r#"<svg xmlns="http://www.w3.org/2000/svg""#,
+                r#" version="1.1" width="{width}" height="{height}""#,
+                r#" viewBox="0 0 {width} {height}">"#,
+                r#"<g transform="{transform}">{svg}</g></svg>"#,
+            ),
+            width = width,
+            height = height,
+            transform = transform.trim_end(),
+            svg = svg::nest(svg, 0.0, 0.0, w as f64, h as f64),
+        )
+    }
+}
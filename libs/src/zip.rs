@@ -0,0 +1,133 @@
+//! Many codes in one response, as a ZIP archive. The images are stored as
+//! they are, PNG and JPEG being compressed already.
+
+use std::error;
+use std::fmt;
+
+use crate::gen::Generator;
+
+/// DOS date of 1980-01-01, the earliest a ZIP archive can hold.
+const DATE: u16 = 1 << 5 | 1;
+
+/// Names are UTF-8.
+const FLAGS: u16 = 1 << 11;
+
+/// Version 2.0, the first with folders.
+const VERSION: u16 = 20;
+
+/// A ZIP archive of stored files, written as they are added.
+#[derive(Debug, Default)]
+pub struct Zip {
+    data: Vec<u8>,
+    directory: Vec<u8>,
+    count: u16,
+}
+
+impl Zip {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: &str, contents: &[u8]) {
+        let offset = self.data.len() as u32;
+        let crc = crc32fast::hash(contents);
+
+        // The fields local and central headers share.
+        let mut common = vec![];
+        common.extend(VERSION.to_le_bytes());
+        common.extend(FLAGS.to_le_bytes());
+        common.extend(0u16.to_le_bytes()); // stored
+        common.extend(0u16.to_le_bytes()); // midnight
+        common.extend(DATE.to_le_bytes());
+        common.extend(crc.to_le_bytes());
+        common.extend((contents.len() as u32).to_le_bytes());
+        common.extend((contents.len() as u32).to_le_bytes());
+        common.extend((name.len() as u16).to_le_bytes());
+        common.extend(0u16.to_le_bytes()); // no extra field
+
+        self.data.extend(0x0403_4b50u32.to_le_bytes());
+        self.data.extend(&common);
+        self.data.extend(name.as_bytes());
+        self.data.extend(contents);
+
+        self.directory.extend(0x0201_4b50u32.to_le_bytes());
+        self.directory.extend(VERSION.to_le_bytes());
+        self.directory.extend(&common);
+        self.directory.extend(0u16.to_le_bytes()); // no comment
+        self.directory.extend(0u16.to_le_bytes()); // first disk
+        self.directory.extend(0u16.to_le_bytes()); // internal attributes
+        self.directory.extend(0u32.to_le_bytes()); // external attributes
+        self.directory.extend(offset.to_le_bytes());
+        self.directory.extend(name.as_bytes());
+
+        self.count += 1;
+    }
+
+    /// The bytes written so far, less the end of the directory.
+    pub fn size(&self) -> usize {
+        self.data.len() + self.directory.len()
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let offset = self.data.len() as u32;
+        let size = self.directory.len() as u32;
+        self.data.append(&mut self.directory);
+
+        self.data.extend(0x0605_4b50u32.to_le_bytes());
+        self.data.extend(0u16.to_le_bytes()); // this disk
+        self.data.extend(0u16.to_le_bytes()); // disk of the directory
+        self.data.extend(self.count.to_le_bytes());
+        self.data.extend(self.count.to_le_bytes());
+        self.data.extend(size.to_le_bytes());
+        self.data.extend(offset.to_le_bytes());
+        self.data.extend(0u16.to_le_bytes()); // no comment
+        self.data
+    }
+}
+
+/// Generates the code of each input into a ZIP archive, as a file of its
+/// name with the extension of the format.
+pub fn archive<I, N, D>(
+    gen: &Generator,
+    inputs: I,
+) -> Result<Vec<u8>, ArchiveError>
+where
+    I: IntoIterator<Item = (N, D)>,
+    N: AsRef<str>,
+    D: AsRef<[u8]>,
+{
+    let mut zip = Zip::new();
+    for (name, input) in inputs {
+        let (name, input) = (name.as_ref(), input.as_ref());
+        let err = |message: String| ArchiveError {
+            name: name.to_string(),
+            message,
+        };
+
+        let code = gen.code(input).map_err(|e| err(e.to_string()))?;
+        gen.check_logo(&code).map_err(|e| err(e.to_string()))?;
+        gen.check_halftone()
+            .map_err(|e| err(format!("halftone: {}", e)))?;
+        gen.check_size(&code)
+            .map_err(|e| err(format!("size: {}", e)))?;
+        let image = gen.generate(input).map_err(|e| err(e.to_string()))?;
+
+        zip.add(&format!("{}.{}", name, gen.format.extension()), &image);
+    }
+    Ok(zip.finish())
+}
+
+/// A code of the archive that couldn't be generated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveError {
+    pub name: String,
+    pub message: String,
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.message)
+    }
+}
+
+impl error::Error for ArchiveError {}
@@ -0,0 +1,213 @@
+use std::convert::TryInto;
+
+use libs::parse_rows;
+use libs::Batch;
+use libs::BatchOutput;
+use libs::Format;
+use libs::Generator;
+use libs::Row;
+use libs::Zip;
+
+fn csv(body: &str) -> Vec<Row> {
+    parse_rows(Some("text/csv"), body.as_bytes()).unwrap()
+}
+
+fn u16_at(data: &[u8], at: usize) -> usize {
+    u16::from_le_bytes(data[at..at + 2].try_into().unwrap()) as usize
+}
+
+fn u32_at(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+}
+
+/// CRC-32 as ZIP uses it, bit by bit.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                crc >> 1 ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// The files of an archive, read from its central directory as unzip
+/// does, checking the local headers and the CRCs on the way.
+fn unzip(zip: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let end = zip.len() - 22;
+    assert_eq!(u32_at(zip, end), 0x0605_4b50, "end of central directory");
+    let count = u16_at(zip, end + 10);
+    assert_eq!(u16_at(zip, end + 8), count);
+    let size = u32_at(zip, end + 12) as usize;
+    let mut at = u32_at(zip, end + 16) as usize;
+    assert_eq!(at + size, end);
+
+    let mut files = vec![];
+    for _ in 0..count {
+        assert_eq!(u32_at(zip, at), 0x0201_4b50, "central header");
+        let crc = u32_at(zip, at + 16);
+        let length = u32_at(zip, at + 20) as usize;
+        assert_eq!(u32_at(zip, at + 24) as usize, length);
+        let name_length = u16_at(zip, at + 28);
+        let extra = u16_at(zip, at + 30) + u16_at(zip, at + 32);
+        let offset = u32_at(zip, at + 42) as usize;
+        let name = &zip[at + 46..at + 46 + name_length];
+        at += 46 + name_length + extra;
+
+        assert_eq!(u32_at(zip, offset), 0x0403_4b50, "local header");
+        assert_eq!(u32_at(zip, offset + 14), crc);
+        assert_eq!(&zip[offset + 30..offset + 30 + name_length], name);
+        let start = offset + 30 + name_length + u16_at(zip, offset + 28);
+        let contents = &zip[start..start + length];
+        assert_eq!(crc32(contents), crc);
+
+        files
+            .push((String::from_utf8(name.to_vec()).unwrap(), contents.into()));
+    }
+    assert_eq!(at, end);
+    files
+}
+
+#[test]
+fn csv_reads_quoted_cells() {
+    let rows = csv("\u{feff}data,caption\r\n\
+         \"https://qrcode.show/?a=1,b=2\",plain\r\n\
+         \r\n\
+         \"two\r\nlines\",\"say \"\"hi\"\"\"\n\
+         last,\n");
+    assert_eq!(rows.len(), 3);
+
+    // The byte order mark isn't part of the first column.
+    assert_eq!(
+        rows[0].fields.get("data"),
+        Some("https://qrcode.show/?a=1,b=2")
+    );
+    assert_eq!(rows[0].fields.get("caption"), Some("plain"));
+    assert_eq!(rows[0].line, 2);
+
+    // Line breaks in quotes are kept, and count for the lines after.
+    assert_eq!(rows[1].fields.get("data"), Some("two\r\nlines"));
+    assert_eq!(rows[1].fields.get("caption"), Some("say \"hi\""));
+    assert_eq!((rows[1].number, rows[1].line), (2, 4));
+
+    assert_eq!(rows[2].fields.get("data"), Some("last"));
+    assert_eq!(rows[2].fields.get("caption"), None);
+    assert_eq!(rows[2].line, 6);
+}
+
+#[test]
+fn csv_refuses_malformed_records() {
+    let error = parse_rows(Some("text/csv"), b"data\nok\n\"open,\nstill\n")
+        .unwrap_err();
+    assert_eq!(error.line, Some(3));
+
+    let error = parse_rows(Some("text/csv"), b"data\na,b\n").unwrap_err();
+    assert_eq!(error.line, Some(2));
+
+    assert!(parse_rows(Some("text/csv"), b"data\n\n").is_err());
+}
+
+#[test]
+fn zip_reads_back() {
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+
+    let mut zip = Zip::new();
+    zip.add("a.txt", b"hello");
+    zip.add("käse/b.bin", &[0, 1, 2, 255]);
+    zip.add("empty", b"");
+    let files = unzip(&zip.finish());
+
+    assert_eq!(
+        files,
+        vec![
+            ("a.txt".to_string(), b"hello".to_vec()),
+            ("käse/b.bin".to_string(), vec![0, 1, 2, 255]),
+            ("empty".to_string(), vec![]),
+        ]
+    );
+}
+
+#[test]
+fn batch_zip_holds_the_codes() {
+    let rows = csv("data,name\nhttps://qrcode.show,home\n42,answer\n");
+    let gen = Generator {
+        format: Format::Png,
+        ..Default::default()
+    };
+    let mut batch = Batch::new(BatchOutput::Zip, None, None, &rows);
+    for row in &rows {
+        batch.add(row, &gen).unwrap();
+    }
+
+    let (output, zip) = batch.finish();
+    assert_eq!(output, BatchOutput::Zip);
+    let files = unzip(&zip);
+    let names: Vec<&str> = files.iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(names, ["home.png", "answer.png"]);
+    assert_eq!(files[0].1, gen.generate(b"https://qrcode.show").unwrap());
+}
+
+#[test]
+fn batch_zip_defaults_to_png() {
+    // What an Accept of application/zip, naming no type of code, gives.
+    let rows = csv("data\nhttps://qrcode.show\n");
+    let mut batch = Batch::new(BatchOutput::Zip, None, None, &rows);
+    batch.add(&rows[0], &Generator::default()).unwrap();
+    assert!(batch.size() > 0);
+
+    let files = unzip(&batch.finish().1);
+    assert_eq!(files[0].0, "1.png");
+    assert!(image::load_from_memory(&files[0].1).is_ok());
+}
+
+#[test]
+fn pdf_xref_points_at_the_objects() {
+    let rows = csv("data\nhttps://qrcode.show\n42\n");
+    let mut batch = Batch::new(BatchOutput::Pdf, None, None, &rows);
+    for row in &rows {
+        batch.add(row, &Generator::default()).unwrap();
+    }
+    let (output, pdf) = batch.finish();
+    assert_eq!(output, BatchOutput::Pdf);
+    assert!(pdf.starts_with(b"%PDF-"));
+
+    let tail = String::from_utf8_lossy(&pdf[pdf.len() - 64..]).into_owned();
+    let xref: usize = tail
+        .split("startxref\n")
+        .nth(1)
+        .and_then(|s| s.lines().next())
+        .and_then(|s| s.parse().ok())
+        .unwrap();
+
+    let table = String::from_utf8_lossy(&pdf[xref..]).into_owned();
+    let mut lines = table.lines();
+    assert_eq!(lines.next(), Some("xref"));
+    let size: usize = lines.next().unwrap()[2..].parse().unwrap();
+    assert!(size > 1);
+
+    // Each entry is 20 bytes, the first one free.
+    let entries = &pdf[xref + 5 + format!("0 {}\n", size).len()..];
+    for id in 0..size {
+        let entry = &entries[id * 20..(id + 1) * 20];
+        let offset: usize =
+            std::str::from_utf8(&entry[..10]).unwrap().parse().unwrap();
+        if id == 0 {
+            assert_eq!(entry, b"0000000000 65535 f \n");
+            continue;
+        }
+        assert_eq!(&entry[10..], b" 00000 n \n");
+        let object = format!("{} 0 obj", id);
+        assert!(
+            pdf[offset..].starts_with(object.as_bytes()),
+            "object {} at {}",
+            id,
+            offset
+        );
+    }
+    assert!(table.contains(&format!("/Size {}", size)));
+}
@@ -0,0 +1,103 @@
+use image::Rgba;
+use libs::Color;
+use libs::Format;
+use libs::Generator;
+
+mod common;
+
+const INPUT: &[u8] = b"https://qrcode.show";
+
+const COLORS: &[(&str, &str)] = &[
+    ("336699", "ffffff"),
+    ("000", "fff"),
+    ("navy", "rgb(250, 240, 230)"),
+    ("hsl(210, 50%, 40%)", "#eeeeee"),
+    ("#123456", "abcdef"),
+];
+
+fn svg_fills(svg: &str) -> Vec<Rgba<u8>> {
+    svg.split(r#"fill=""#)
+        .skip(1)
+        .filter_map(|s| s.split('"').next())
+        .map(|fill| fill.parse::<Color>().unwrap().to_rgba())
+        .collect()
+}
+
+fn raster_colors(bytes: &[u8]) -> (Rgba<u8>, Rgba<u8>) {
+    let image = image::load_from_memory(bytes).unwrap().to_rgba8();
+    let modules = Generator::default().code(INPUT).unwrap().width() as u32 + 8;
+    let module = image.width() / modules;
+
+    // The top left corner is always in the quiet zone and the center of the
+    // top left finder pattern is always dark.
+    let light = *image.get_pixel(0, 0);
+    let center = (4 + 3) * module + module / 2;
+    let dark = *image.get_pixel(center, center);
+    (dark, light)
+}
+
+fn close(a: Rgba<u8>, b: Rgba<u8>, tolerance: u8) -> bool {
+    a.0.iter()
+        .zip(b.0.iter())
+        .all(|(x, y)| (*x as i16 - *y as i16).abs() <= tolerance as i16)
+}
+
+#[test]
+fn png_matches_svg_fill() {
+    for (dark, light) in COLORS {
+        let svg = common::colored(Format::Svg, dark, light)
+            .generate(INPUT)
+            .unwrap();
+        let fills = svg_fills(&String::from_utf8(svg).unwrap());
+        assert_eq!(fills.len(), 2);
+
+        let png = common::colored(Format::Png, dark, light)
+            .generate(INPUT)
+            .unwrap();
+        let (d, l) = raster_colors(&png);
+
+        assert_eq!(l, fills[0], "light color {}", light);
+        assert_eq!(d, fills[1], "dark color {}", dark);
+    }
+}
+
+#[test]
+fn jpeg_is_close_to_svg_fill() {
+    for (dark, light) in COLORS {
+        let svg = common::colored(Format::Svg, dark, light)
+            .generate(INPUT)
+            .unwrap();
+        let fills = svg_fills(&String::from_utf8(svg).unwrap());
+
+        let jpeg = common::colored(Format::Jpeg, dark, light)
+            .generate(INPUT)
+            .unwrap();
+        let (d, l) = raster_colors(&jpeg);
+
+        assert!(close(l, fills[0], 8), "light color {}: {:?}", light, l);
+        assert!(close(d, fills[1], 8), "dark color {}: {:?}", dark, d);
+    }
+}
+
+#[test]
+fn hex_color_is_not_linearized() {
+    let color = "336699".parse::<Color>().unwrap();
+    assert_eq!(color.to_rgba(), Rgba([0x33, 0x66, 0x99, 0xff]));
+    assert_eq!(color.to_svg(), "#336699");
+}
+
+#[test]
+fn png_srgb_chunk_is_optional() {
+    let mut gen = common::colored(Format::Png, "336699", "fff");
+
+    let png = gen.generate(INPUT).unwrap();
+    assert!(!png.windows(4).any(|w| w == b"sRGB"));
+
+    gen.srgb = Some(true);
+    let png = gen.generate(INPUT).unwrap();
+    assert!(png.windows(4).any(|w| w == b"sRGB"));
+    assert!(png.windows(4).any(|w| w == b"gAMA"));
+
+    let (dark, _) = raster_colors(&png);
+    assert_eq!(dark, Rgba([0x33, 0x66, 0x99, 0xff]));
+}
@@ -0,0 +1,313 @@
+//! Generators and reading codes back, shared by the tests.
+#![allow(dead_code)]
+
+use std::collections::HashSet;
+
+use image::Rgba;
+use libs::Area;
+use libs::EcLevel;
+use libs::Format;
+use libs::Generator;
+use libs::Kind;
+use libs::Matrix;
+use libs::QrCode;
+use libs::Version;
+
+const ALPHANUMERIC: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// A generator of `format` in the given colors, the rest left as default.
+pub fn colored(format: Format, dark: &str, light: &str) -> Generator {
+    Generator {
+        format,
+        dark_color: Some(dark.parse().unwrap()),
+        light_color: Some(light.parse().unwrap()),
+        ..Default::default()
+    }
+}
+
+pub fn luma(px: &Rgba<u8>) -> f64 {
+    let [r, g, b, _] = px.0;
+    0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64
+}
+
+/// Reads the code of `input` back from `bytes` the way a scanner does, by
+/// thresholding the center of each module.
+pub fn sample(bytes: &[u8], gen: &Generator, input: &[u8]) -> Vec<bool> {
+    let image = image::load_from_memory(bytes).unwrap().to_rgba8();
+    let code = gen.code(input).unwrap();
+    let margin = gen.margin(&code);
+    let size = gen.module_size.expect("a fixed module size");
+
+    let dark = luma(&gen.dark_color().to_rgba());
+    let light = luma(&gen.light_color().to_rgba());
+    let threshold = (dark + light) / 2.0;
+
+    let mut modules = vec![];
+    for y in 0..code.width() as u32 {
+        for x in 0..code.width() as u32 {
+            let center = |m: u32| (m + margin) * size + size / 2;
+            let px = image.get_pixel(center(x), center(y));
+            modules.push(luma(px) < threshold);
+        }
+    }
+    modules
+}
+
+/// Multiplies in GF(256) with the polynomial QR codes use.
+fn mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 == 1 {
+            product ^= a;
+        }
+        a = if a & 0x80 != 0 { a << 1 ^ 0x1d } else { a << 1 };
+        b >>= 1;
+    }
+    product
+}
+
+fn inverse(a: u8) -> u8 {
+    (0..254).fold(1, |p, _| mul(p, a))
+}
+
+/// Recovers the codewords at `lost` of a block, data first, from the
+/// others. The block as a polynomial is a multiple of the generator, so it
+/// is zero at each of its `ec` roots, 2^0 to 2^(ec - 1): that gives a linear
+/// system in the lost codewords. The roots left over check the rest.
+fn correct(block: &mut [u8], lost: &[usize], ec: usize) {
+    assert!(lost.len() <= ec, "{} codewords lost of {}", lost.len(), ec);
+    let n = block.len();
+    let powers: Vec<u8> = (0..255)
+        .scan(1, |a, _| {
+            let power = *a;
+            *a = mul(*a, 2);
+            Some(power)
+        })
+        .collect();
+    // The value of codeword `k`'s term at the root 2^j, without the codeword.
+    let term = |k: usize, j: usize| powers[(n - 1 - k) * j % 255];
+    let syndrome = |block: &[u8], j: usize| {
+        (0..n).fold(0, |s, k| s ^ mul(block[k], term(k, j)))
+    };
+
+    for &k in lost {
+        block[k] = 0;
+    }
+    let mut rows: Vec<Vec<u8>> = (0..lost.len())
+        .map(|j| {
+            let mut row: Vec<u8> = lost.iter().map(|&k| term(k, j)).collect();
+            row.push(syndrome(block, j));
+            row
+        })
+        .collect();
+
+    // Gauss-Jordan elimination; the terms of distinct codewords make a
+    // Vandermonde matrix, so there is always a pivot.
+    for col in 0..lost.len() {
+        let pivot = (col..lost.len()).find(|&r| rows[r][col] != 0).unwrap();
+        rows.swap(col, pivot);
+        let scale = inverse(rows[col][col]);
+        for v in rows[col].iter_mut() {
+            *v = mul(*v, scale);
+        }
+        let pivot = rows[col].clone();
+        for (r, row) in rows.iter_mut().enumerate() {
+            let factor = row[col];
+            if r != col && factor != 0 {
+                for (v, p) in row.iter_mut().zip(&pivot) {
+                    *v ^= mul(factor, *p);
+                }
+            }
+        }
+    }
+    for (row, &k) in rows.iter().zip(lost) {
+        block[k] = row[lost.len()];
+    }
+
+    for j in 0..ec {
+        assert_eq!(syndrome(block, j), 0, "a codeword was read wrong");
+    }
+}
+
+/// The 15 bits of format information for the error correction level and
+/// the mask, with their BCH code, masked.
+fn format_info(data: u16) -> u16 {
+    let mut rem = data << 10;
+    for i in (10..15).rev() {
+        if rem & (1 << i) != 0 {
+            rem ^= 0x537 << (i - 10);
+        }
+    }
+    (data << 10 | rem) ^ 0x5412
+}
+
+fn mask(mask: u16, x: usize, y: usize) -> bool {
+    match mask {
+        0 => (x + y).is_multiple_of(2),
+        1 => y.is_multiple_of(2),
+        2 => x.is_multiple_of(3),
+        3 => (x + y).is_multiple_of(3),
+        4 => (y / 2 + x / 3).is_multiple_of(2),
+        5 => (x * y) % 2 + (x * y) % 3 == 0,
+        6 => ((x * y) % 2 + (x * y) % 3).is_multiple_of(2),
+        _ => ((x + y) % 2 + (x * y) % 3).is_multiple_of(2),
+    }
+}
+
+/// Decodes the payload of a normal code from its modules, without error
+/// correction, so any module read wrong fails the test.
+pub fn decode(modules: &[bool], width: usize) -> Vec<u8> {
+    decode_hidden(modules, width, None)
+}
+
+/// Decodes the payload of a normal code from its modules, recovering the
+/// codewords with modules in `hidden` by error correction. Any other module
+/// read wrong fails the test.
+pub fn decode_hidden(
+    modules: &[bool],
+    width: usize,
+    hidden: Option<Area>,
+) -> Vec<u8> {
+    let version = (width - 17) / 4;
+    let dark = |x: usize, y: usize| modules[y * width + x];
+    let is_hidden = |x: usize, y: usize| {
+        hidden.is_some_and(|a| a.positions().any(|p| p == (x, y)))
+    };
+
+    // The first copy of the format information, next to the top left eye.
+    let coords = [
+        (0, 8),
+        (1, 8),
+        (2, 8),
+        (3, 8),
+        (4, 8),
+        (5, 8),
+        (7, 8),
+        (8, 8),
+        (8, 7),
+        (8, 5),
+        (8, 4),
+        (8, 3),
+        (8, 2),
+        (8, 1),
+        (8, 0),
+    ];
+    let format = coords
+        .iter()
+        .fold(0, |bits, &(x, y)| bits << 1 | dark(x, y) as u16);
+    let data = (0..32)
+        .min_by_key(|&d| (format_info(d) ^ format).count_ones())
+        .unwrap();
+    let ec_level =
+        [EcLevel::M, EcLevel::L, EcLevel::H, EcLevel::Q][(data >> 3) as usize];
+
+    // Any code of the version and level has the same layout.
+    let layout =
+        QrCode::with_version(b"", Version::Normal(version as i16), ec_level)
+            .unwrap();
+    assert_eq!(layout.width(), width);
+    let matrix = Matrix::new(&layout, 0);
+
+    // The zigzag, two columns at a time from the right.
+    let mut bits = vec![];
+    let mut erased = HashSet::new();
+    let mut right = width as isize - 1;
+    let mut upwards = true;
+    while right > 0 {
+        if right == 6 {
+            right -= 1;
+        }
+        for i in 0..width {
+            let y = if upwards { width - 1 - i } else { i };
+            for x in [right as usize, right as usize - 1] {
+                if matrix.kind(x, y) == Kind::Data {
+                    if is_hidden(x, y) {
+                        erased.insert(bits.len() / 8);
+                    }
+                    bits.push(dark(x, y) ^ mask(data & 7, x, y));
+                }
+            }
+        }
+        upwards = !upwards;
+        right -= 2;
+    }
+    let mut codewords: Vec<u8> = bits
+        .chunks_exact(8)
+        .map(|byte| byte.iter().fold(0, |b, &bit| b << 1 | bit as u8))
+        .collect();
+
+    // The data codewords, block after block.
+    let (blocks, ec_per_block) = libs::blocks(&layout);
+    let count = blocks.iter().max().unwrap() + 1;
+    let data_len = blocks.len() - count * ec_per_block;
+    for block in 0..count {
+        let at: Vec<usize> =
+            (0..blocks.len()).filter(|&i| blocks[i] == block).collect();
+        let mut codes: Vec<u8> = at.iter().map(|&i| codewords[i]).collect();
+        let lost: Vec<usize> =
+            (0..at.len()).filter(|&k| erased.contains(&at[k])).collect();
+        correct(&mut codes, &lost, ec_per_block);
+        for (&i, &code) in at.iter().zip(&codes) {
+            codewords[i] = code;
+        }
+    }
+    let (blocks, codewords) = (&blocks, &codewords);
+    let stream: Vec<bool> = (0..count)
+        .flat_map(|block| (0..data_len).filter(move |&i| blocks[i] == block))
+        .flat_map(|i| (0..8).rev().map(move |bit| codewords[i] >> bit & 1 == 1))
+        .collect();
+
+    let mut at = 0;
+    let mut read = |n: usize| {
+        let value = stream[at..at + n]
+            .iter()
+            .fold(0, |v, &bit| v << 1 | bit as usize);
+        at += n;
+        value
+    };
+    let length_bits = |bits: [usize; 3]| match version {
+        1..=9 => bits[0],
+        10..=26 => bits[1],
+        _ => bits[2],
+    };
+
+    let mut payload = vec![];
+    loop {
+        match read(4) {
+            0 => break,
+            0b0001 => {
+                let mut len = read(length_bits([10, 12, 14]));
+                while len > 0 {
+                    let (digits, bits) = match len {
+                        1 => (1, 4),
+                        2 => (2, 7),
+                        _ => (3, 10),
+                    };
+                    let number = format!("{:01$}", read(bits), digits);
+                    payload.extend_from_slice(number.as_bytes());
+                    len -= digits;
+                }
+            }
+            0b0010 => {
+                let mut len = read(length_bits([9, 11, 13]));
+                while len > 1 {
+                    let pair = read(11);
+                    payload.push(ALPHANUMERIC[pair / 45]);
+                    payload.push(ALPHANUMERIC[pair % 45]);
+                    len -= 2;
+                }
+                if len == 1 {
+                    payload.push(ALPHANUMERIC[read(6)]);
+                }
+            }
+            0b0100 => {
+                let len = read(length_bits([8, 16, 16]));
+                for _ in 0..len {
+                    payload.push(read(8) as u8);
+                }
+            }
+            mode => panic!("unexpected mode {:04b}", mode),
+        }
+    }
+    payload
+}
@@ -0,0 +1,154 @@
+use libs::CaptionPosition;
+use libs::Format;
+use libs::Frame;
+use libs::Generator;
+use libs::Rotation;
+
+const INPUT: &[u8] = b"https://qrcode.show";
+
+/// The code of `INPUT` is 25 modules wide, 33 with the quiet zone, and the
+/// caption band 7 modules high.
+const WIDTH: f64 = 33.0;
+const BAND: f64 = 7.0;
+
+/// An SVG code in `frame` with `caption`.
+fn framed(frame: Frame, caption: &str) -> Generator {
+    Generator {
+        format: Format::Svg,
+        frame: Some(frame),
+        frame_text: Some(caption.into()),
+        ..Default::default()
+    }
+}
+
+/// The path data of the caption, the last path of the SVG output.
+fn caption(gen: &Generator) -> String {
+    let svg = String::from_utf8(gen.generate(INPUT).unwrap()).unwrap();
+    let path = &svg[svg.rfind("<path").unwrap()..];
+    let d = &path[path.find(" d=\"").unwrap() + 4..];
+    d[..d.find('"').unwrap()].to_string()
+}
+
+/// The number of outlines of path data, and the box around their points,
+/// as (left, top, right, bottom). Curve handles count as points.
+fn outlines(d: &str) -> (usize, (f64, f64, f64, f64)) {
+    let numbers: Vec<f64> = d
+        .split(|c: char| c.is_ascii_alphabetic() || c == ' ')
+        .filter(|n| !n.is_empty())
+        .map(|n| n.parse().unwrap())
+        .collect();
+    let mut bounds = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+    for point in numbers.chunks(2) {
+        bounds.0 = bounds.0.min(point[0]);
+        bounds.1 = bounds.1.min(point[1]);
+        bounds.2 = bounds.2.max(point[0]);
+        bounds.3 = bounds.3.max(point[1]);
+    }
+    (d.matches('M').count(), bounds)
+}
+
+#[test]
+fn short_captions_stay_on_one_line() {
+    let (count, (left, top, right, bottom)) =
+        outlines(&caption(&framed(Frame::Border, "SCAN ME")));
+    // S, C, A and its counter, N, M and E.
+    assert_eq!(count, 7);
+
+    // Cap letters fill half the band, centered under the code and its
+    // border.
+    let height = bottom - top;
+    assert!((3.4..3.8).contains(&height), "{}", height);
+    let middle = WIDTH + 2.0 + BAND / 2.0;
+    assert!(((top + bottom) / 2.0 - middle).abs() < 0.05);
+    assert!(((left + right) / 2.0 - (WIDTH / 2.0 + 1.0)).abs() < 0.05);
+
+    // At the top, the band is above the code.
+    let gen = Generator {
+        frame_position: Some(CaptionPosition::Top),
+        ..framed(Frame::Border, "SCAN ME")
+    };
+    let (_, (_, top, _, bottom)) = outlines(&caption(&gen));
+    assert!(((top + bottom) / 2.0 - BAND / 2.0).abs() < 0.05);
+}
+
+#[test]
+fn long_captions_wrap_onto_two_lines() {
+    let text = "SCAN THIS CODE TO SEE THE MENU OF TODAY";
+    let (_, (left, top, right, bottom)) =
+        outlines(&caption(&framed(Frame::Border, text)));
+    assert!(left >= 1.0 && right <= WIDTH + 1.0);
+
+    // Two lines half the band apart, their caps at most 70% of the full
+    // size.
+    let height = bottom - top;
+    assert!(height > BAND / 2.0, "{}", height);
+    assert!(height <= BAND / 2.0 + 0.7 * BAND / 2.0 + 0.1, "{}", height);
+    assert!(top >= WIDTH + 2.0 && bottom <= WIDTH + 2.0 + BAND);
+
+    // Without a space to break at, it shrinks on one line.
+    let (_, (_, top, _, bottom)) =
+        outlines(&caption(&framed(Frame::Border, "SCANTHISCODENOW")));
+    assert!(bottom - top < BAND / 2.0);
+}
+
+#[test]
+fn overlong_captions_are_cut_short() {
+    let text = "A".repeat(300);
+    let (count, (left, top, right, bottom)) =
+        outlines(&caption(&framed(Frame::Banner, &text)));
+    assert!(left >= 2.0 && right <= WIDTH + 2.0);
+
+    // Some of the letters, each an outline and its counter, and the three
+    // dots of the ellipsis.
+    assert_eq!(count % 2, 1);
+    let letters = (count - 3) / 2;
+    assert!((10..300).contains(&letters), "{}", letters);
+
+    // No smaller than 35% of the full size.
+    let height = bottom - top;
+    assert!(height >= 0.35 * BAND / 2.0 - 0.05, "{}", height);
+
+    // Two lines, each cut short.
+    let text = format!("{} {}", "A".repeat(150), "A".repeat(150));
+    let (count, (_, top, _, bottom)) =
+        outlines(&caption(&framed(Frame::Banner, &text)));
+    assert!(count < 300);
+    assert!(bottom - top > BAND / 2.0);
+}
+
+#[test]
+fn frames_add_to_the_output_size() {
+    // In modules: the code, the frame lines or the ribbon, and the band.
+    let cases = [
+        (Frame::Border, (35, 42)),
+        (Frame::Rounded, (35, 41)),
+        (Frame::Bubble, (33, 41)),
+        (Frame::Banner, (37, 40)),
+    ];
+    for (frame, (width, height)) in cases {
+        let gen = Generator {
+            format: Format::Png,
+            module_size: Some(4),
+            frame: Some(frame),
+            ..Default::default()
+        };
+        let png = gen.generate(INPUT).unwrap();
+        let image = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(image.dimensions(), (width * 4, height * 4), "{:?}", frame);
+
+        let gen = Generator {
+            rotation: Some(Rotation::Quarter),
+            ..gen
+        };
+        let png = gen.generate(INPUT).unwrap();
+        let image = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(image.dimensions(), (height * 4, width * 4), "{:?}", frame);
+    }
+
+    let gen = Generator {
+        module_size: Some(4),
+        ..framed(Frame::Border, "SCAN ME")
+    };
+    let svg = String::from_utf8(gen.generate(INPUT).unwrap()).unwrap();
+    assert!(svg.contains(r#"width="140" height="168""#));
+}
@@ -0,0 +1,108 @@
+use image::Rgba;
+use libs::Format;
+use libs::Generator;
+use libs::Gradient;
+use libs::GradientError;
+use libs::GradientKind;
+
+const INPUT: &[u8] = b"https://qrcode.show";
+
+fn rgb(r: u8, g: u8, b: u8) -> Rgba<u8> {
+    Rgba([r, g, b, 255])
+}
+
+#[test]
+fn linear_gradients_end_at_the_edges() {
+    let gradient: Gradient = "linear(to right, red, blue)".parse().unwrap();
+    assert_eq!(gradient.kind, GradientKind::Linear(90.0));
+    for y in [0.0, 50.0, 100.0] {
+        assert_eq!(gradient.color_at(0.0, y, 100.0), rgb(255, 0, 0));
+        assert_eq!(gradient.color_at(100.0, y, 100.0), rgb(0, 0, 255));
+        assert_eq!(gradient.color_at(50.0, y, 100.0), rgb(128, 0, 128));
+    }
+
+    // At an angle, the corners get the end colors.
+    let gradient: Gradient = "linear(45deg, red, blue)".parse().unwrap();
+    assert_eq!(gradient.color_at(0.0, 100.0, 100.0), rgb(255, 0, 0));
+    assert_eq!(gradient.color_at(100.0, 0.0, 100.0), rgb(0, 0, 255));
+
+    // Without an angle, top to bottom.
+    let gradient: Gradient = "linear(red, blue)".parse().unwrap();
+    assert_eq!(gradient.color_at(50.0, 0.0, 100.0), rgb(255, 0, 0));
+    assert_eq!(gradient.color_at(50.0, 100.0, 100.0), rgb(0, 0, 255));
+}
+
+#[test]
+fn radial_gradients_end_at_the_corners() {
+    let gradient: Gradient = "radial(red, lime 50%, blue)".parse().unwrap();
+    assert_eq!(gradient.color_at(50.0, 50.0, 100.0), rgb(255, 0, 0));
+    assert_eq!(gradient.color_at(0.0, 0.0, 100.0), rgb(0, 0, 255));
+    assert_eq!(gradient.color_at(100.0, 100.0, 100.0), rgb(0, 0, 255));
+
+    // Halfway to the corner.
+    assert_eq!(gradient.color_at(25.0, 25.0, 100.0), rgb(0, 255, 0));
+}
+
+#[test]
+fn dark_modules_are_painted_with_the_gradient() {
+    let gradient: Gradient = "linear(to right, red, blue)".parse().unwrap();
+    let gen = Generator {
+        format: Format::Png,
+        module_size: Some(8),
+        dark_gradient: Some(gradient.clone()),
+        ..Default::default()
+    };
+    let png = gen.generate(INPUT).unwrap();
+    let image = image::load_from_memory(&png).unwrap().to_rgba8();
+    let size = image.width() as f64;
+
+    let code = gen.code(INPUT).unwrap();
+    let margin = gen.margin(&code);
+    let modules = code.to_colors();
+    let mut dark = 0;
+    for (i, module) in modules.iter().enumerate() {
+        let x = (i % code.width()) as u32 + margin;
+        let y = (i / code.width()) as u32 + margin;
+        let (px, py) = (x * 8 + 4, y * 8 + 4);
+        let pixel = *image.get_pixel(px, py);
+        if *module == qrcode::Color::Dark {
+            dark += 1;
+            let expected =
+                gradient.color_at(px as f64 + 0.5, py as f64 + 0.5, size);
+            assert_eq!(pixel, expected, "({}, {})", x, y);
+        } else {
+            assert_eq!(pixel, rgb(255, 255, 255), "({}, {})", x, y);
+        }
+    }
+    assert!(dark > 0);
+
+    // In SVG, the gradient line runs across the whole view box.
+    let gen = Generator {
+        format: Format::Svg,
+        ..gen
+    };
+    let svg = String::from_utf8(gen.generate(INPUT).unwrap()).unwrap();
+    assert!(
+        svg.contains(r#"x1="0" y1="16.5" x2="33" y2="16.5""#),
+        "{}",
+        svg
+    );
+    assert!(svg.contains(r##"<stop offset="0" stop-color="#ff0000"/>"##));
+    assert!(svg.contains(r##"<stop offset="1" stop-color="#0000ff"/>"##));
+}
+
+#[test]
+fn angles_must_be_finite() {
+    let invalid = |angle: &str| {
+        let err = format!("linear({}, red, blue)", angle)
+            .parse::<Gradient>()
+            .unwrap_err();
+        assert!(matches!(err, GradientError::Color(_)), "{}", angle);
+        err.to_string().replace(angle, "")
+    };
+
+    let expected = invalid("fortydeg");
+    for angle in ["NaNdeg", "infdeg", "-infdeg", "1e400deg", "1e308turn"] {
+        assert_eq!(invalid(angle), expected, "{}", angle);
+    }
+}
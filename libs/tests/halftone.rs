@@ -0,0 +1,122 @@
+use image::Rgba;
+use image::RgbaImage;
+use libs::Format;
+use libs::GenerateError;
+use libs::Generator;
+use libs::Halftone;
+use libs::HalftoneError;
+
+mod common;
+
+const INPUT: &[u8] = b"https://qrcode.show";
+
+/// Longer inputs, in several error correction blocks, and with numeric and
+/// alphanumeric segments.
+const INPUTS: &[&[u8]] = &[
+    INPUT,
+    b"https://qrcode.show/?utm_source=poster&utm_medium=print&id=4242",
+    b"HTTPS://QRCODE.SHOW/ORDERS/0123456789012345",
+];
+
+const MODULE_SIZE: u32 = 9;
+
+/// A busy picture covering the whole luma range.
+fn picture() -> Vec<u8> {
+    let image = RgbaImage::from_fn(160, 120, |x, y| {
+        let r = (x * 255 / 160) as u8;
+        let g = (y * 255 / 120) as u8;
+        let b = if (x / 10 + y / 10) % 2 == 0 { 40 } else { 220 };
+        Rgba([r, g, b, 255])
+    });
+
+    let mut png = vec![];
+    image::codecs::png::PngEncoder::new(&mut png)
+        .encode(&image, 160, 120, image::ColorType::Rgba8)
+        .unwrap();
+    png
+}
+
+/// A code of `format` in the given colors drawn over `picture`.
+fn halftone(format: Format, dark: &str, light: &str) -> Generator {
+    Generator {
+        module_size: Some(MODULE_SIZE),
+        halftone: Some(Halftone::from_bytes(&picture()).unwrap()),
+        ..common::colored(format, dark, light)
+    }
+}
+
+#[test]
+fn halftone_modules_read_back() {
+    for (dark, light) in &[("000", "fff"), ("navy", "#fffbe6")] {
+        for format in [Format::Png, Format::Jpeg] {
+            let gen = halftone(format, dark, light);
+            let expected: Vec<bool> = gen
+                .code(INPUT)
+                .unwrap()
+                .to_colors()
+                .into_iter()
+                .map(|c| c.select(true, false))
+                .collect();
+
+            let bytes = gen.generate(INPUT).unwrap();
+            assert_eq!(
+                common::sample(&bytes, &gen, INPUT),
+                expected,
+                "{} on {}",
+                dark,
+                light
+            );
+        }
+    }
+}
+
+#[test]
+fn halftone_raises_error_correction() {
+    let gen = halftone(Format::Png, "000", "fff");
+    let code = gen.code(INPUT).unwrap();
+    assert_eq!(code.error_correction_level(), libs::EcLevel::H);
+}
+
+#[test]
+fn halftone_codes_decode() {
+    for input in INPUTS {
+        for (dark, light) in &[("000", "fff"), ("navy", "#fffbe6")] {
+            for format in [Format::Png, Format::Jpeg] {
+                let gen = halftone(format, dark, light);
+                let bytes = gen.generate(input).unwrap();
+                let width = gen.code(input).unwrap().width();
+                let modules = common::sample(&bytes, &gen, input);
+                assert_eq!(
+                    common::decode(&modules, width),
+                    *input,
+                    "{} on {}",
+                    dark,
+                    light
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn halftone_refuses_what_it_cannot_draw() {
+    let gen = halftone(Format::Svg, "000", "fff");
+    assert!(gen.check_halftone().is_err());
+    assert_eq!(
+        gen.generate(INPUT),
+        Err(GenerateError::Halftone(HalftoneError::Format))
+    );
+
+    let gen = Generator {
+        shape: Some("circle".parse().unwrap()),
+        ..halftone(Format::Png, "000", "fff")
+    };
+    assert!(gen.check_halftone().is_err());
+    assert_eq!(
+        gen.generate(INPUT),
+        Err(GenerateError::Halftone(HalftoneError::Style))
+    );
+
+    let gen = halftone(Format::Png, "000", "fff");
+    assert!(gen.check_halftone().is_ok());
+}
@@ -0,0 +1,86 @@
+use libs::Generator;
+use libs::InputEncoding;
+use libs::InputError;
+
+fn decode(encoding: &str, input: &str) -> Result<Vec<u8>, InputError> {
+    encoding
+        .parse::<InputEncoding>()
+        .unwrap()
+        .decode(input.as_bytes())
+}
+
+#[test]
+fn base64_decodes() {
+    for input in ["AAECA/8=", "AAECA/8", " AAEC\nA/8= "] {
+        assert_eq!(decode("base64", input).unwrap(), [0, 1, 2, 3, 255]);
+    }
+    assert_eq!(decode("base64", "aGk=").unwrap(), b"hi");
+    assert_eq!(decode("base64", "").unwrap(), b"");
+}
+
+#[test]
+fn base64url_uses_its_own_alphabet() {
+    assert_eq!(decode("base64url", "-_8=").unwrap(), [251, 255]);
+    assert_eq!(decode("base64url", "-_8").unwrap(), [251, 255]);
+    assert!(decode("base64url", "+/8=").is_err());
+    assert!(decode("base64", "-_8=").is_err());
+}
+
+#[test]
+fn base64_refuses_bad_padding() {
+    for input in ["aGk==", "aGk===", "aG=k", "a===", "aGVsbG8=="] {
+        assert!(
+            matches!(decode("base64", input), Err(InputError::Base64(_))),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn hex_decodes() {
+    assert_eq!(decode("hex", "00ff7F").unwrap(), [0, 255, 127]);
+    assert_eq!(decode("hex", "de ad\nBE EF").unwrap(), [222, 173, 190, 239]);
+    assert_eq!(decode("hex", "").unwrap(), b"");
+}
+
+#[test]
+fn hex_refuses_odd_lengths_and_other_characters() {
+    assert!(matches!(decode("hex", "abc"), Err(InputError::HexLength)));
+    assert!(matches!(decode("hex", "a b c"), Err(InputError::HexLength)));
+    assert!(matches!(decode("hex", "00 0g"), Err(InputError::Hex(4))));
+    assert!(matches!(decode("hex", "0x00"), Err(InputError::Hex(1))));
+}
+
+#[test]
+fn percent_decodes() {
+    assert_eq!(decode("percent", "a%23b%2fc%2F").unwrap(), b"a#b/c/");
+    assert_eq!(decode("percent", "%00%FF").unwrap(), [0, 255]);
+    assert_eq!(decode("percent", "plain + text").unwrap(), b"plain + text");
+}
+
+#[test]
+fn percent_refuses_malformed_escapes() {
+    for (input, offset) in [("a%g0", 1), ("%2g", 0), ("ab%", 2), ("%2", 0)] {
+        assert!(
+            matches!(decode("percent", input), Err(InputError::Percent(o)) if o == offset),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn encodings_parse() {
+    assert!("Base64URL".parse::<InputEncoding>().is_ok());
+    assert!("base32".parse::<InputEncoding>().is_err());
+
+    // Without an encoding, the input is taken as it is.
+    let gen = Generator::default();
+    assert_eq!(&*gen.decode_input(b"%41").unwrap(), b"%41");
+    let gen = Generator {
+        input_encoding: Some(InputEncoding::Percent),
+        ..Default::default()
+    };
+    assert_eq!(&*gen.decode_input(b"%41").unwrap(), b"A");
+}
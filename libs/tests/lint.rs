@@ -0,0 +1,132 @@
+use libs::Format;
+use libs::Frame;
+use libs::Generator;
+use libs::Warning;
+
+mod common;
+
+const INPUT: &[u8] = b"https://qrcode.show";
+
+fn lint(gen: Generator) -> Vec<Warning> {
+    let gen = Generator {
+        format: Format::Png,
+        ..gen
+    };
+    let code = gen.code(INPUT).unwrap();
+    gen.lint(&code)
+}
+
+#[test]
+fn clean_codes_have_no_warnings() {
+    assert_eq!(lint(Generator::default()), []);
+    assert_eq!(lint(common::colored(Format::Png, "navy", "#fffbe6")), []);
+    assert_eq!(
+        lint(Generator {
+            eye_color: Some("darkred".parse().unwrap()),
+            frame: Some(Frame::Banner),
+            ..Default::default()
+        }),
+        []
+    );
+
+    // Inverting colors that were the wrong way around puts them right.
+    assert_eq!(
+        lint(Generator {
+            invert: Some(true),
+            ..common::colored(Format::Png, "fff", "000")
+        }),
+        []
+    );
+
+    // Text can't be styled, so there is nothing to warn about.
+    let gen = Generator {
+        format: Format::Unicode,
+        ..common::colored(Format::Png, "#777", "#888")
+    };
+    let code = gen.code(INPUT).unwrap();
+    assert_eq!(gen.lint(&code), []);
+}
+
+#[test]
+fn low_contrast_warns() {
+    let warnings = lint(common::colored(Format::Png, "#777", "#888"));
+    assert!(matches!(warnings[..], [Warning::LowContrast(r)] if r < 1.5));
+
+    // The eyes are drawn in their own color.
+    let warnings = lint(Generator {
+        eye_color: Some("#eee".parse().unwrap()),
+        ..Default::default()
+    });
+    assert!(matches!(warnings[..], [Warning::LowContrast(_)]));
+
+    // So is the caption of the frame.
+    let warnings = lint(Generator {
+        frame: Some(Frame::Border),
+        frame_color: Some("#ddd".parse().unwrap()),
+        ..Default::default()
+    });
+    assert!(matches!(warnings[..], [Warning::LowFrameContrast(_)]));
+}
+
+#[test]
+fn inverted_colors_warn() {
+    assert_eq!(
+        lint(common::colored(Format::Png, "fff", "000")),
+        [Warning::InvertedColors]
+    );
+    assert_eq!(
+        lint(Generator {
+            invert: Some(true),
+            ..Default::default()
+        }),
+        [Warning::InvertedColors]
+    );
+}
+
+#[test]
+fn transparent_colors_warn() {
+    let warnings = lint(common::colored(Format::Png, "#00000080", "fff"));
+    assert!(matches!(
+        warnings[..],
+        [Warning::TransparentDarkColor(a), ..] if a < 0.6
+    ));
+
+    let warnings = lint(common::colored(Format::Png, "000", "transparent"));
+    assert!(matches!(
+        warnings[..],
+        [Warning::TransparentLightColor(a)] if a == 0.0
+    ));
+}
+
+#[test]
+fn small_modules_and_quiet_zones_warn() {
+    let warnings = lint(Generator {
+        module_size: Some(2),
+        ..Default::default()
+    });
+    assert_eq!(warnings, [Warning::SmallModules(2)]);
+
+    let warnings = lint(Generator {
+        margin: Some(1),
+        ..Default::default()
+    });
+    assert_eq!(
+        warnings,
+        [Warning::SmallQuietZone {
+            margin: 1,
+            minimum: 4
+        }]
+    );
+
+    let warnings = lint(Generator {
+        quiet_zone: Some(false),
+        ..Default::default()
+    });
+    assert_eq!(
+        warnings,
+        [Warning::SmallQuietZone {
+            margin: 0,
+            minimum: 4
+        }]
+    );
+}
@@ -0,0 +1,119 @@
+use image::Rgba;
+use image::RgbaImage;
+use libs::EcLevel;
+use libs::Format;
+use libs::GenerateError;
+use libs::Generator;
+use libs::Logo;
+use libs::LogoError;
+use libs::VersionType;
+use libs::DEFAULT_LOGO_SIZE;
+use libs::MAX_LOGO_SIZE;
+
+mod common;
+
+/// In one and in several error correction blocks, and with a numeric
+/// segment.
+const INPUTS: &[&[u8]] = &[
+    b"https://qrcode.show",
+    b"https://qrcode.show/?utm_source=poster&utm_medium=print&id=4242",
+    b"HTTPS://QRCODE.SHOW/ORDERS/0123456789012345",
+];
+
+/// A dark square on a transparent background, so that whatever it covers
+/// reads wrong.
+fn logo() -> Logo {
+    let image = RgbaImage::from_fn(64, 48, |x, y| {
+        let edge = x < 4 || y < 4 || x >= 60 || y >= 44;
+        Rgba(if edge { [0; 4] } else { [20, 20, 60, 255] })
+    });
+
+    let mut png = vec![];
+    image::codecs::png::PngEncoder::new(&mut png)
+        .encode(&image, 64, 48, image::ColorType::Rgba8)
+        .unwrap();
+    Logo::from_bytes(&png).unwrap()
+}
+
+/// A PNG code with `logo` at `logo_size` of its width.
+fn with_logo(logo_size: f64) -> Generator {
+    Generator {
+        format: Format::Png,
+        module_size: Some(9),
+        logo: Some(logo()),
+        logo_size: Some(logo_size),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn logo_codes_decode() {
+    let mut decoded = 0;
+    for input in INPUTS {
+        for logo_size in [0.1, DEFAULT_LOGO_SIZE, 0.3, MAX_LOGO_SIZE] {
+            let gen = with_logo(logo_size);
+            let bytes = match gen.generate(input) {
+                Ok(bytes) => bytes,
+                // Larger logos hide too much of some codes.
+                Err(GenerateError::Logo(_))
+                    if logo_size > DEFAULT_LOGO_SIZE =>
+                {
+                    continue
+                }
+                Err(e) => panic!("{}", e),
+            };
+            decoded += 1;
+            let code = gen.code(input).unwrap();
+            let area = gen.logo_area(&code).unwrap();
+            assert!(area.width > 2 && area.height > 2);
+
+            let modules = common::sample(&bytes, &gen, input);
+            assert_eq!(
+                common::decode_hidden(&modules, code.width(), Some(area)),
+                *input,
+                "{} at {}",
+                String::from_utf8_lossy(input),
+                logo_size
+            );
+        }
+    }
+    assert!(decoded > 2 * INPUTS.len());
+}
+
+#[test]
+fn logo_raises_error_correction() {
+    let gen = with_logo(0.2);
+    let code = gen.code(INPUTS[0]).unwrap();
+    assert!(code.error_correction_level() >= EcLevel::Q);
+}
+
+#[test]
+fn logos_that_hide_too_much_are_refused() {
+    let gen = Generator {
+        error_correction_level: Some(EcLevel::L),
+        ..with_logo(0.4)
+    };
+    let err = gen.generate(INPUTS[1]).unwrap_err();
+    assert!(
+        matches!(err, GenerateError::Logo(LogoError::TooLarge { .. })),
+        "{}",
+        err
+    );
+    assert!(err.to_string().starts_with("logo is too large"));
+}
+
+#[test]
+fn logos_on_micro_codes_are_refused() {
+    let gen = Generator {
+        version_type: VersionType::MicroVersion,
+        version_number: Some(4),
+        ..with_logo(0.2)
+    };
+    let code = gen.code(b"1").unwrap();
+    assert!(code.version().is_micro());
+    assert_eq!(gen.check_logo(&code), Err(LogoError::Micro));
+    assert_eq!(
+        gen.generate(b"1"),
+        Err(GenerateError::Logo(LogoError::Micro))
+    );
+}
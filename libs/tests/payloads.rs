@@ -0,0 +1,543 @@
+use libs::build_payload;
+use libs::DateTime;
+use libs::Fields;
+use libs::Format;
+use libs::Generator;
+use libs::Iban;
+use libs::PayloadError;
+
+/// The payload of the fields, given as a query string.
+fn build(kind: &str, query: &str) -> String {
+    match build_payload(kind, &Fields::from_query(query)) {
+        Some(Ok(payload)) => payload.data,
+        _ => panic!("{} was refused: {}", kind, query),
+    }
+}
+
+/// The field a payload is refused for, given as a query string.
+fn refused(kind: &str, query: &str) -> &'static str {
+    match build_payload(kind, &Fields::from_query(query)) {
+        Some(Err(PayloadError::Invalid { field, .. })) => field,
+        Some(Err(PayloadError::Missing(field))) => field,
+        _ => panic!("{} was not refused: {}", kind, query),
+    }
+}
+
+#[test]
+fn wifi_escapes_special_characters() {
+    assert_eq!(
+        build("wifi", r#"ssid=Café;"Bar",\:&password=pa\ss;w,o:rd"#),
+        r#"WIFI:T:WPA;S:Café\;"Bar"\,\\\:;P:pa\\ss\;w\,o\:rd;;"#
+    );
+    assert_eq!(
+        build("wifi", "ssid=Guest&auth=open&hidden=true"),
+        "WIFI:T:nopass;S:Guest;H:true;;"
+    );
+    assert_eq!(
+        build("wifi", "ssid=Guest&auth=wep&password=0123456789"),
+        "WIFI:T:WEP;S:Guest;P:0123456789;;"
+    );
+}
+
+#[test]
+fn wifi_refuses_invalid_credentials() {
+    let cases = [
+        ("ssid", "password=password".to_string()),
+        ("ssid", format!("ssid={}&password=password", "x".repeat(33))),
+        ("password", "ssid=Guest".into()),
+        ("password", "ssid=Guest&password=short".into()),
+        (
+            "password",
+            format!("ssid=Guest&password={}", "g".repeat(64)),
+        ),
+        ("password", "ssid=Guest&auth=open&password=password".into()),
+        ("password", "ssid=Guest&auth=wep&password=012345678".into()),
+        ("auth", "ssid=Guest&auth=wpa4&password=password".into()),
+    ];
+    for (field, query) in &cases {
+        assert_eq!(refused("wifi", query), *field, "{}", query);
+    }
+}
+
+#[test]
+fn vcard_escapes_text() {
+    let vcard = build(
+        "contact",
+        "name=Doe, Jane&org=Acme; Inc, Ltd&title=Head%0Aof sales\
+         &phone=%2B41 44 123 45 67&email=jane@example.com\
+         &address=Main St 1, Zürich",
+    );
+    assert_eq!(
+        vcard,
+        "BEGIN:VCARD\r\n\
+         VERSION:3.0\r\n\
+         N:Doe;Jane;;;\r\n\
+         FN:Jane Doe\r\n\
+         ORG:Acme\\; Inc\\, Ltd\r\n\
+         TITLE:Head\\nof sales\r\n\
+         TEL:+41 44 123 45 67\r\n\
+         EMAIL:jane@example.com\r\n\
+         ADR:;;Main St 1\\, Zürich;;;;\r\n\
+         END:VCARD"
+    );
+}
+
+#[test]
+fn vcard_folds_long_lines_between_characters() {
+    // `TITLE:` and 68 letters leave 1 byte of the 75 for the 2 of `é`.
+    let title = format!("{}é{}", "a".repeat(68), "b".repeat(100));
+    let vcard = build(
+        "contact",
+        &format!("name=Jane Doe&format=vcard4&title={}", title),
+    );
+
+    let lines: Vec<&str> = vcard.split("\r\n").collect();
+    assert!(lines.iter().all(|line| line.len() <= 75), "{:?}", lines);
+    assert_eq!(lines[4], format!("TITLE:{}", "a".repeat(68)));
+    assert!(lines[5].starts_with(" é"));
+    assert_eq!(
+        vcard.replace("\r\n ", ""),
+        format!(
+            "BEGIN:VCARD\r\nVERSION:4.0\r\nN:Doe;Jane;;;\r\nFN:Jane Doe\r\n\
+             TITLE:{}\r\nEND:VCARD",
+            title
+        )
+    );
+}
+
+#[test]
+fn mecard_escapes_text() {
+    let mecard = build(
+        "contact",
+        "format=mecard&name=Doe, Jane&org=Acme; Inc, Ltd\
+         &title=Head%0Aof sales&phone=%2B41 44 123 45 67\
+         &email=jane@example.com&url=https://example.com/a:b",
+    );
+    assert_eq!(
+        mecard,
+        "MECARD:N:Doe,Jane;ORG:Acme\\; Inc\\, Ltd;TEL:+41 44 123 45 67;\
+         EMAIL:jane@example.com;URL:https\\://example.com/a\\:b;\
+         NOTE:Head of sales;;"
+    );
+}
+
+#[test]
+fn contact_refuses_invalid_fields() {
+    let cases = [
+        ("name", "org=Acme"),
+        ("phone", "name=Jane&phone=12a"),
+        ("phone", "name=Jane&phone=1%2B2"),
+        ("phone", "name=Jane&phone=()"),
+        ("email", "name=Jane&email=jane"),
+        ("format", "name=Jane&format=xcard"),
+        ("url", "name=Jane&url=example.com"),
+        ("url", "name=Jane&url=javascript:alert(1)"),
+        ("url", "name=Jane&url=https://"),
+    ];
+    for (field, query) in &cases {
+        assert_eq!(refused("contact", query), *field, "{}", query);
+    }
+}
+
+#[test]
+fn contact_refuses_line_injection() {
+    let injected = "END:VCARD%0D%0ABEGIN:VCARD%0D%0AFN:Mallory";
+    let cases = [
+        ("url", format!("name=Jane&url=https://x%0A{}", injected)),
+        ("url", "name=Jane&url=https://example.com/a b".to_string()),
+        (
+            "email",
+            format!("name=Jane&email=jane@example.com%0D%0A{}", injected),
+        ),
+        ("email", "name=Jane&email=jane@example.com;TYPE=work".into()),
+        ("email", "name=Jane&email=ja ne@example.com".into()),
+        ("phone", format!("name=Jane&phone=123%0A{}", injected)),
+        ("org", "name=Jane&org=Acme%00Inc".into()),
+        ("name", "name=Jane%0BDoe".into()),
+    ];
+    for (field, query) in &cases {
+        assert_eq!(refused("contact", query), *field, "{}", query);
+    }
+
+    // Line breaks in text are escaped, never written out.
+    let vcard = build("contact", &format!("name=Jane&address={}", injected));
+    assert_eq!(
+        vcard
+            .split("\r\n")
+            .filter(|l| l.starts_with("BEGIN:"))
+            .count(),
+        1
+    );
+    assert!(vcard.contains("ADR:;;END:VCARD\\nBEGIN:VCARD\\nFN:Mallory;;;;"));
+}
+
+#[test]
+fn event_dates_parse() {
+    let date = |s: &str| s.parse::<DateTime>().map(|d| d.to_string());
+    assert_eq!(date("2024-05-04").unwrap(), "20240504");
+    assert_eq!(date("20240504").unwrap(), "20240504");
+    assert_eq!(date("2024-05-04T09:30").unwrap(), "20240504T093000");
+    assert_eq!(date("2024-05-04 09:30:15").unwrap(), "20240504T093015");
+    assert_eq!(date("20240504T093000Z").unwrap(), "20240504T093000Z");
+    assert_eq!(date("2024-02-29").unwrap(), "20240229");
+    assert_eq!(date("2000-02-29").unwrap(), "20000229");
+
+    for s in &[
+        "",
+        "2024-5-4",
+        "2024-05-04Z",
+        "2024-05-04T9:30",
+        "2024-05-04T09:30:1",
+        "2024-05-04T24:00",
+        "2024-05-04T09:60",
+        "2024-13-01",
+        "2024-04-31",
+        "2023-02-29",
+        "1900-02-29",
+        "2024-05-04T09:30+02",
+    ] {
+        assert!(date(s).is_err(), "{}", s);
+    }
+}
+
+#[test]
+fn event_all_day_end_is_exclusive() {
+    let end = |start: &str, end: &str| {
+        let event = build(
+            "event",
+            &format!("summary=Fair&start={}&end={}", start, end),
+        );
+        let line = event.split("\r\n").find(|l| l.starts_with("DTEND"));
+        line.unwrap().to_string()
+    };
+    assert_eq!(end("2024-05-04", "2024-05-04"), "DTEND;VALUE=DATE:20240505");
+    assert_eq!(end("2024-02-27", "2024-02-28"), "DTEND;VALUE=DATE:20240229");
+    assert_eq!(end("2023-02-27", "2023-02-28"), "DTEND;VALUE=DATE:20230301");
+    assert_eq!(end("2024-12-30", "2024-12-31"), "DTEND;VALUE=DATE:20250101");
+    assert_eq!(
+        end("2024-05-04T09:00", "2024-05-04T17:30"),
+        "DTEND:20240504T173000"
+    );
+
+    assert_eq!(
+        build(
+            "event",
+            "summary=Launch; party, all night&start=2026-05-04T18:00\
+             &end=2026-05-05T02:00&tz=Europe/Berlin&location=Hall 1\
+             &rrule=RRULE:freq=weekly;count=4"
+        ),
+        "BEGIN:VEVENT\r\n\
+         SUMMARY:Launch\\; party\\, all night\r\n\
+         DTSTART;TZID=Europe/Berlin:20260504T180000\r\n\
+         DTEND;TZID=Europe/Berlin:20260505T020000\r\n\
+         LOCATION:Hall 1\r\n\
+         RRULE:FREQ=WEEKLY;COUNT=4\r\n\
+         END:VEVENT"
+    );
+}
+
+#[test]
+fn event_refuses_invalid_fields() {
+    let cases = [
+        ("summary", "start=2024-05-04"),
+        ("start", "summary=S"),
+        ("start", "summary=S&start=May 4"),
+        ("end", "summary=S&start=2024-05-04&end=2024-05-03"),
+        ("end", "summary=S&start=2024-05-04T10:00&end=2024-05-04T10:00"),
+        ("end", "summary=S&start=2024-05-04T10:00&end=2024-05-05"),
+        ("end", "summary=S&start=2024-05-04T10:00Z&end=2024-05-04T11:00"),
+        // Time zones go with local times only.
+        ("tz", "summary=S&start=2024-05-04&tz=Europe/Berlin"),
+        ("tz", "summary=S&start=2024-05-04T10:00Z&tz=Europe/Berlin"),
+        ("tz", "summary=S&start=2024-05-04T10:00&tz=Europe/Berlin:x"),
+        ("tz", "summary=S&start=2024-05-04T10:00&tz=Europe Berlin"),
+        ("rrule", "summary=S&start=2024-05-04&rrule=COUNT=4"),
+        ("rrule", "summary=S&start=2024-05-04&rrule=FREQ=HOURLY;FREQ=DAILY"),
+        ("rrule", "summary=S&start=2024-05-04&rrule=FREQ=DAILY;X-NAME=1"),
+        ("rrule", "summary=S&start=2024-05-04&rrule=FREQ=DAILY;COUNT=0"),
+        (
+            "rrule",
+            "summary=S&start=2024-05-04&rrule=FREQ=DAILY;COUNT=2;UNTIL=20240601",
+        ),
+    ];
+    for (field, query) in &cases {
+        assert_eq!(refused("event", query), *field, "{}", query);
+    }
+}
+
+#[test]
+fn event_refuses_rrule_injection() {
+    for rule in &[
+        "FREQ=DAILY%0D%0AEND:VEVENT%0D%0ABEGIN:VEVENT%0D%0ASUMMARY:Spam",
+        "FREQ=DAILY;BYDAY=MO%0D%0AATTACH:https://example.com/x",
+        "FREQ=DAILY;UNTIL=20240601%0AATTACH:https://example.com/x",
+        "FREQ=DAILY;BYDAY=MO:TU",
+        "FREQ=DAILY;BYDAY=MO TU",
+        "FREQ=DAILY;BYDAY=",
+    ] {
+        let query = format!("summary=S&start=2024-05-04&rrule={}", rule);
+        assert_eq!(refused("event", &query), "rrule", "{}", rule);
+    }
+
+    let event = build(
+        "event",
+        "summary=S&start=2024-05-04&rrule=FREQ=MONTHLY;BYDAY=-1SU,%2B2MO\
+         ;BYMONTH=1,7;UNTIL=20251231T000000Z;WKST=MO",
+    );
+    assert!(event.replace("\r\n ", "").contains(
+        "\r\nRRULE:FREQ=MONTHLY;BYDAY=-1SU,+2MO;BYMONTH=1,7;\
+         UNTIL=20251231T000000Z;WKST=MO\r\n"
+    ));
+}
+
+const QR_BILL: &str = "iban=CH44 3199 9123 0008 8901 2\
+    &name=Robert Schneider AG&street=Rue du Lac&building=1268\
+    &postcode=2501&town=Biel&country=CH&amount=199.95&currency=CHF\
+    &reference=21 00000 00003 13947 14300 09017";
+
+#[test]
+fn iban_checks_mod97() {
+    for iban in &[
+        "DE89 3704 0044 0532 0130 00",
+        "gb82west12345698765432",
+        "CH93 0076 2011 6238 5295 7",
+    ] {
+        let parsed: Iban = iban.parse().unwrap();
+        assert_eq!(parsed.to_string(), iban.replace(' ', "").to_uppercase());
+    }
+
+    for iban in &[
+        "DE88 3704 0044 0532 0130 00",
+        "DE89 3704 0044 0532 0130 0",
+        "US89 3704 0044 0532 0130 00",
+        "DE89-3704-0044-0532-0130-00",
+    ] {
+        assert!(iban.parse::<Iban>().is_err(), "{}", iban);
+    }
+}
+
+#[test]
+fn sepa_normalizes_creditor_references() {
+    assert_eq!(
+        build(
+            "sepa",
+            "name=Red Cross&iban=DE89 3704 0044 0532 0130 00\
+             &bic=COBADEFFXXX&amount=12.5&reference=rf18 5390 0754 7034",
+        ),
+        "BCD\n002\n1\nSCT\nCOBADEFFXXX\nRed Cross\nDE89370400440532013000\n\
+         EUR12.50\n\nRF18539007547034"
+    );
+
+    let cases = [
+        ("iban", "name=X&iban=DE88370400440532013000"),
+        (
+            "reference",
+            "name=X&iban=DE89370400440532013000&reference=INV-42",
+        ),
+        (
+            "reference",
+            "name=X&iban=DE89370400440532013000&reference=RF19539007547034",
+        ),
+    ];
+    for (field, query) in &cases {
+        assert_eq!(refused("sepa", query), *field, "{}", query);
+    }
+}
+
+#[test]
+fn qr_bill_checks_references() {
+    assert_eq!(
+        build("qrbill", QR_BILL),
+        "SPC\n0200\n1\nCH4431999123000889012\nS\nRobert Schneider AG\n\
+         Rue du Lac\n1268\n2501\nBiel\nCH\n\n\n\n\n\n\n\n199.95\nCHF\n\
+         \n\n\n\n\n\n\nQRR\n210000000003139471430009017\n\nEPD"
+    );
+
+    let address = "name=R&postcode=2501&town=Biel&country=CH";
+    let cases = [
+        // QR references end in a recursive modulo 10 check digit.
+        "iban=CH4431999123000889012&reference=210000000003139471430009018",
+        // QR-IBANs take QR references only.
+        "iban=CH4431999123000889012&reference=RF18539007547034",
+        "iban=CH4431999123000889012",
+        "iban=CH9300762011623852957&reference=RF19539007547034",
+        "iban=CH9300762011623852957&reference=210000000003139471430009017",
+    ];
+    for query in &cases {
+        let query = format!("{}&{}", query, address);
+        assert_eq!(refused("qrbill", &query), "reference", "{}", query);
+    }
+}
+
+#[test]
+fn qr_bill_cross_is_7_of_46_of_the_code() {
+    let payload =
+        build_payload("qrbill", &Fields::from_query(QR_BILL)).unwrap();
+    let payload = payload.unwrap();
+    let mut gen = Generator {
+        format: Format::Png,
+        module_size: Some(10),
+        margin: Some(0),
+        ..Default::default()
+    };
+    payload.configure(&mut gen);
+
+    let png = gen.generate(payload.data.as_bytes()).unwrap();
+    let image = image::load_from_memory(&png).unwrap().to_rgba8();
+    let (size, _) = image.dimensions();
+
+    // From the middle of the white arm, past the black square, to the
+    // blank padding around it.
+    let center = size / 2;
+    let dark = |x: u32| image.get_pixel(x, center).0[0] < 128;
+    let start = (center..size).find(|&x| dark(x)).unwrap();
+    let edge = (start..size).find(|&x| !dark(x)).unwrap();
+
+    let cross = 2.0 * (edge - center) as f64;
+    let expected = size as f64 * 7.0 / 46.0;
+    assert!((cross - expected).abs() <= 10.0, "{} {}", cross, expected);
+}
+
+#[test]
+fn bitcoin_checks_bech32_and_base58check() {
+    let cases = [
+        // BIP 173, in upper case to fit the alphanumeric mode.
+        (
+            "address=bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            "BITCOIN:BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4",
+        ),
+        // BIP 350.
+        (
+            "address=bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+            "BITCOIN:BC1P0XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQZK5JJ0",
+        ),
+        (
+            "address=BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4\
+             &amount=0.0005&label=Luke Jr",
+            "bitcoin:bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4\
+             ?amount=0.0005&label=Luke%20Jr",
+        ),
+        (
+            "address=1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2&amount=1",
+            "bitcoin:1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2?amount=1",
+        ),
+        (
+            "address=3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy",
+            "bitcoin:3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy",
+        ),
+    ];
+    for (query, uri) in &cases {
+        assert_eq!(build("bitcoin", query), *uri);
+    }
+
+    for address in &[
+        // Version 0 with the bech32m checksum, version 1 with bech32.
+        "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kemeawh",
+        "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zs2a8zr3",
+        "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3T4",
+        "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN3",
+        "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN0",
+    ] {
+        let query = format!("address={}", address);
+        assert_eq!(refused("bitcoin", &query), "address", "{}", address);
+    }
+}
+
+#[test]
+fn otp_normalizes_base32_secrets() {
+    let cases = [
+        // `Hello!` and 0xdeadbeef, in lower case and grouped.
+        (
+            "issuer=ACME Co&account=jane@example.com\
+             &secret=jbsw y3dp ehpk 3pxp",
+            "otpauth://totp/ACME%20Co:jane@example.com\
+             ?secret=JBSWY3DPEHPK3PXP&issuer=ACME%20Co",
+        ),
+        (
+            "type=hotp&account=jane&secret=JBSWY3DPEHPK3PXP====&counter=7\
+             &digits=8&algorithm=sha256",
+            "otpauth://hotp/jane?secret=JBSWY3DPEHPK3PXP&algorithm=SHA256\
+             &digits=8&counter=7",
+        ),
+        // The secret of the RFC 4226 test vectors.
+        (
+            "account=jane&secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&period=60",
+            "otpauth://totp/jane?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ\
+             &period=60",
+        ),
+    ];
+    for (query, uri) in &cases {
+        assert_eq!(build("otp", query), *uri);
+    }
+}
+
+#[test]
+fn otp_refuses_invalid_fields() {
+    let cases = [
+        ("secret", "account=jane&secret=JBSWY3DPEHPK3PX1"),
+        ("secret", "account=jane&secret=JBSWY3DPEHPK3PXPA"),
+        ("secret", "account=jane&secret=JBSWY3DP"),
+        ("issuer", "issuer=A:B&account=jane&secret=JBSWY3DPEHPK3PXP"),
+        ("digits", "account=jane&secret=JBSWY3DPEHPK3PXP&digits=9"),
+        ("counter", "type=hotp&account=jane&secret=JBSWY3DPEHPK3PXP"),
+        ("counter", "account=jane&secret=JBSWY3DPEHPK3PXP&counter=1"),
+    ];
+    for (field, query) in &cases {
+        assert_eq!(refused("otp", query), *field, "{}", query);
+    }
+}
+
+#[test]
+fn phone_numbers_become_e164() {
+    let cases = [
+        ("tel", "number=%2B49 (30) 1234-567", "tel:+49301234567"),
+        ("tel", "number=0049 30 1234567", "tel:+49301234567"),
+        ("mms", "number=%2B1 555.0100", "MMSTO:+15550100"),
+        // Short codes are kept.
+        (
+            "sms",
+            "number=12345&message=Hi:%0D%0Athere",
+            "SMSTO:12345:Hi:\nthere",
+        ),
+    ];
+    for (kind, query, payload) in &cases {
+        assert_eq!(build(kind, query), *payload);
+    }
+
+    for query in &[
+        "number=030 1234567",
+        "number=%2B0301234567",
+        "number=%2B12",
+        "number=%2B1234567890123456",
+        "number=%2B49 30 CALL",
+    ] {
+        assert_eq!(refused("tel", query), "number", "{}", query);
+    }
+    assert_eq!(refused("sms", "number=0123"), "number");
+    assert_eq!(refused("sms", "number=12345&message=a%07"), "message");
+}
+
+#[test]
+fn mailto_percent_encodes_text() {
+    assert_eq!(
+        build(
+            "mailto",
+            "to=jane%2Bnews@example.com&to=bob@example.com\
+             &cc=carol@example.com&subject=Q3 report %26 ideas?\
+             &body=Hi,%0Asee 50%25 / 100#",
+        ),
+        "mailto:jane%2Bnews@example.com,bob@example.com\
+         ?cc=carol@example.com&subject=Q3%20report%20%26%20ideas%3F\
+         &body=Hi%2C%0D%0Asee%2050%25%20%2F%20100%23"
+    );
+
+    let cases = [
+        ("to", "subject=Hi"),
+        ("to", "to=jane"),
+        ("cc", "to=jane@example.com&cc=bob@"),
+        ("subject", "to=jane@example.com&subject=a%0Ab"),
+    ];
+    for (field, query) in &cases {
+        assert_eq!(refused("mailto", query), *field, "{}", query);
+    }
+}
@@ -0,0 +1,109 @@
+use std::convert::TryInto;
+
+use libs::Format;
+use libs::Frame;
+use libs::Generator;
+use libs::PhysicalSize;
+
+const INPUT: &[u8] = b"https://qrcode.show";
+
+/// The pixels per meter of the pHYs chunk, along both axes, and its unit.
+fn png_phys(png: &[u8]) -> (u32, u32, u8) {
+    let at = png.windows(4).position(|w| w == b"pHYs").unwrap() + 4;
+    let int = |i: usize| u32::from_be_bytes(png[i..i + 4].try_into().unwrap());
+    (int(at), int(at + 4), png[at + 8])
+}
+
+/// The units and the densities of the JFIF header.
+fn jpeg_density(jpeg: &[u8]) -> (u8, u16, u16) {
+    let at = jpeg.windows(5).position(|w| w == b"JFIF\0").unwrap() + 7;
+    let int = |i: usize| u16::from_be_bytes([jpeg[i], jpeg[i + 1]]);
+    (jpeg[at], int(at + 1), int(at + 3))
+}
+
+#[test]
+fn png_is_exactly_the_physical_size() {
+    // 30mm at 300 DPI is 354.33 pixels.
+    let gen = Generator {
+        format: Format::Png,
+        physical_size: Some("30mm".parse::<PhysicalSize>().unwrap()),
+        dpi: Some(300),
+        ..Default::default()
+    };
+    let png = gen.generate(INPUT).unwrap();
+    let image = image::load_from_memory(&png).unwrap().to_rgba8();
+    assert_eq!(image.dimensions(), (354, 354));
+
+    // 300 DPI is 11811 pixels per meter.
+    assert_eq!(png_phys(&png), (11811, 11811, 1));
+}
+
+#[test]
+fn png_modules_stay_whole() {
+    let gen = Generator {
+        format: Format::Png,
+        physical_size: Some("30mm".parse::<PhysicalSize>().unwrap()),
+        dpi: Some(300),
+        ..Default::default()
+    };
+    let png = gen.generate(INPUT).unwrap();
+    let image = image::load_from_memory(&png).unwrap().to_rgba8();
+
+    // 33 modules with the quiet zone, of 10 pixels each, centered.
+    let code = Generator::default().code(INPUT).unwrap();
+    let modules = code.width() as u32 + 8;
+    assert_eq!(modules, 33);
+    let offset = (354 - modules * 10) / 2;
+
+    let colors = code.to_colors();
+    for y in 0..code.width() {
+        for x in 0..code.width() {
+            let center = |i: usize| offset + (4 + i as u32) * 10 + 5;
+            let dark = image.get_pixel(center(x), center(y)).0[0] < 128;
+            assert_eq!(
+                dark,
+                colors[y * code.width() + x] == qrcode::Color::Dark
+            );
+        }
+    }
+    assert_eq!(image.get_pixel(0, 0).0, [255, 255, 255, 255]);
+}
+
+#[test]
+fn jpeg_is_exactly_the_physical_size() {
+    let gen = Generator {
+        format: Format::Jpeg,
+        physical_size: Some("1.5in".parse::<PhysicalSize>().unwrap()),
+        dpi: Some(200),
+        ..Default::default()
+    };
+    let jpeg = gen.generate(INPUT).unwrap();
+    let image = image::load_from_memory(&jpeg).unwrap();
+    assert_eq!(image.to_rgba8().dimensions(), (300, 300));
+
+    // Unit 1: dots per inch.
+    assert_eq!(jpeg_density(&jpeg), (1, 200, 200));
+}
+
+#[test]
+fn framed_and_tiny_codes_fit_the_physical_size() {
+    let gen = Generator {
+        format: Format::Png,
+        physical_size: Some("4cm".parse::<PhysicalSize>().unwrap()),
+        dpi: Some(300),
+        frame: Some("banner".parse::<Frame>().unwrap()),
+        ..Default::default()
+    };
+    let image = image::load_from_memory(&gen.generate(INPUT).unwrap()).unwrap();
+    assert_eq!(image.to_rgba8().dimensions(), (472, 472));
+
+    // Fewer pixels than modules: scaled down rather than left larger.
+    let gen = Generator {
+        format: Format::Png,
+        physical_size: Some("5mm".parse::<PhysicalSize>().unwrap()),
+        dpi: Some(72),
+        ..Default::default()
+    };
+    let image = image::load_from_memory(&gen.generate(INPUT).unwrap()).unwrap();
+    assert_eq!(image.to_rgba8().dimensions(), (14, 14));
+}
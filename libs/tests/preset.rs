@@ -0,0 +1,40 @@
+use libs::Presets;
+
+#[test]
+fn request_headers_override_the_style() {
+    let presets: Presets = "[brand]\n\
+                            dark-color = navy\n\
+                            light-color = #fffbe6\n\
+                            margin = 2\n"
+        .parse()
+        .unwrap();
+    let preset = presets.find("Brand").unwrap();
+
+    // The request sets its own dark color and margin, the style fills in
+    // the light color.
+    let sent = ["x-qr-dark-color", "x-qr-margin"];
+    assert_eq!(
+        preset.unset(|key| sent.contains(&key)),
+        [("x-qr-light-color", "#fffbe6")]
+    );
+
+    // Without headers of its own, the request takes the whole style.
+    assert_eq!(
+        preset.unset(|_| false),
+        [
+            ("x-qr-dark-color", "navy"),
+            ("x-qr-light-color", "#fffbe6"),
+            ("x-qr-margin", "2"),
+        ]
+    );
+}
+
+#[test]
+fn default_styles_parse() {
+    let presets = Presets::default();
+    assert_eq!(
+        presets.find("dots").unwrap().get("x-qr-shape"),
+        Some("circle")
+    );
+    assert!(presets.find("nope").is_err());
+}
@@ -0,0 +1,128 @@
+use libs::EyeShape;
+use libs::Format;
+use libs::Generator;
+use libs::Kind;
+use libs::Matrix;
+use libs::Shape;
+
+mod common;
+
+const INPUT: &[u8] = b"https://qrcode.show/?utm_source=poster&id=4242";
+
+/// A raster code of `format` with modules drawn as `shape`.
+fn shaped(format: Format, shape: Shape) -> Generator {
+    Generator {
+        format,
+        module_size: Some(9),
+        shape: Some(shape),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn shaped_modules_decode() {
+    let shapes = [
+        Shape::Square,
+        Shape::Rounded,
+        Shape::Circle,
+        Shape::Diamond,
+        Shape::Liquid,
+    ];
+    for format in [Format::Png, Format::Jpeg] {
+        for shape in shapes {
+            let gen = shaped(format, shape);
+            let bytes = gen.generate(INPUT).unwrap();
+            let code = gen.code(INPUT).unwrap();
+            let modules = common::sample(&bytes, &gen, INPUT);
+            assert_eq!(
+                common::decode(&modules, code.width()),
+                INPUT,
+                "{:?} {:?}",
+                format,
+                shape
+            );
+        }
+    }
+}
+
+#[test]
+fn shaped_modules_leave_their_corners_light() {
+    // The luma at the corner and at the center of a lone dark module.
+    let corner = |shape| {
+        let gen = shaped(Format::Png, shape);
+        let png = gen.generate(INPUT).unwrap();
+        let image = image::load_from_memory(&png).unwrap().to_rgba8();
+        let code = gen.code(INPUT).unwrap();
+
+        // The first dark module away from the eyes, without dark
+        // neighbors.
+        let colors = code.to_colors();
+        let width = code.width();
+        let dark =
+            |x: usize, y: usize| colors[y * width + x] == qrcode::Color::Dark;
+        let (x, y) = (9..width - 9)
+            .flat_map(|y| (9..width - 9).map(move |x| (x, y)))
+            .find(|&(x, y)| {
+                dark(x, y)
+                    && !dark(x - 1, y)
+                    && !dark(x + 1, y)
+                    && !dark(x, y - 1)
+                    && !dark(x, y + 1)
+            })
+            .unwrap();
+        let at = |m: usize| (m as u32 + gen.margin(&code)) * 9;
+        (
+            common::luma(image.get_pixel(at(x), at(y))),
+            common::luma(image.get_pixel(at(x) + 4, at(y) + 4)),
+        )
+    };
+
+    assert_eq!(corner(Shape::Square), (0.0, 0.0));
+    for shape in [Shape::Rounded, Shape::Circle, Shape::Diamond, Shape::Liquid]
+    {
+        let (corner, center) = corner(shape);
+        assert!(corner > 200.0, "{:?}: {}", shape, corner);
+        assert_eq!(center, 0.0, "{:?}", shape);
+    }
+}
+
+#[test]
+fn eye_styles_change_only_the_finder_patterns() {
+    let plain = shaped(Format::Png, Shape::Square);
+    let code = plain.code(INPUT).unwrap();
+    let matrix = Matrix::new(&code, plain.margin(&code) as usize);
+    let render = |gen: &Generator| {
+        let png = gen.generate(INPUT).unwrap();
+        image::load_from_memory(&png).unwrap().to_rgba8()
+    };
+    let before = render(&plain);
+
+    let styles = [
+        Generator {
+            eye_shape: Some(EyeShape::Circle),
+            ..plain.clone()
+        },
+        Generator {
+            eye_dot_shape: Some(EyeShape::Diamond),
+            ..plain.clone()
+        },
+        Generator {
+            eye_color: Some("darkred".parse().unwrap()),
+            ..plain.clone()
+        },
+    ];
+    for gen in &styles {
+        let after = render(gen);
+        assert_eq!(before.dimensions(), after.dimensions());
+
+        let mut changed = 0;
+        for (x, y, px) in after.enumerate_pixels() {
+            if *px != *before.get_pixel(x, y) {
+                let (x, y) = (x as usize / 9, y as usize / 9);
+                assert_eq!(matrix.kind(x, y), Kind::Finder, "({}, {})", x, y);
+                changed += 1;
+            }
+        }
+        assert!(changed > 0);
+    }
+}
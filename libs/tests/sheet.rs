@@ -0,0 +1,141 @@
+use libs::Sheet;
+use libs::SheetError;
+use libs::Spacing;
+
+const IN: f64 = 72.0;
+const MM: f64 = IN / 25.4;
+
+fn assert_close((x, y): (f64, f64), (ex, ey): (f64, f64)) {
+    assert!(
+        (x - ex).abs() < 1e-6 && (y - ey).abs() < 1e-6,
+        "({}, {}) is not ({}, {})",
+        x,
+        y,
+        ex,
+        ey
+    );
+}
+
+/// The labels are centered on the page, so the margins at the bottom and
+/// on the right match those at the top and on the left.
+fn assert_centered(sheet: &Sheet) {
+    let (x, y) = sheet.label(sheet.labels() - 1);
+    assert_close(
+        (
+            sheet.width - x - sheet.label_width,
+            sheet.height - y - sheet.label_height,
+        ),
+        (sheet.left, sheet.top),
+    );
+}
+
+#[test]
+fn avery_5160() {
+    for name in &["5160", "avery-5160", "Avery 5160"] {
+        let sheet: Sheet = name.parse().unwrap();
+        assert_eq!((sheet.columns, sheet.rows), (3, 10));
+        assert_close((sheet.width, sheet.height), (8.5 * IN, 11.0 * IN));
+        assert_close(
+            (sheet.label_width, sheet.label_height),
+            (2.625 * IN, 1.0 * IN),
+        );
+
+        // Half an inch from the top, 3/16 inch from the side, with 1/8
+        // inch between the columns and none between the rows.
+        assert_close(sheet.label(0), (13.5, 36.0));
+        assert_close(sheet.label(1), (211.5, 36.0));
+        assert_close(sheet.label(2), (409.5, 36.0));
+        assert_close(sheet.label(3), (13.5, 108.0));
+        assert_close(sheet.label(29), (409.5, 684.0));
+        assert_centered(&sheet);
+    }
+}
+
+#[test]
+fn avery_l7160() {
+    let sheet: Sheet = "L7160".parse().unwrap();
+    assert_eq!((sheet.columns, sheet.rows), (3, 7));
+    assert_close((sheet.width, sheet.height), (210.0 * MM, 297.0 * MM));
+    assert_close(
+        (sheet.label_width, sheet.label_height),
+        (63.5 * MM, 38.1 * MM),
+    );
+
+    assert_close(sheet.label(0), (7.21 * MM, 15.15 * MM));
+    assert_close(sheet.label(2), ((7.21 + 2.0 * 66.04) * MM, 15.15 * MM));
+    assert_close(sheet.label(4), ((7.21 + 66.04) * MM, (15.15 + 38.1) * MM));
+    assert_close(
+        sheet.label(20),
+        ((7.21 + 2.0 * 66.04) * MM, (15.15 + 6.0 * 38.1) * MM),
+    );
+    assert_centered(&sheet);
+}
+
+#[test]
+fn custom_grid_with_margins_and_gutters() {
+    let sheet: Sheet = "a4:3x8".parse().unwrap();
+    assert_close(
+        (sheet.label_width, sheet.label_height),
+        (70.0 * MM, 297.0 / 8.0 * MM),
+    );
+    assert_close(sheet.label(0), (0.0, 0.0));
+
+    let margin: Spacing = "10mm 5mm".parse().unwrap();
+    let gutter: Spacing = "2mm 3mm".parse().unwrap();
+    let sheet = sheet.space(Some(margin), Some(gutter)).unwrap();
+
+    // 210mm less the margins and two gutters across, 297mm less the
+    // margins and seven gutters down.
+    let (width, height) = (194.0 / 3.0, 263.0 / 8.0);
+    assert_close(
+        (sheet.label_width, sheet.label_height),
+        (width * MM, height * MM),
+    );
+    assert_close(sheet.label(0), (5.0 * MM, 10.0 * MM));
+    assert_close(
+        sheet.label(4),
+        ((5.0 + width + 3.0) * MM, (10.0 + height + 2.0) * MM),
+    );
+    assert_close(
+        sheet.label(23),
+        (
+            (5.0 + 2.0 * (width + 3.0)) * MM,
+            (10.0 + 7.0 * (height + 2.0)) * MM,
+        ),
+    );
+    assert_centered(&sheet);
+
+    // Only the gutters, keeping the margins.
+    let gutter: Spacing = "1in".parse().unwrap();
+    let sheet = sheet.space(None, Some(gutter)).unwrap();
+    assert_close((sheet.left, sheet.top), (5.0 * MM, 10.0 * MM));
+    assert_close((sheet.column_gap, sheet.row_gap), (IN, IN));
+}
+
+#[test]
+fn sheets_refuse_what_does_not_fit() {
+    for name in &["", "5159", "a4", "a4:0x8", "a4:3x51", "a6:3x8", "a4:3by8"] {
+        assert!(
+            matches!(name.parse::<Sheet>(), Err(SheetError::Sheet(_))),
+            "{}",
+            name
+        );
+    }
+    for spacing in &["", "10", "-1mm", "1mm 2mm 3mm", "5px"] {
+        assert!(spacing.parse::<Spacing>().is_err(), "{}", spacing);
+    }
+
+    let margin: Spacing = "1mm".parse().unwrap();
+    let sheet: Sheet = "5160".parse().unwrap();
+    assert!(matches!(
+        sheet.space(Some(margin), None),
+        Err(SheetError::Fixed)
+    ));
+
+    let margin: Spacing = "150mm 10mm".parse().unwrap();
+    let sheet: Sheet = "a4:3x8".parse().unwrap();
+    assert!(matches!(
+        sheet.space(Some(margin), None),
+        Err(SheetError::NoRoom)
+    ));
+}
@@ -0,0 +1,99 @@
+use libs::Format;
+use libs::GenerateError;
+use libs::Generator;
+use libs::Shape;
+use libs::MAX_MARGIN;
+use libs::MAX_MODULE_SIZE;
+
+/// Fills a version 40 code, 177 modules across.
+const INPUT: &[u8] = &[b'x'; 2300];
+
+fn too_large(gen: &Generator) -> (u64, u64) {
+    match gen.generate(INPUT) {
+        Err(GenerateError::TooLarge { width, height }) => (width, height),
+        Err(e) => panic!("{}", e),
+        Ok(_) => panic!("{:?} was not refused", gen.format),
+    }
+}
+
+#[test]
+fn largest_modules_and_margin_are_refused() {
+    for format in [Format::Png, Format::Jpeg, Format::PlainText] {
+        let gen = Generator {
+            format,
+            module_size: Some(MAX_MODULE_SIZE),
+            margin: Some(MAX_MARGIN),
+            ..Default::default()
+        };
+        let (width, height) = too_large(&gen);
+        assert_eq!(height, 377 * 100);
+        assert!(width >= height);
+    }
+
+    // SVG isn't drawn, so it stays as large as it likes.
+    let gen = Generator {
+        format: Format::Svg,
+        module_size: Some(MAX_MODULE_SIZE),
+        margin: Some(MAX_MARGIN),
+        ..Default::default()
+    };
+    assert!(gen.generate(INPUT).is_ok());
+}
+
+#[test]
+fn large_dimensions_are_refused() {
+    let cases = [
+        Generator {
+            width: Some(u32::MAX),
+            ..Default::default()
+        },
+        Generator {
+            min_height: Some(100_000),
+            ..Default::default()
+        },
+        Generator {
+            physical_size: Some("200cm".parse().unwrap()),
+            dpi: Some(1200),
+            ..Default::default()
+        },
+        Generator {
+            shape: Some(Shape::Circle),
+            module_size: Some(30),
+            ..Default::default()
+        },
+    ];
+    for gen in &cases {
+        let gen = Generator {
+            format: Format::Png,
+            ..gen.clone()
+        };
+        too_large(&gen);
+        let code = gen.code(INPUT).unwrap();
+        assert!(gen.image(&code).is_err());
+    }
+}
+
+#[test]
+fn the_limit_leaves_room_for_large_prints() {
+    // A version 40 code at 23 pixels a module, 4255 pixels across with
+    // the quiet zone, is refused, at 22 pixels a module it is not.
+    for (module_size, fits) in [(22, true), (23, false)] {
+        let gen = Generator {
+            format: Format::Png,
+            module_size: Some(module_size),
+            ..Default::default()
+        };
+        let code = gen.code(INPUT).unwrap();
+        assert_eq!(gen.check_size(&code).is_ok(), fits, "{}", module_size);
+    }
+
+    let gen = Generator {
+        format: Format::Png,
+        physical_size: Some("5in".parse().unwrap()),
+        dpi: Some(300),
+        ..Default::default()
+    };
+    let png = gen.generate(b"https://qrcode.show").unwrap();
+    let image = image::load_from_memory(&png).unwrap().to_rgba8();
+    assert_eq!(image.dimensions(), (1500, 1500));
+}
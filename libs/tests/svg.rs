@@ -0,0 +1,457 @@
+use libs::Color;
+use libs::Format;
+use libs::Frame;
+use libs::Generator;
+use libs::QrCode;
+use libs::Rotation;
+use libs::Sheet;
+use qrcode::render::svg;
+
+const INPUTS: &[&[u8]] = &[
+    b"1",
+    b"https://qrcode.show",
+    b"HELLO WORLD 0123456789",
+    &[b'x'; 2300],
+];
+
+/// The value of the first `name` attribute after `from` in `svg`.
+fn attribute<'a>(svg: &'a str, from: &str, name: &str) -> Option<&'a str> {
+    let start = svg.find(from)?;
+    let tag = &svg[start..start + svg[start..].find('>')?];
+    let key = format!(" {}=\"", name);
+    let value = &tag[tag.find(&key)? + key.len()..];
+    Some(&value[..value.find('"')?])
+}
+
+/// The vertical edges of each outline of path data of `M`, `h`, `v` and
+/// `z` commands, as `(x, from y, to y)`.
+fn outlines(d: &str) -> Vec<Vec<(i64, i64, i64)>> {
+    let mut outlines: Vec<Vec<_>> = vec![];
+    let (mut x, mut y, mut start) = (0, 0, (0, 0));
+    let mut rest = d;
+    while let Some(command) = rest.chars().next() {
+        rest = &rest[1..];
+        let end = rest.find(char::is_alphabetic).unwrap_or(rest.len());
+        let numbers: Vec<i64> = rest[..end]
+            .split(' ')
+            .filter(|n| !n.is_empty())
+            .map(|n| n.parse().unwrap())
+            .collect();
+        rest = &rest[end..];
+
+        match (command, &numbers[..]) {
+            ('M', [mx, my]) => {
+                x = *mx;
+                y = *my;
+                start = (x, y);
+                outlines.push(vec![]);
+            }
+            ('h', [dx]) => x += dx,
+            ('v', [dy]) => {
+                outlines.last_mut().unwrap().push((x, y, y + dy));
+                y += dy;
+            }
+            // The closing side, back to the start.
+            ('z', []) => {
+                assert!(x == start.0 || y == start.1);
+                if y != start.1 {
+                    outlines.last_mut().unwrap().push((x, y, start.1));
+                }
+                x = start.0;
+                y = start.1;
+            }
+            _ => panic!("unexpected {} {:?}", command, numbers),
+        }
+    }
+    outlines
+}
+
+/// The area an outline encloses, positive if it runs clockwise.
+fn area(edges: &[(i64, i64, i64)]) -> i64 {
+    edges.iter().map(|(x, y0, y1)| x * (y1 - y0)).sum()
+}
+
+/// The number of areas of modules of `dark`, dark ones joined along their
+/// sides and light ones also at their corners, leaving out the light ones
+/// reaching the edge.
+fn areas(modules: &[bool], width: usize, dark: bool) -> usize {
+    let mut seen = vec![false; modules.len()];
+    let mut count = 0;
+    for i in 0..modules.len() {
+        if seen[i] || modules[i] != dark {
+            continue;
+        }
+        let (mut stack, mut edge) = (vec![i], false);
+        seen[i] = true;
+        while let Some(i) = stack.pop() {
+            let (x, y) = (i % width, i / width);
+            edge |= x == 0 || y == 0 || x == width - 1 || y == width - 1;
+            let (left, right) = (x > 0, x < width - 1);
+            let (up, down) = (y > 0, y < width - 1);
+            let neighbours = [
+                (left, 0, 1, true),
+                (right, 2, 1, true),
+                (up, 1, 0, true),
+                (down, 1, 2, true),
+                (left && up, 0, 0, !dark),
+                (right && up, 2, 0, !dark),
+                (left && down, 0, 2, !dark),
+                (right && down, 2, 2, !dark),
+            ];
+            for &(inside, dx, dy, joined) in &neighbours {
+                let j = (i + dx + dy * width).wrapping_sub(1 + width);
+                if inside && joined && !seen[j] && modules[j] == dark {
+                    seen[j] = true;
+                    stack.push(j);
+                }
+            }
+        }
+        if dark || !edge {
+            count += 1;
+        }
+    }
+    count
+}
+
+/// The modules of a `width` wide grid whose middle the path covers under
+/// the nonzero fill rule, counting the edges a ray to the right crosses.
+fn fill(d: &str, width: usize) -> Vec<bool> {
+    let edges: Vec<_> = outlines(d).concat();
+    let mut filled = vec![false; width * width];
+    for y in 0..width {
+        // The winding the edges at each x add, right of the middles left
+        // of them.
+        let mut crossing = vec![0; width + 1];
+        for &(x, y0, y1) in &edges {
+            if y0.min(y1) <= y as i64 && (y as i64) < y0.max(y1) {
+                crossing[x as usize] += if y1 > y0 { 1 } else { -1 };
+            }
+        }
+        let mut winding = 0;
+        for x in (0..width).rev() {
+            winding += crossing[x + 1];
+            assert!(winding == 0 || winding == 1, "({}, {})", x, y);
+            filled[y * width + x] = winding != 0;
+        }
+    }
+    filled
+}
+
+/// The modules of the code with its quiet zone, row by row.
+fn modules(gen: &Generator, code: &QrCode) -> (Vec<bool>, usize) {
+    let margin = gen.margin(code) as usize;
+    let width = code.width() + 2 * margin;
+    let colors = code.to_colors();
+    let dark = (0..width * width)
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            (margin..width - margin).contains(&x)
+                && (margin..width - margin).contains(&y)
+                && colors[(y - margin) * code.width() + x - margin]
+                    == qrcode::Color::Dark
+        })
+        .collect();
+    (dark, width)
+}
+
+#[test]
+fn path_fills_exactly_the_dark_modules() {
+    let gen = Generator {
+        format: Format::Svg,
+        ..Default::default()
+    };
+    for input in INPUTS {
+        let code = gen.code(input).unwrap();
+        let (dark_modules, width) = modules(&gen, &code);
+        let svg = String::from_utf8(gen.generate(input).unwrap()).unwrap();
+        let filled = fill(attribute(&svg, "<path", "d").unwrap(), width);
+        assert_eq!(filled, dark_modules);
+
+        // An outline around each dark area and each hole, also where they
+        // touch another only at a corner.
+        let d = attribute(&svg, "<path", "d").unwrap();
+        let areas = |dark| areas(&dark_modules, width, dark);
+        let outlines = outlines(d);
+        let clockwise = outlines.iter().filter(|o| area(o) > 0).count();
+        assert_eq!(clockwise, areas(true));
+        assert_eq!(outlines.len() - clockwise, areas(false));
+
+        let at = |x: usize, y: usize| dark_modules[y * width + x];
+        let diagonal = (0..width - 1).any(|y| {
+            (0..width - 1).any(|x| {
+                at(x, y) == at(x + 1, y + 1)
+                    && at(x + 1, y) == at(x, y + 1)
+                    && at(x, y) != at(x + 1, y)
+            })
+        });
+        assert!(diagonal);
+    }
+}
+
+#[test]
+fn finder_rings_keep_their_holes() {
+    let gen = Generator {
+        format: Format::Svg,
+        margin: Some(0),
+        ..Default::default()
+    };
+    let svg = String::from_utf8(gen.generate(b"1").unwrap()).unwrap();
+    let filled = fill(attribute(&svg, "<path", "d").unwrap(), 21);
+    let covers = |x: usize, y: usize| filled[y * 21 + x];
+
+    // The ring of the top left finder, its light ring and its dot.
+    for i in 0..7 {
+        assert!(covers(i, 0) && covers(0, i));
+        assert!(covers(i, 6) && covers(6, i));
+    }
+    for i in 1..6 {
+        assert!(!covers(i, 1) && !covers(1, i));
+        assert!(!covers(i, 5) && !covers(5, i));
+    }
+    for (x, y) in [(2, 2), (3, 3), (4, 4), (2, 4), (4, 2)] {
+        assert!(covers(x, y));
+    }
+}
+
+#[test]
+fn inverted_output_swaps_the_fills() {
+    let colors = Generator {
+        format: Format::Svg,
+        dark_color: Some("#123456".parse::<Color>().unwrap()),
+        light_color: Some("#fedcba".parse::<Color>().unwrap()),
+        ..Default::default()
+    };
+    let inverted = Generator {
+        invert: Some(true),
+        ..colors.clone()
+    };
+    let input = b"https://qrcode.show";
+    let plain = String::from_utf8(colors.generate(input).unwrap()).unwrap();
+    let svg = String::from_utf8(inverted.generate(input).unwrap()).unwrap();
+
+    let fills = |svg: &str| {
+        (
+            attribute(svg, "<rect", "fill").unwrap().to_string(),
+            attribute(svg, "<path", "fill").unwrap().to_string(),
+        )
+    };
+    let (background, foreground) = fills(&plain);
+    assert_eq!(fills(&svg), (foreground, background));
+
+    // The same modules are traced, the light ones now drawn dark.
+    assert_eq!(
+        attribute(&svg, "<path", "d"),
+        attribute(&plain, "<path", "d")
+    );
+}
+
+#[test]
+fn outlines_are_smaller_than_a_square_per_module() {
+    let gen = Generator {
+        format: Format::Svg,
+        module_size: Some(8),
+        ..Default::default()
+    };
+    let input = &[b'x'; 2300];
+    let code = gen.code(input).unwrap();
+    assert_eq!(code.width(), 177);
+
+    let traced = gen.generate(input).unwrap();
+    let squares = code
+        .render::<svg::Color>()
+        .quiet_zone(true)
+        .module_dimensions(8, 8)
+        .build();
+    assert!(
+        traced.len() * 2 < squares.len(),
+        "{} against {} bytes",
+        traced.len(),
+        squares.len()
+    );
+}
+
+#[test]
+fn scalable_output_drops_only_the_root_size() {
+    let input = b"https://qrcode.show";
+    let cases = [
+        Generator::default(),
+        Generator {
+            shape: Some("circle".parse().unwrap()),
+            ..Default::default()
+        },
+        Generator {
+            frame: Some(Frame::Banner),
+            rotation: Some(Rotation::Quarter),
+            alt_text: Some(r#"Say "hi" > 3"#.into()),
+            ..Default::default()
+        },
+    ];
+    for gen in &cases {
+        let gen = Generator {
+            format: Format::Svg,
+            ..gen.clone()
+        };
+        let fixed = String::from_utf8(gen.generate(input).unwrap()).unwrap();
+        let gen = Generator {
+            scalable: Some(true),
+            ..gen
+        };
+        let svg = String::from_utf8(gen.generate(input).unwrap()).unwrap();
+
+        assert_eq!(attribute(&svg, "<svg", "width"), None);
+        assert_eq!(attribute(&svg, "<svg", "height"), None);
+        assert_eq!(
+            attribute(&svg, "<svg", "viewBox"),
+            attribute(&fixed, "<svg", "viewBox")
+        );
+        assert_eq!(
+            attribute(&svg, "<svg", "xmlns"),
+            Some("http://www.w3.org/2000/svg")
+        );
+
+        // Everything but the root keeps its size.
+        let root = |svg: &str| {
+            let start = svg.find("<svg").unwrap();
+            start + svg[start..].find('>').unwrap() + 1
+        };
+        assert_eq!(&svg[root(&svg)..], &fixed[root(&fixed)..]);
+    }
+}
+
+#[test]
+fn rotation_swaps_the_size_of_framed_output() {
+    let gen = Generator {
+        format: Format::Svg,
+        frame: Some(Frame::Banner),
+        module_size: Some(4),
+        ..Default::default()
+    };
+    let upright = String::from_utf8(gen.generate(b"1").unwrap()).unwrap();
+    let (width, height) = (
+        attribute(&upright, "<svg", "width").unwrap(),
+        attribute(&upright, "<svg", "height").unwrap(),
+    );
+    assert_ne!(width, height);
+
+    let gen = Generator {
+        rotation: Some(Rotation::Quarter),
+        ..gen
+    };
+    let svg = String::from_utf8(gen.generate(b"1").unwrap()).unwrap();
+    assert_eq!(attribute(&svg, "<svg", "width"), Some(height));
+    assert_eq!(attribute(&svg, "<svg", "height"), Some(width));
+    assert_eq!(
+        attribute(&svg, "<g", "transform"),
+        Some(format!("translate({} 0) rotate(90)", height).as_str())
+    );
+
+    // The original is nested at its own size.
+    let nested = &svg[svg.find("<g").unwrap()..];
+    assert_eq!(attribute(nested, "<svg", "x"), Some("0"));
+    assert_eq!(attribute(nested, "<svg", "width"), Some(width));
+    assert_eq!(attribute(nested, "<svg", "height"), Some(height));
+}
+
+#[test]
+fn sheets_nest_codes_on_their_labels() {
+    let sheet: Sheet = "a4:2x2".parse().unwrap();
+    let rows = libs::parse_rows(Some("text/csv"), b"data\n1\n2\n").unwrap();
+    let mut batch =
+        libs::Batch::new(libs::BatchOutput::Svg, None, Some(sheet), &rows);
+    for row in &rows {
+        batch.add(row, &Generator::default()).unwrap();
+    }
+    let svg = String::from_utf8(batch.finish().1).unwrap();
+
+    // Two square codes, each as wide as half the page.
+    let codes: Vec<&str> = svg
+        .match_indices("<svg x=")
+        .map(|(i, _)| &svg[i..])
+        .collect();
+    assert_eq!(codes.len(), 2);
+    let number = |code: &str, name| -> f64 {
+        attribute(code, "<svg", name).unwrap().parse().unwrap()
+    };
+    let (page_width, page_height) = (595.2756, 841.8898);
+    for (i, code) in codes.iter().enumerate() {
+        let size = number(code, "width");
+        assert!((size - page_width / 2.0).abs() < 0.01, "{}", size);
+        assert_eq!(number(code, "height"), size);
+        assert!((number(code, "x") - i as f64 * page_width / 2.0).abs() < 0.01);
+        let top = (page_height / 2.0 - size) / 2.0;
+        assert!((number(code, "y") - top).abs() < 0.01);
+        assert!(attribute(code, "<svg", "viewBox").is_some());
+    }
+}
+
+#[test]
+fn accessible_output_is_labelled_and_escaped() {
+    let svg = |gen: Generator, input: &[u8]| {
+        let gen = Generator {
+            format: Format::Svg,
+            ..gen
+        };
+        String::from_utf8(gen.generate(input).unwrap()).unwrap()
+    };
+    let element = |svg: &str, name: &str| {
+        let open = format!("<{}>", name);
+        let start = svg.find(&open).unwrap() + open.len();
+        let end = start + svg[start..].find('<').unwrap();
+        svg[start..end].to_string()
+    };
+
+    let input = br#"<a href="x">Tom & Jerry</a>"#;
+    let labelled = svg(
+        Generator {
+            accessible: Some(true),
+            ..Default::default()
+        },
+        input,
+    );
+    let label =
+        "QR code: &lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&lt;/a&gt;";
+    assert_eq!(attribute(&labelled, "<svg", "role"), Some("img"));
+    assert_eq!(attribute(&labelled, "<svg", "aria-label"), Some(label));
+    assert_eq!(element(&labelled, "title"), label);
+    assert_eq!(
+        element(&labelled, "desc"),
+        "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&lt;/a&gt;"
+    );
+
+    // Alt text replaces the payload in the label.
+    let labelled = svg(
+        Generator {
+            alt_text: Some(r#"Menu "today" <1 & 2>"#.into()),
+            ..Default::default()
+        },
+        input,
+    );
+    let label = "Menu &quot;today&quot; &lt;1 &amp; 2&gt;";
+    assert_eq!(attribute(&labelled, "<svg", "aria-label"), Some(label));
+    assert_eq!(element(&labelled, "title"), label);
+
+    // Long payloads are cut short in the label only.
+    let input = "x".repeat(300);
+    let labelled = svg(
+        Generator {
+            accessible: Some(true),
+            ..Default::default()
+        },
+        input.as_bytes(),
+    );
+    let label = attribute(&labelled, "<svg", "aria-label").unwrap();
+    assert_eq!(label.chars().count(), 100);
+    assert!(label.ends_with('…'));
+    assert_eq!(element(&labelled, "desc"), input);
+
+    // Unlabelled unless asked for, except on the HTML page.
+    let plain = svg(Generator::default(), b"hi");
+    assert_eq!(attribute(&plain, "<svg", "aria-label"), None);
+    assert!(!plain.contains("<title>"));
+    let gen = Generator {
+        format: Format::Html,
+        ..Default::default()
+    };
+    let html = String::from_utf8(gen.generate(b"hi").unwrap()).unwrap();
+    assert_eq!(attribute(&html, "<svg", "aria-label"), Some("QR code: hi"));
+}
@@ -0,0 +1,123 @@
+use libs::Fields;
+use libs::Template;
+use libs::TemplateError;
+use libs::MAX_CODES;
+
+fn expand(query: &str) -> Result<Vec<(String, String)>, TemplateError> {
+    Template::expand(&Fields::from_query(query))
+}
+
+fn inputs(template: &str) -> Vec<String> {
+    let template: Template = template.parse().unwrap();
+    template
+        .render(&Fields::from_query(""))
+        .unwrap()
+        .into_iter()
+        .map(|(_, input)| input)
+        .collect()
+}
+
+#[test]
+fn sequences_count_up() {
+    let codes = expand("template=https://ex.com/item/{{seq:1..3}}").unwrap();
+    assert_eq!(
+        codes,
+        [
+            ("1".to_string(), "https://ex.com/item/1".to_string()),
+            ("2".to_string(), "https://ex.com/item/2".to_string()),
+            ("3".to_string(), "https://ex.com/item/3".to_string()),
+        ]
+    );
+
+    // A sequence of one.
+    assert_eq!(inputs("n{{seq:7..7}}"), ["n7"]);
+}
+
+#[test]
+fn sequences_keep_the_width_of_their_start() {
+    assert_eq!(
+        inputs("{{seq:0098..0101}}"),
+        ["0098", "0099", "0100", "0101"]
+    );
+    assert_eq!(inputs("{{ seq:08..11 }}"), ["08", "09", "10", "11"]);
+
+    // Numbers wider than the start aren't cut.
+    assert_eq!(inputs("{{seq:9..10}}"), ["9", "10"]);
+}
+
+#[test]
+fn sequences_refuse_descending_and_empty_ranges() {
+    for range in ["5..1", "..", "1..", "..5", "1-5", "a..b", "-1..5", "1...5"] {
+        let template = format!("{{{{seq:{}}}}}", range);
+        assert!(
+            matches!(
+                template.parse::<Template>(),
+                Err(TemplateError::Sequence(_))
+            ),
+            "{}",
+            range
+        );
+    }
+}
+
+#[test]
+fn placeholders_fill_in_together() {
+    let codes = expand(
+        "template={{base}}/{{seq:01..02}}?batch={{id}}%26n={{seq:11..12}}\
+         &base=https://ex.com&id=b7",
+    )
+    .unwrap();
+    assert_eq!(
+        codes,
+        [
+            (
+                "01".to_string(),
+                "https://ex.com/01?batch=b7&n=11".to_string()
+            ),
+            (
+                "02".to_string(),
+                "https://ex.com/02?batch=b7&n=12".to_string()
+            ),
+        ]
+    );
+
+    // Without a sequence, one code named 1.
+    let codes = expand("template=hi {{name}}!&name=you").unwrap();
+    assert_eq!(codes, [("1".to_string(), "hi you!".to_string())]);
+
+    assert_eq!(
+        expand("template={{name}}"),
+        Err(TemplateError::Missing("name".into()))
+    );
+    assert_eq!(expand("name=you"), Err(TemplateError::NoTemplate));
+    assert_eq!(
+        "{{seq:1..2}}{{seq:1..3}}".parse::<Template>(),
+        Err(TemplateError::Lengths)
+    );
+    assert_eq!(
+        "ab{{id".parse::<Template>(),
+        Err(TemplateError::Unclosed(2))
+    );
+    assert!(matches!(
+        "{{a b}}".parse::<Template>(),
+        Err(TemplateError::Placeholder(_))
+    ));
+}
+
+#[test]
+fn templates_make_at_most_max_codes() {
+    let template: Template = "{{seq:0001..1000}}".parse().unwrap();
+    assert_eq!(template.count(), Ok(MAX_CODES));
+    let codes = template.render(&Fields::from_query("")).unwrap();
+    assert_eq!(codes.len(), MAX_CODES);
+    assert_eq!(codes[999].1, "1000");
+
+    assert_eq!(
+        "{{seq:0001..1001}}".parse::<Template>(),
+        Err(TemplateError::TooMany(1001))
+    );
+    assert_eq!(
+        "{{seq:0..999999999999999999}}".parse::<Template>(),
+        Err(TemplateError::TooMany(1_000_000_000_000_000_000))
+    );
+}
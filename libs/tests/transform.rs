@@ -0,0 +1,106 @@
+use image::Rgba;
+use image::RgbaImage;
+use libs::Format;
+use libs::Frame;
+use libs::Generator;
+use libs::Rotation;
+use libs::Transform;
+
+const INPUT: &[u8] = b"https://qrcode.show";
+
+fn rotate(rotation: Rotation) -> Transform {
+    Transform {
+        rotation,
+        mirror: false,
+    }
+}
+
+const MIRROR: Transform = Transform {
+    rotation: Rotation::None,
+    mirror: true,
+};
+
+/// An image without symmetries, each pixel telling where it came from.
+fn image() -> RgbaImage {
+    RgbaImage::from_fn(5, 3, |x, y| Rgba([x as u8, y as u8, 0, 255]))
+}
+
+fn png(gen: &Generator) -> RgbaImage {
+    let png = gen.generate(INPUT).unwrap();
+    image::load_from_memory(&png).unwrap().to_rgba8()
+}
+
+#[test]
+fn modules_turn_clockwise() {
+    let modules = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+    assert_eq!(
+        rotate(Rotation::Quarter).modules(&modules, 3),
+        [7, 4, 1, 8, 5, 2, 9, 6, 3]
+    );
+    assert_eq!(
+        rotate(Rotation::Half).modules(&modules, 3),
+        [9, 8, 7, 6, 5, 4, 3, 2, 1]
+    );
+    assert_eq!(
+        rotate(Rotation::ThreeQuarters).modules(&modules, 3),
+        [3, 6, 9, 2, 5, 8, 1, 4, 7]
+    );
+    assert_eq!(MIRROR.modules(&modules, 3), [3, 2, 1, 6, 5, 4, 9, 8, 7]);
+}
+
+#[test]
+fn four_quarter_turns_and_two_mirrors_change_nothing() {
+    let modules: Vec<u32> = (0..49).collect();
+    let mut turned = modules.clone();
+    for _ in 0..4 {
+        turned = rotate(Rotation::Quarter).modules(&turned, 7);
+    }
+    assert_eq!(turned, modules);
+    assert_eq!(MIRROR.modules(&MIRROR.modules(&modules, 7), 7), modules);
+
+    let mut turned = image();
+    for _ in 0..4 {
+        turned = rotate(Rotation::Quarter).raster(turned);
+    }
+    assert_eq!(turned, image());
+    assert_eq!(MIRROR.raster(MIRROR.raster(image())), image());
+
+    // The same for whole codes, frame and all.
+    let gen = Generator {
+        format: Format::Png,
+        module_size: Some(4),
+        frame: Some(Frame::Banner),
+        ..Default::default()
+    };
+    let mut turned = png(&gen);
+    for _ in 0..4 {
+        turned = rotate(Rotation::Quarter).raster(turned);
+    }
+    assert_eq!(turned, png(&gen));
+}
+
+#[test]
+fn generated_codes_are_transformed() {
+    let gen = Generator {
+        format: Format::Png,
+        module_size: Some(4),
+        ..Default::default()
+    };
+    for rotation in [Rotation::Quarter, Rotation::Half, Rotation::ThreeQuarters]
+    {
+        for mirror in [false, true] {
+            let transform = Transform { rotation, mirror };
+            let transformed = Generator {
+                rotation: Some(rotation),
+                mirror: Some(mirror),
+                ..gen.clone()
+            };
+            assert_eq!(
+                png(&transformed),
+                transform.raster(png(&gen)),
+                "{:?}",
+                transform
+            );
+        }
+    }
+}